@@ -1,4 +1,7 @@
-use std::{env, path::PathBuf};
+use std::{env, fs, path::PathBuf};
+
+/// Symbols only found in newer SDK releases, with the name of the cfg emitted when present.
+const PROBES: &[(&str, &str)] = &[("RfcGetInt8", "int8"), ("RFCTYPE_UTCLONG", "utclong")];
 
 fn main() {
     let sdk_home = PathBuf::from(
@@ -12,6 +15,17 @@ fn main() {
     println!("cargo:rustc-link-lib=dylib=sapucum");
     println!("cargo:include={}", include_path.display());
 
+    // Dependent crates receive the detected features as DEP_SAPNWRFC_<NAME> variables
+    let header = fs::read_to_string(include_path.join("sapnwrfc.h"))
+        .expect("Couldn't read the sapnwrfc.h header");
+    for (symbol, name) in PROBES {
+        println!("cargo:rustc-check-cfg=cfg(sapnwrfc_{})", name);
+        if header.contains(symbol) {
+            println!("cargo:rustc-cfg=sapnwrfc_{}", name);
+            println!("cargo:{}=1", name);
+        }
+    }
+
     let dst_path = PathBuf::from(env::var("OUT_DIR").unwrap());
 
     bindgen::builder()
@@ -0,0 +1,144 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Error, FnArg, ItemFn, LitStr, ReturnType};
+
+/// Turn a function into the handler of a function module called on an RFC server.
+///
+/// The function takes at most one input, read from the parameters of the call with the
+/// `FromFunction` trait, and returns a result whose value is written back to the parameters with
+/// the `IntoFunction` trait. An error is returned to the caller, as an ABAP exception when it has
+/// a key.
+///
+/// A module with the name of the function is generated next to it, holding a `register`
+/// function to install the handler on a server.
+///
+/// ```no_run
+/// use saprfc::{
+///     rfc_handler,
+///     server::{FromFunction, IntoFunction},
+///     RfcConnection, RfcError, RfcFunction, RfcServer,
+/// };
+///
+/// struct Request(String);
+///
+/// impl FromFunction for Request {
+///     fn from_function(func: &RfcFunction) -> Result<Self, RfcError> {
+///         Ok(Self(func.get_string("REQUTEXT")?))
+///     }
+/// }
+///
+/// struct Response(String);
+///
+/// impl IntoFunction for Response {
+///     fn into_function(self, func: &mut RfcFunction) -> Result<(), RfcError> {
+///         func.set_string("ECHOTEXT", &self.0)
+///     }
+/// }
+///
+/// #[rfc_handler("STFC_CONNECTION")]
+/// fn connection(request: Request) -> Result<Response, RfcError> {
+///     Ok(Response(request.0))
+/// }
+///
+/// let repository = RfcConnection::for_dest("TEST").unwrap();
+/// let server = RfcServer::builder().set_param("dest", "TEST_SERVER").build().unwrap();
+/// connection::register(&server, &repository).unwrap();
+/// ```
+///
+/// Handlers cannot be async, generic or take more than one input, they must return a result and
+/// the function module name is required. These mistakes are reported on the handler at compile
+/// time, see the UI tests of this crate.
+#[proc_macro_attribute]
+pub fn rfc_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr2: TokenStream2 = attr.clone().into();
+    if attr2.is_empty() {
+        return Error::new(
+            attr2.span(),
+            "expected the function module name, like #[rfc_handler(\"Z_MY_FM\")]",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let name = parse_macro_input!(attr as LitStr);
+    let func = parse_macro_input!(item as ItemFn);
+    expand(name, func)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn expand(name: LitStr, func: ItemFn) -> syn::Result<TokenStream2> {
+    let sig = &func.sig;
+    if name.value().trim().is_empty() {
+        return Err(Error::new(name.span(), "the function module name is empty"));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(Error::new(asyncness.span(), "RFC handlers cannot be async"));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "RFC handlers cannot be generic",
+        ));
+    }
+    if let ReturnType::Default = sig.output {
+        return Err(Error::new_spanned(
+            sig,
+            "RFC handlers must return a Result of the output and an RfcError",
+        ));
+    }
+    let input_type = match sig.inputs.len() {
+        0 => None,
+        1 => match &sig.inputs[0] {
+            FnArg::Typed(arg) => Some(&arg.ty),
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(receiver.span(), "RFC handlers cannot take self"))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &sig.inputs,
+                "RFC handlers take at most one input",
+            ))
+        }
+    };
+
+    let ident = &sig.ident;
+    let vis = &func.vis;
+    let doc = format!(
+        "Registration of [`{}`] as the `{}` handler.",
+        ident,
+        name.value()
+    );
+    let call = match input_type {
+        Some(input_type) => quote! {
+            let input = <#input_type as ::saprfc::server::FromFunction>::from_function(func)?;
+            let output = super::#ident(input)?;
+        },
+        None => quote! {
+            let output = super::#ident()?;
+        },
+    };
+
+    Ok(quote! {
+        #func
+
+        #[doc = #doc]
+        #vis mod #ident {
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// Install the handler on a server, looking up the function description with the
+            /// repository connection.
+            pub fn register(
+                server: &::saprfc::RfcServer,
+                repository: &::saprfc::RfcConnection,
+            ) -> ::saprfc::error::Result<()> {
+                server.install_function(repository, #name, |_conn, func| {
+                    #call
+                    ::saprfc::server::IntoFunction::into_function(output, func)
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,8 @@
+use saprfc::rfc_handler;
+
+#[rfc_handler(" ")]
+fn connection() -> Result<(), saprfc::RfcError> {
+    Ok(())
+}
+
+fn main() {}
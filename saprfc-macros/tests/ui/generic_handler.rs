@@ -0,0 +1,8 @@
+use saprfc::rfc_handler;
+
+#[rfc_handler("STFC_CONNECTION")]
+fn connection<T>() -> Result<(), saprfc::RfcError> {
+    Ok(())
+}
+
+fn main() {}
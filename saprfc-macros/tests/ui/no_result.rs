@@ -0,0 +1,6 @@
+use saprfc::rfc_handler;
+
+#[rfc_handler("STFC_CONNECTION")]
+fn connection() {}
+
+fn main() {}
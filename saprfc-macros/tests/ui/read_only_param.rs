@@ -0,0 +1,8 @@
+// Parameters obtained as read-only have no setters
+fn fill(func: &saprfc::RfcFunction) -> saprfc::error::Result<()> {
+    let mut param = func.export_parameter("ECHOSTRUCT")?;
+    param.set_string("value")?;
+    Ok(())
+}
+
+fn main() {}
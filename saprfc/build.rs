@@ -0,0 +1,13 @@
+use std::env;
+
+/// The optional SDK features detected by the `sapnwrfc-sys` build script.
+const SDK_FEATURES: &[&str] = &["int8", "utclong"];
+
+fn main() {
+    for name in SDK_FEATURES {
+        println!("cargo:rustc-check-cfg=cfg(sapnwrfc_{})", name);
+        if env::var_os(format!("DEP_SAPNWRFC_{}", name.to_uppercase())).is_some() {
+            println!("cargo:rustc-cfg=sapnwrfc_{}", name);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+#[cfg(feature = "rate-limit")]
+use crate::limit::RateLimiter;
+use crate::{
+    connection::{RfcConnection, RfcConnectionBuilder},
+    error::{Result, RfcError},
+    function::RfcFunction,
+};
+use sapnwrfc_sys::_RFC_RC;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    thread,
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// The default number of requests allowed to wait for the worker.
+pub const DEFAULT_QUEUE_SIZE: usize = 32;
+
+type Job = Box<dyn FnOnce(&RfcConnection) + Send>;
+
+enum Command {
+    Run(Job),
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// An RFC connection owned by a dedicated worker thread.
+///
+/// All the requests are processed in order on the same thread, which keeps the backend session
+/// and avoids tying up a blocking thread pool with long calls.
+pub struct ActorConnection;
+
+impl ActorConnection {
+    /// Open a connection on a new worker thread with the default queue size.
+    pub async fn spawn(builder: RfcConnectionBuilder) -> Result<ActorHandle> {
+        Self::spawn_bounded(builder, DEFAULT_QUEUE_SIZE).await
+    }
+
+    /// Open a connection on a new worker thread.
+    ///
+    /// At most `queue_size` requests can be waiting for the worker, further requests wait for
+    /// room in the queue before being sent. The queue must have room for at least one request.
+    ///
+    /// The logon runs on the worker thread, the returned future completes once it is done.
+    pub async fn spawn_bounded(
+        builder: RfcConnectionBuilder,
+        queue_size: usize,
+    ) -> Result<ActorHandle> {
+        if queue_size == 0 {
+            return Err(RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_PARAMETER,
+                "The queue size of a connection worker cannot be zero",
+            ));
+        }
+        let (tx, rx) = mpsc::channel(queue_size);
+        let (open_tx, open_rx) = oneshot::channel();
+        thread::Builder::new()
+            .name("rfc-connection".to_owned())
+            .spawn(move || match builder.build() {
+                Ok(conn) => {
+                    let _ = open_tx.send(Ok(()));
+                    run(conn, rx);
+                }
+                Err(err) => {
+                    let _ = open_tx.send(Err(err));
+                }
+            })
+            .map_err(|err| RfcError::custom(&err.to_string()))?;
+        open_rx.await.map_err(|_| terminated())??;
+        Ok(ActorHandle {
+            tx,
+            #[cfg(feature = "rate-limit")]
+            limiter: None,
+        })
+    }
+}
+
+fn run(conn: RfcConnection, mut rx: mpsc::Receiver<Command>) {
+    while let Some(cmd) = rx.blocking_recv() {
+        match cmd {
+            Command::Run(job) => {
+                if panic::catch_unwind(AssertUnwindSafe(|| job(&conn))).is_err() {
+                    // Dropping the receiver fails all the pending requests
+                    log::error!("Connection worker panicked, failing pending requests");
+                    return;
+                }
+            }
+            Command::Shutdown(ack) => {
+                drop(conn);
+                let _ = ack.send(());
+                return;
+            }
+        }
+    }
+}
+
+fn terminated() -> RfcError {
+    RfcError::custom("Connection worker has terminated")
+}
+
+/// A handle to send requests to a connection worker.
+#[derive(Clone)]
+pub struct ActorHandle {
+    tx: mpsc::Sender<Command>,
+    #[cfg(feature = "rate-limit")]
+    limiter: Option<RateLimiter>,
+}
+
+impl ActorHandle {
+    /// Throttle the function calls made with [`call`](Self::call) and the closures run with
+    /// [`interact`](Self::interact).
+    #[cfg(feature = "rate-limit")]
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Run a closure with the connection on the worker thread.
+    ///
+    /// The closure first waits for the rate limiter, if any, and counts as a single call.
+    pub async fn interact<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&RfcConnection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        #[cfg(feature = "rate-limit")]
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+        let (res_tx, res_rx) = oneshot::channel();
+        let job: Job = Box::new(move |conn| {
+            let _ = res_tx.send(f(conn));
+        });
+        self.tx
+            .send(Command::Run(job))
+            .await
+            .map_err(|_| terminated())?;
+        res_rx.await.map_err(|_| terminated())?
+    }
+
+    /// Call a function module on the worker thread.
+    ///
+    /// The `setup` closure provides the parameters before the invocation and the `extract`
+    /// closure reads the results after.
+    pub async fn call<S, E, T>(&self, name: &str, setup: S, extract: E) -> Result<T>
+    where
+        S: FnOnce(&mut RfcFunction) -> Result<()> + Send + 'static,
+        E: FnOnce(&RfcFunction) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let name = name.to_owned();
+        self.interact(move |conn| {
+            let mut func = conn.get_function(&name)?;
+            setup(&mut func)?;
+            func.invoke()?;
+            extract(&func)
+        })
+        .await
+    }
+
+    /// Check if the connection is alive by sending an RFC ping.
+    pub async fn ping(&self) -> Result<()> {
+        self.interact(|conn| conn.ping()).await
+    }
+
+    /// Close the connection and stop the worker once the previously queued requests are done.
+    pub async fn shutdown(&self) -> Result<()> {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        self.tx
+            .send(Command::Shutdown(ack_tx))
+            .await
+            .map_err(|_| terminated())?;
+        ack_rx.await.map_err(|_| terminated())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn echo(
+        handle: &ActorHandle,
+        value: String,
+    ) -> impl std::future::Future<Output = Result<String>> + '_ {
+        handle.call(
+            "SCP_STRING_ECHO",
+            move |func| func.set_string("IMP", &value),
+            |func| func.get_string("EXP"),
+        )
+    }
+
+    #[tokio::test]
+    async fn empty_queue_test() {
+        let err = ActorConnection::spawn_bounded(RfcConnection::builder(), 0)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), crate::error::RfcErrorCode::InvalidParameter);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn actor_test() {
+        let handle = ActorConnection::spawn(RfcConnection::builder().set_param("dest", "TEST"))
+            .await
+            .unwrap();
+
+        handle.ping().await.unwrap();
+
+        // Requests are processed in the order they are queued
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let push = |i| {
+            let order = order.clone();
+            handle.interact(move |_| {
+                order.lock().unwrap().push(i);
+                Ok(())
+            })
+        };
+        let (a, b, c) = tokio::join!(push(0), push(1), push(2));
+        a.and(b).and(c).unwrap();
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+
+        // Concurrent calls from several tasks
+        let tasks: Vec<_> = (0..4)
+            .map(|i| {
+                let handle = handle.clone();
+                tokio::spawn(async move { echo(&handle, format!("Task {}", i)).await })
+            })
+            .collect();
+        for (i, task) in tasks.into_iter().enumerate() {
+            assert_eq!(task.await.unwrap().unwrap(), format!("Task {}", i));
+        }
+
+        handle.shutdown().await.unwrap();
+        assert!(handle.ping().await.is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Run a diagnostic suite against a destination and report on each step.
+//!
+//! The destination is given by name, as found in the `sapnwrfc.ini` file, or as connection
+//! parameters like `ashost=host sysnr=00 client=100 user=USER passwd=secret`.
+
+use saprfc::{
+    doctor::{self, DoctorOptions},
+    RfcConnection,
+};
+use std::{env, process, time::Duration};
+
+const USAGE: &str = "\
+Usage: nwrfc-doctor [OPTIONS] <DEST | KEY=VALUE...>
+
+Options:
+    --json              Print the report as JSON
+    --pings <N>         Number of pings sent to measure the latency [default: 5]
+    --function <NAME>   Function module whose metadata fetch is timed, can be repeated
+    --timeout <SECS>    Time allowed to each call [default: 30]
+    -h, --help          Print this help";
+
+fn main() {
+    let mut json = false;
+    let mut opts = DoctorOptions::new();
+    let mut functions = Vec::new();
+    let mut builder = RfcConnection::builder();
+    let mut has_target = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--pings" => opts = opts.set_ping_samples(parse_value(&arg, args.next())),
+            "--function" => functions.push(value(&arg, args.next())),
+            "--timeout" => {
+                let secs = parse_value(&arg, args.next());
+                opts = opts.set_call_timeout(Duration::from_secs(secs));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => fail(&format!("Unknown option {}", arg)),
+            _ => {
+                builder = match arg.split_once('=') {
+                    Some((key, value)) => builder.set_param(key, value),
+                    None => builder.set_param("dest", &arg),
+                };
+                has_target = true;
+            }
+        }
+    }
+    if !has_target {
+        fail("Missing the destination");
+    }
+    if !functions.is_empty() {
+        opts = opts.set_functions(functions);
+    }
+
+    let report = doctor::run(builder, &opts);
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        println!("{}", report);
+    }
+    process::exit(report.exit_code());
+}
+
+fn value(option: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| fail(&format!("Missing the value of {}", option)))
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: Option<String>) -> T {
+    let value = self::value(option, value);
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("Invalid value {:?} for {}", value, option)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(64);
+}
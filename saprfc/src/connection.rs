@@ -1,24 +1,60 @@
 use crate::{
-    error::{Result, RfcErrorInfo},
-    function::RfcFunction,
+    error::{GetFunctionError, Result, RfcError, RfcErrorInfo},
+    events::{self, ConnectionEvent, ConnectionEventKind},
+    function::{OwnedRfcFunction, RfcFunction, RfcFunctionDesc},
+    ini,
     macros::{check_rc_ok, is_rc_err},
-    uc,
+    sdk::{self, TraceLevel},
+    session::StatefulSession,
+    throughput::RfcThroughput,
+    transaction::RfcTransaction,
+    types::RfcTypeDesc,
+    uc::{self, UcString},
+    value::{self, Value},
 };
 use sapnwrfc_sys::{
-    self, RfcCloseConnection, RfcCreateFunction, RfcGetFunctionDesc, RfcOpenConnection, RfcPing,
-    SAP_UC,
+    self, RfcCancel, RfcCloseConnection, RfcCreateFunction, RfcGetConnectionAttributes,
+    RfcGetFunctionDesc, RfcGetPartnerSSOTicket, RfcGetTypeDesc, RfcIsConnectionHandleValid,
+    RfcOpenConnection, RfcPing, RfcRemoveFunctionDesc, RfcReopenConnection, RfcResetServerContext,
+    RfcSetTraceLevel, RFC_ATTRIBUTES, RFC_FUNCTION_DESC_HANDLE, RFC_FUNCTION_HANDLE, _RFC_RC,
 };
-use std::{collections::HashMap, ptr};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    env, fmt, ptr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The deadlines of the operations run with a timeout, all watched by a single thread.
+static WATCHDOG: Watchdog = Watchdog::new();
+
+/// The room first given to a partner SSO ticket, in code units, grown when the ticket is longer.
+const SSO_TICKET_INITIAL_LEN: usize = 4096;
 
 /// An SAP NW RFC connection.
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct RfcConnection {
     handle: sapnwrfc_sys::RFC_CONNECTION_HANDLE,
+    id: u64,
+    dest: String,
+    sys_id: String,
+    broken: AtomicBool,
+    default_call_timeout: Option<Duration>,
+    cancel_target: Arc<Mutex<CancelTarget>>,
+    throughput: RefCell<Option<RfcThroughput>>,
+    /// Whether the handle is closed on drop, instead of by the SDK which lent it.
+    owns_handle: bool,
 }
 
 impl RfcConnection {
-    pub(crate) fn new(params: Vec<(Vec<SAP_UC>, Vec<SAP_UC>)>) -> Result<RfcConnection> {
+    pub(crate) fn new(params: &[(UcString, UcString)]) -> Result<RfcConnection> {
         let conn_params: Vec<_> = params
             .iter()
             .map(|(k, v)| sapnwrfc_sys::RFC_CONNECTION_PARAMETER {
@@ -36,9 +72,38 @@ impl RfcConnection {
             )
         };
         if handle.is_null() {
-            return Err(err_info);
+            return Err(err_info.into());
+        }
+        let mut conn = Self::from_handle(handle);
+        if let Ok(attrs) = conn.attributes() {
+            conn.dest = attrs.dest;
+            conn.sys_id = attrs.sys_id;
+        }
+        conn.emit_event(ConnectionEventKind::Opened, None);
+        Ok(conn)
+    }
+
+    fn from_handle(handle: sapnwrfc_sys::RFC_CONNECTION_HANDLE) -> Self {
+        Self {
+            handle,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            dest: String::new(),
+            sys_id: String::new(),
+            broken: AtomicBool::new(false),
+            default_call_timeout: None,
+            cancel_target: Arc::new(Mutex::new(CancelTarget(handle))),
+            throughput: RefCell::new(None),
+            owns_handle: true,
         }
-        Ok(Self { handle })
+    }
+
+    /// Wrap a connection handle owned by the SDK, like the one of a server call.
+    ///
+    /// The handle is left open on drop, only the fields of the wrapper are released.
+    pub(crate) fn borrowed(handle: sapnwrfc_sys::RFC_CONNECTION_HANDLE) -> Self {
+        let mut conn = Self::from_handle(handle);
+        conn.owns_handle = false;
+        conn
     }
 
     /// Get an empty connection builder to provide parameters for connecting.
@@ -48,57 +113,682 @@ impl RfcConnection {
 
     /// Open a connection to a destination specified in an `sapnwrfc.ini` file.
     ///
-    /// Equivalent to only setting the `dest` parameter in a connection builder.
+    /// Equivalent to only setting the `dest` parameter in a connection builder, except that the
+    /// destination is first looked up in the ini file to report a missing one clearly.
     pub fn for_dest(name: &str) -> Result<RfcConnection> {
-        Self::new(vec![(uc::from_str("dest")?, uc::from_str(name)?)])
+        ini::check_destination(name)?;
+        Self::new(&[(uc::from_str("dest")?, uc::from_str(name)?)])
+    }
+
+    pub(crate) fn handle(&self) -> sapnwrfc_sys::RFC_CONNECTION_HANDLE {
+        self.handle
+    }
+
+    /// Get the id of the connection, unique within the process and reported in its events.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Check if a previous failure left the connection unusable.
+    ///
+    /// Communication failures during a call break the connection, pools discard broken
+    /// connections instead of handing them out again.
+    pub fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::Acquire)
+    }
+
+    /// Check if a communication failure poisoned the connection, so it must not be used again.
+    ///
+    /// This is the same flag as [`is_broken`](Self::is_broken), which pools check before handing
+    /// out a connection. Code keeping its own connections checks it the same way after a failed
+    /// call, and opens a new connection instead.
+    pub fn is_poisoned(&self) -> bool {
+        self.is_broken()
+    }
+
+    /// Open the connection again with the same parameters, typically once it is broken.
+    ///
+    /// The connection keeps its id but gets a new backend session, an open connection is closed
+    /// first.
+    pub fn reopen(&mut self) -> Result<()> {
+        unsafe {
+            check_rc_ok!(RfcReopenConnection(self.handle));
+        }
+        self.broken.store(false, Ordering::Release);
+        if let Ok(attrs) = self.attributes() {
+            self.sys_id = attrs.sys_id;
+        }
+        self.emit_event(ConnectionEventKind::Reconnected, None);
+        Ok(())
+    }
+
+    /// Check that the connection is still open, as far as the SDK knows, without a round trip.
+    ///
+    /// A connection dropped by the backend is only detected on the next call, use
+    /// [`ping`](Self::ping) to make sure.
+    pub fn is_valid(&self) -> bool {
+        let mut valid = 0;
+        let mut err_info = RfcErrorInfo::new();
+        let rc =
+            unsafe { RfcIsConnectionHandleValid(self.handle, &mut valid, err_info.as_mut_ptr()) };
+        rc == _RFC_RC::RFC_OK && valid != 0
+    }
+
+    /// Get the throughput counting the calls made on the connection, if one is attached.
+    pub fn throughput(&self) -> Option<RfcThroughput> {
+        self.throughput.borrow().clone()
+    }
+
+    /// Keep the attached throughput alive as long as the connection.
+    pub(crate) fn set_throughput(&self, throughput: Option<RfcThroughput>) {
+        *self.throughput.borrow_mut() = throughput;
+    }
+
+    /// Get the time allowed to function calls which do not set their own timeout.
+    pub fn default_call_timeout(&self) -> Option<Duration> {
+        self.default_call_timeout
+    }
+
+    /// Set the time allowed to function calls which do not set their own timeout.
+    pub fn set_default_call_timeout(&mut self, timeout: Option<Duration>) {
+        self.default_call_timeout = timeout;
+    }
+
+    /// Flag the connection as broken if the error leaves it unusable.
+    pub(crate) fn track_error(&self, err_info: RfcErrorInfo) -> RfcErrorInfo {
+        if err_info.breaks_connection() {
+            self.mark_broken(&err_info);
+        }
+        err_info
+    }
+
+    fn mark_broken(&self, err_info: &RfcErrorInfo) {
+        // The flag is set from the thread running the call and read from the pool
+        if !self.broken.swap(true, Ordering::AcqRel) {
+            self.emit_event(ConnectionEventKind::Broken, Some(err_info.clone()));
+        }
+    }
+
+    /// Send an event about the connection to the listeners of the process.
+    pub(crate) fn emit_event(&self, kind: ConnectionEventKind, error: Option<RfcErrorInfo>) {
+        events::emit(ConnectionEvent {
+            connection_id: self.id,
+            dest: self.dest.clone(),
+            sys_id: self.sys_id.clone(),
+            kind,
+            error,
+        });
     }
 
     /// Check if the connection is alive by sending an RFC ping.
     pub fn ping(&self) -> Result<()> {
-        unsafe {
-            check_rc_ok!(RfcPing(self.handle));
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe { RfcPing(self.handle, err_info.as_mut_ptr()) }) {
+            return Err(self.track_error(err_info).into());
+        }
+        Ok(())
+    }
+
+    /// Set the trace level of this connection only.
+    pub fn set_trace_level(&self, level: TraceLevel) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe {
+            RfcSetTraceLevel(
+                self.handle,
+                ptr::null(),
+                level.as_u32(),
+                err_info.as_mut_ptr(),
+            )
+        }) {
+            return Err(self.track_error(err_info).into());
         }
         Ok(())
     }
 
+    /// Get the attributes of the connection, like the system and user it is logged on to.
+    pub fn attributes(&self) -> Result<ConnectionAttributes> {
+        let mut attrs = RFC_ATTRIBUTES::default();
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe {
+            RfcGetConnectionAttributes(self.handle, &mut attrs, err_info.as_mut_ptr())
+        }) {
+            return Err(self.track_error(err_info).into());
+        }
+        Ok(ConnectionAttributes {
+            dest: uc::to_string_truncate(&attrs.dest)?,
+            host: uc::to_string_truncate(&attrs.host)?,
+            partner_host: uc::to_string_truncate(&attrs.partnerHost)?,
+            sys_number: uc::to_string_truncate(&attrs.sysNumber)?,
+            sys_id: uc::to_string_truncate(&attrs.sysId)?,
+            client: uc::to_string_truncate(&attrs.client)?,
+            user: uc::to_string_truncate(&attrs.user)?,
+            language: uc::to_string_truncate(&attrs.language)?,
+            iso_language: uc::to_string_truncate(&attrs.isoLanguage)?,
+            codepage: uc::to_string_truncate(&attrs.codepage)?,
+            partner_codepage: uc::to_string_truncate(&attrs.partnerCodepage)?,
+            rfc_role: uc::to_string_truncate(&attrs.rfcRole)?,
+            rel: uc::to_string_truncate(&attrs.rel)?,
+            partner_rel: uc::to_string_truncate(&attrs.partnerRel)?,
+            kernel_rel: uc::to_string_truncate(&attrs.kernelRel)?,
+            prog_name: uc::to_string_truncate(&attrs.progName)?,
+            partner_ip: uc::to_string_truncate(&attrs.partnerIP)?,
+        })
+    }
+
+    /// Get the SSO ticket the backend issued for the logged on user, to forward to other calls.
+    ///
+    /// The backend only issues a ticket when the `getsso2` parameter was set to `1` at logon,
+    /// which [`set_sso_ticket`](RfcConnectionBuilder::set_sso_ticket) and
+    /// [`set_x509_cert`](RfcConnectionBuilder::set_x509_cert) do unless told otherwise.
+    pub fn partner_sso_ticket(&self) -> Result<String> {
+        let ticket = uc::convert(SSO_TICKET_INITIAL_LEN, |buf, buf_len, res_len, err_info| {
+            let rc = unsafe { RfcGetPartnerSSOTicket(self.handle, buf, buf_len, err_info) };
+            // The same length holds the size of the buffer in and the one of the ticket out
+            *res_len = *buf_len;
+            rc
+        })?;
+        Ok(uc::to_string(&ticket, ticket.len() as u32)?
+            .trim_end_matches('\0')
+            .to_owned())
+    }
+
+    /// Check the state of the connection and collect what is known about it.
+    ///
+    /// The ping is cancelled if it takes longer than the timeout, which breaks the connection.
+    /// Each section of the report holds its own error instead of failing the whole report.
+    pub fn health_report(&self, timeout: Duration) -> HealthReport {
+        let start = Instant::now();
+        let (latency, ping_error) = match self.cancel_after(timeout, "Ping", || self.ping()) {
+            Ok(()) => (Some(start.elapsed()), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        let (attributes, attributes_error) = match self.attributes() {
+            Ok(attributes) => (Some(attributes), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        HealthReport {
+            latency,
+            ping_error,
+            attributes,
+            attributes_error,
+            sdk_version: sdk::version(),
+        }
+    }
+
+    /// Run an operation, cancelling it from another thread if it does not complete in time.
+    pub(crate) fn cancel_after<T>(
+        &self,
+        timeout: Duration,
+        what: &str,
+        op: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => return op(),
+        };
+        let key = WATCHDOG.watch(deadline, self.cancel_handle())?;
+        let result = op();
+        if WATCHDOG.unwatch(key) {
+            let err_info = RfcErrorInfo::custom_with_code(
+                _RFC_RC::RFC_TIMEOUT,
+                &format!("{} cancelled after {:?}", what, timeout),
+            );
+            self.mark_broken(&err_info);
+            return Err(err_info.into());
+        }
+        result
+    }
+
+    /// Cancel the call in progress on the connection, which breaks the connection.
+    ///
+    /// As the connection cannot be shared between threads, a call is usually cancelled from
+    /// another thread with a [`ConnectionCancelHandle`] instead.
+    pub fn cancel(&self) -> Result<()> {
+        self.cancel_handle().cancel()
+    }
+
+    /// Get a handle to cancel the calls on the connection from another thread.
+    pub fn cancel_handle(&self) -> ConnectionCancelHandle {
+        ConnectionCancelHandle(Arc::clone(&self.cancel_target))
+    }
+
+    /// Fetch the description of a function module so it is cached for later use.
+    pub fn prefetch_function(&self, name: &str) -> Result<()> {
+        let uc_name = uc::from_str(&name.to_uppercase())?;
+
+        let mut err_info = RfcErrorInfo::new();
+        let desc =
+            unsafe { RfcGetFunctionDesc(self.handle, uc_name.as_ptr(), err_info.as_mut_ptr()) };
+        if desc.is_null() {
+            return Err(self.track_error(err_info).into());
+        }
+        Ok(())
+    }
+
+    /// Begin a stateful session, keeping the ABAP user context of the calls until it ends.
+    pub fn begin_context(&self) -> StatefulSession<'_> {
+        StatefulSession::new(self)
+    }
+
+    /// Reset the ABAP user context of the connection, discarding the work not committed.
+    ///
+    /// The next call starts in a fresh context, like on a new connection.
+    pub fn end_context(&self) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe { RfcResetServerContext(self.handle, err_info.as_mut_ptr()) }) {
+            return Err(self.track_error(err_info).into());
+        }
+        Ok(())
+    }
+
+    /// Commit the work of the BAPIs called in the current context with `BAPI_TRANSACTION_COMMIT`.
+    ///
+    /// Waiting makes the commit return only once the updates are written. Error and abort
+    /// messages in the returned `BAPIRET2` fail the commit.
+    pub fn commit(&self, wait: bool) -> Result<()> {
+        let mut func = self.get_function("BAPI_TRANSACTION_COMMIT")?;
+        if wait {
+            func.set_chars("WAIT", "X")?;
+        }
+        func.invoke_checked()
+    }
+
+    /// Discard the work of the BAPIs called in the current context with
+    /// `BAPI_TRANSACTION_ROLLBACK`.
+    pub fn rollback(&self) -> Result<()> {
+        self.get_function("BAPI_TRANSACTION_ROLLBACK")?
+            .invoke_checked()
+    }
+
+    /// Start a transactional RFC unit, with a new id unless the id of a previous attempt is given.
+    ///
+    /// Giving a queue name makes it a queued RFC unit.
+    pub fn begin_transaction(
+        &self,
+        tid: Option<&str>,
+        queue: Option<&str>,
+    ) -> Result<RfcTransaction<'_>> {
+        RfcTransaction::new(self, tid, queue)
+    }
+
     /// Get a remote enabled function module by name.
-    pub fn get_function<'conn>(&'conn self, name: &str) -> Result<RfcFunction<'conn>> {
+    ///
+    /// The name is converted to uppercase, use [`get_function_exact`](Self::get_function_exact)
+    /// to look up the name as given.
+    pub fn get_function(
+        &self,
+        name: &str,
+    ) -> std::result::Result<RfcFunction<'_>, GetFunctionError> {
+        self.get_function_exact(&name.to_uppercase())
+    }
+
+    /// Call a function module with import values by parameter name, returning the values of
+    /// its export, changing and table parameters by name.
+    ///
+    /// Values are converted according to the types of the parameters, a value which does not
+    /// fit its parameter is an error naming it. Calls needing more control go through
+    /// [`get_function`](Self::get_function).
+    pub fn call(
+        &self,
+        name: &str,
+        imports: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>> {
+        let mut func = self.get_function(name)?;
+        value::set_imports(&mut func, &imports)?;
+        func.invoke()?;
+        value::get_exports(&func)
+    }
+
+    /// Get a remote enabled function module by its exact name.
+    pub fn get_function_exact(
+        &self,
+        name: &str,
+    ) -> std::result::Result<RfcFunction<'_>, GetFunctionError> {
+        self.create_function(name)
+            .map(|(func, desc)| RfcFunction::new(self, func, desc))
+            .map_err(|err| GetFunctionError::new(name, err.into()))
+    }
+
+    /// Get the description of a remote enabled function module by name, without creating a
+    /// function to call.
+    ///
+    /// The name is converted to uppercase. Descriptions are cached by the SDK for each system.
+    pub fn describe_function(
+        &self,
+        name: &str,
+    ) -> std::result::Result<RfcFunctionDesc<'_>, GetFunctionError> {
+        let name = name.to_uppercase();
+        self.function_desc(&name)
+            .map(|desc| RfcFunctionDesc::new(self, desc))
+            .map_err(|err| GetFunctionError::new(&name, err.into()))
+    }
+
+    /// Remove the cached description of a function module, to fetch it again from the backend
+    /// on the next lookup.
+    ///
+    /// The cache is shared by all the connections to the same system.
+    pub fn evict_function_desc(&self, name: &str) -> Result<()> {
+        let repository_id = uc::from_str(&self.repository_id()?)?;
+        let name = uc::from_str(&name.to_uppercase())?;
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe {
+            RfcRemoveFunctionDesc(repository_id.as_ptr(), name.as_ptr(), err_info.as_mut_ptr())
+        }) {
+            return Err(err_info.into());
+        }
+        Ok(())
+    }
+
+    /// Get the description of a structure type of the ABAP dictionary by name.
+    ///
+    /// The name is converted to uppercase. Descriptions are cached by the SDK for each system.
+    pub fn get_type_desc(&self, name: &str) -> Result<RfcTypeDesc> {
+        let name = uc::from_str(&name.to_uppercase())?;
+        let mut err_info = RfcErrorInfo::new();
+        let desc = unsafe { RfcGetTypeDesc(self.handle, name.as_ptr(), err_info.as_mut_ptr()) };
+        if desc.is_null() {
+            return Err(self.track_error(err_info).into());
+        }
+        Ok(RfcTypeDesc::new(desc))
+    }
+
+    /// Get the id of the system the metadata cache of the SDK is kept for.
+    fn repository_id(&self) -> Result<String> {
+        if self.sys_id.is_empty() {
+            Ok(self.attributes()?.sys_id)
+        } else {
+            Ok(self.sys_id.clone())
+        }
+    }
+
+    /// Get a remote enabled function module by name, handing the connection over to it.
+    ///
+    /// The function does not borrow the connection, it can be stored or moved to another thread
+    /// on its own. The connection is closed with the function, or when the lookup fails.
+    pub fn get_function_owned(
+        self,
+        name: &str,
+    ) -> std::result::Result<OwnedRfcFunction, GetFunctionError> {
+        let name = name.to_uppercase();
+        let (func, desc) = self
+            .create_function(&name)
+            .map_err(|err| GetFunctionError::new(&name, err.into()))?;
+        Ok(RfcFunction::new_owned(self, func, desc))
+    }
+
+    /// Get a remote enabled function module by name, giving up if the lookup of its description
+    /// takes longer than the timeout.
+    ///
+    /// The lookup is cancelled when the timeout is reached, which breaks the connection.
+    pub fn get_function_with_timeout(
+        &self,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<RfcFunction<'_>> {
+        let what = format!("Lookup of function {}", name.to_uppercase());
+        self.cancel_after(timeout, &what, || {
+            self.get_function(name).map_err(Into::into)
+        })
+    }
+
+    fn function_desc(&self, name: &str) -> Result<RFC_FUNCTION_DESC_HANDLE> {
         let uc_name = uc::from_str(name)?;
 
         let mut err_info = RfcErrorInfo::new();
         let desc =
             unsafe { RfcGetFunctionDesc(self.handle, uc_name.as_ptr(), err_info.as_mut_ptr()) };
         if desc.is_null() {
-            return Err(err_info);
+            return Err(self.track_error(err_info).into());
         }
+        Ok(desc)
+    }
+
+    fn create_function(
+        &self,
+        name: &str,
+    ) -> Result<(RFC_FUNCTION_HANDLE, RFC_FUNCTION_DESC_HANDLE)> {
+        let desc = self.function_desc(name)?;
+        let mut err_info = RfcErrorInfo::new();
         let func = unsafe { RfcCreateFunction(desc, err_info.as_mut_ptr()) };
         if func.is_null() {
-            return Err(err_info);
+            return Err(err_info.into());
         }
-        Ok(RfcFunction::new(&self.handle, func, desc))
+        Ok((func, desc))
     }
 }
 
 impl Drop for RfcConnection {
     fn drop(&mut self) {
         if !self.handle.is_null() {
-            let mut err_info = RfcErrorInfo::new();
-            unsafe {
-                if is_rc_err!(RfcCloseConnection(self.handle, err_info.as_mut_ptr())) {
-                    log::warn!("Connection close failed: {}", err_info);
-                }
+            // Pending cancels complete before the close and later ones fail
+            self.cancel_target.lock().unwrap().0 = ptr::null_mut();
+            if !self.owns_handle {
+                return;
             }
+            let mut err_info = RfcErrorInfo::new();
+            let failed =
+                unsafe { is_rc_err!(RfcCloseConnection(self.handle, err_info.as_mut_ptr())) };
             self.handle = ptr::null_mut();
+            let error = if failed {
+                log::warn!("Connection close failed: {}", err_info);
+                Some(err_info)
+            } else {
+                None
+            };
+            self.emit_event(ConnectionEventKind::Closed, error);
         }
     }
 }
 
 unsafe impl Send for RfcConnection {}
 
-/// An RFC connection builder to prepare parameters for opening the connection.
+/// The handle of a connection to cancel, null once the connection is closed.
+#[derive(Debug)]
+struct CancelTarget(sapnwrfc_sys::RFC_CONNECTION_HANDLE);
+
+// The SDK allows cancelling a call in flight from any thread
+unsafe impl Send for CancelTarget {}
+
+/// A handle to cancel the call in progress on a connection from another thread.
+///
+/// A cancelled call fails with an `RFC_CANCELED` error and the connection is broken.
 #[derive(Clone, Debug)]
+pub struct ConnectionCancelHandle(Arc<Mutex<CancelTarget>>);
+
+impl ConnectionCancelHandle {
+    /// Cancel the call in progress, failing once the connection is closed.
+    pub fn cancel(&self) -> Result<()> {
+        let target = self.0.lock().unwrap();
+        if target.0.is_null() {
+            return Err(RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_HANDLE,
+                "Cannot cancel a call on a closed connection",
+            ));
+        }
+        unsafe {
+            check_rc_ok!(RfcCancel(target.0));
+        }
+        Ok(())
+    }
+}
+
+/// Deadlines by time of the connections to cancel, the thread watching them starts on first use.
+struct Watchdog {
+    deadlines: Mutex<Deadlines>,
+    changed: Condvar,
+}
+
+struct Deadlines {
+    pending: BTreeMap<(Instant, u64), ConnectionCancelHandle>,
+    next_id: u64,
+    started: bool,
+}
+
+impl Watchdog {
+    const fn new() -> Self {
+        Self {
+            deadlines: Mutex::new(Deadlines {
+                pending: BTreeMap::new(),
+                next_id: 0,
+                started: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Cancel the call in progress on a connection at the deadline, unless it is unwatched first.
+    fn watch(
+        &'static self,
+        deadline: Instant,
+        handle: ConnectionCancelHandle,
+    ) -> Result<(Instant, u64)> {
+        let mut deadlines = self.deadlines.lock().unwrap();
+        if !deadlines.started {
+            thread::Builder::new()
+                .name("rfc-watchdog".to_owned())
+                .spawn(move || self.run())
+                .map_err(|err| RfcError::custom(&err.to_string()))?;
+            deadlines.started = true;
+        }
+        let key = (deadline, deadlines.next_id);
+        deadlines.next_id += 1;
+        deadlines.pending.insert(key, handle);
+        self.changed.notify_one();
+        Ok(key)
+    }
+
+    /// Stop watching a deadline, telling if it was reached and the connection cancelled.
+    fn unwatch(&self, key: (Instant, u64)) -> bool {
+        self.deadlines
+            .lock()
+            .unwrap()
+            .pending
+            .remove(&key)
+            .is_none()
+    }
+
+    fn run(&self) {
+        let mut deadlines = self.deadlines.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            deadlines = match deadlines.pending.keys().next().copied() {
+                Some(key) if key.0 <= now => {
+                    // Cancelled under the lock, so the operation cannot return before it is done
+                    // and the cancel cannot reach the next call on the connection
+                    if let Some(handle) = deadlines.pending.remove(&key) {
+                        if let Err(err) = handle.cancel() {
+                            log::warn!("Connection cancel failed: {}", err);
+                        }
+                    }
+                    deadlines
+                }
+                Some((deadline, _)) => {
+                    self.changed
+                        .wait_timeout(deadlines, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.changed.wait(deadlines).unwrap(),
+            };
+        }
+    }
+}
+
+/// The attributes of an open connection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConnectionAttributes {
+    pub dest: String,
+    pub host: String,
+    pub partner_host: String,
+    pub sys_number: String,
+    pub sys_id: String,
+    pub client: String,
+    pub user: String,
+    /// The one character SAP language key, like `E`.
+    pub language: String,
+    /// The two letter ISO code of the language, like `EN`.
+    pub iso_language: String,
+    pub codepage: String,
+    pub partner_codepage: String,
+    /// `C` on the client side of the connection and `S` on the server side.
+    pub rfc_role: String,
+    /// The release of the SDK.
+    pub rel: String,
+    /// The release of the backend, like `753`.
+    pub partner_rel: String,
+    /// The release of the backend kernel.
+    pub kernel_rel: String,
+    /// The ABAP program which opened the connection, for server connections.
+    pub prog_name: String,
+    pub partner_ip: String,
+}
+
+/// The outcome of the checks done by [`RfcConnection::health_report`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HealthReport {
+    /// The round trip time of the ping.
+    pub latency: Option<Duration>,
+    pub ping_error: Option<String>,
+    pub attributes: Option<ConnectionAttributes>,
+    pub attributes_error: Option<String>,
+    /// The major, minor and patch level of the SDK.
+    pub sdk_version: (u32, u32, u32),
+}
+
+impl HealthReport {
+    /// Check if every section of the report succeeded.
+    pub fn is_healthy(&self) -> bool {
+        self.ping_error.is_none() && self.attributes_error.is_none()
+    }
+}
+
+/// The parameters read from the environment by [`RfcConnectionBuilder::from_env`].
+const ENV_PARAMS: &[&str] = &[
+    "dest",
+    "ashost",
+    "sysnr",
+    "client",
+    "user",
+    "passwd",
+    "lang",
+    "mshost",
+    "msserv",
+    "group",
+    "sysid",
+    "saprouter",
+    "trace",
+    "snc_mode",
+    "snc_partnername",
+    "snc_qop",
+];
+
+macro_rules! param_setters {
+    ($($(#[$doc:meta])* $method:ident => $key:literal,)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $method<T: ToString>(self, value: T) -> Self {
+                self.set_param($key, value)
+            }
+        )*
+    };
+}
+
+/// The parameters always hidden from the debug output of a builder.
+const SECRET_PARAMS: &[&str] = &["passwd", "password", "x509cert", "mysapsso2"];
+
+/// An RFC connection builder to prepare parameters for opening the connection.
+///
+/// The values of secret parameters, like the password, are not shown in the debug output. With
+/// the `zeroize` feature they are also wiped from memory when replaced or when the builder is
+/// dropped, and the copies converted for the SDK are wiped once the connection is opened. Copies
+/// made by the caller or kept by the SDK itself are out of reach.
+#[derive(Clone)]
 pub struct RfcConnectionBuilder {
     params: HashMap<String, String>,
+    secrets: HashSet<String>,
+    default_call_timeout: Option<Duration>,
 }
 
 impl RfcConnectionBuilder {
@@ -106,9 +796,25 @@ impl RfcConnectionBuilder {
     pub fn new() -> Self {
         Self {
             params: HashMap::new(),
+            secrets: HashSet::new(),
+            default_call_timeout: None,
         }
     }
 
+    /// Get a builder with the parameters found in environment variables named after a prefix.
+    ///
+    /// The variables are the prefix and the uppercase parameter name joined by an underscore,
+    /// like `SAP_ASHOST` for the prefix `SAP`. Parameters set afterwards replace the ones read.
+    pub fn from_env(prefix: &str) -> Self {
+        let prefix = prefix.trim_end_matches('_').to_uppercase();
+        ENV_PARAMS.iter().fold(Self::new(), |builder, key| {
+            match env::var(format!("{}_{}", prefix, key.to_uppercase())) {
+                Ok(value) => builder.set_param(key, value),
+                Err(_) => builder,
+            }
+        })
+    }
+
     /// Set a parameter to a given value.
     ///
     /// Note that all RFC connection parameters are represented as string internally
@@ -117,7 +823,111 @@ impl RfcConnectionBuilder {
     where
         T: ToString,
     {
-        self.params.insert(key.to_owned(), value.to_string());
+        let previous = self.params.insert(key.to_owned(), value.to_string());
+        #[cfg(feature = "zeroize")]
+        if let Some(mut previous) = previous {
+            if self.is_secret(key) {
+                use zeroize::Zeroize;
+
+                previous.zeroize();
+            }
+        }
+        #[cfg(not(feature = "zeroize"))]
+        drop(previous);
+        self
+    }
+
+    /// Set a parameter whose value must not be shown, like a credential.
+    pub fn set_secret_param<T>(mut self, key: &str, value: T) -> Self
+    where
+        T: ToString,
+    {
+        self.secrets.insert(key.to_lowercase());
+        self.set_param(key, value)
+    }
+
+    fn is_secret(&self, key: &str) -> bool {
+        let key = key.to_lowercase();
+        SECRET_PARAMS.contains(&key.as_str()) || self.secrets.contains(&key)
+    }
+
+    param_setters! {
+        /// Set the host of the application server.
+        set_ashost => "ashost",
+        /// Set the instance number of the application server, like `00`.
+        set_sysnr => "sysnr",
+        set_client => "client",
+        set_user => "user",
+        set_passwd => "passwd",
+        /// Set the host of the message server, for logon through a group.
+        set_mshost => "mshost",
+        /// Set the port or service name of the message server.
+        set_msserv => "msserv",
+        /// Set the logon group, for logon through a message server.
+        set_group => "group",
+        set_sysid => "sysid",
+        /// Set the route string to reach the backend through SAProuters.
+        set_saprouter => "saprouter",
+        /// Set the trace level of the SDK, from `0` to `3` or a [`TraceLevel`].
+        set_trace => "trace",
+        /// Set whether to secure the connection with SNC, `1` to enable.
+        set_snc_mode => "snc_mode",
+        /// Set the SNC name of the backend.
+        set_snc_partnername => "snc_partnername",
+        /// Set the SNC protection level, from `1` to `9`.
+        set_snc_qop => "snc_qop",
+        /// Set whether the backend issues an SSO ticket for the partner, `1` to enable.
+        set_getsso2 => "getsso2",
+    }
+
+    /// Set the logon language, as an ISO code like `EN` or an ABAP language key like `E`.
+    ///
+    /// Language keys are converted to ISO codes. A value which is neither is kept as given, for
+    /// the logon to reject it.
+    pub fn set_lang<T: ToString>(self, value: T) -> Self {
+        let lang = value.to_string();
+        let lang = match lang.trim() {
+            key if key.chars().count() == 1 => sdk::lang_sap_to_iso(key).unwrap_or(lang),
+            iso => iso.to_uppercase(),
+        };
+        self.set_param("lang", lang)
+    }
+
+    /// Log on with a `MYSAPSSO2` ticket, like the ones issued by a portal.
+    ///
+    /// The backend is also asked for a ticket to forward, see
+    /// [`RfcConnection::partner_sso_ticket`], unless `getsso2` is set otherwise.
+    pub fn set_sso_ticket(self, ticket: &str) -> Self {
+        self.set_secret_param("mysapsso2", ticket)
+            .request_sso_ticket()
+    }
+
+    /// Log on with an X.509 certificate, given base64 encoded without the PEM header and footer.
+    ///
+    /// The backend is also asked for a ticket to forward, like with
+    /// [`set_sso_ticket`](Self::set_sso_ticket).
+    pub fn set_x509_cert(self, cert: &str) -> Self {
+        self.set_secret_param("x509cert", cert).request_sso_ticket()
+    }
+
+    fn request_sso_ticket(mut self) -> Self {
+        self.params
+            .entry("getsso2".to_owned())
+            .or_insert_with(|| "1".to_owned());
+        self
+    }
+
+    /// Get the parameters set so far.
+    pub fn params(&self) -> &HashMap<String, String> {
+        &self.params
+    }
+
+    /// Set the time allowed to function calls on the connection.
+    ///
+    /// Calls taking longer are cancelled, which breaks the connection. A call can set its own
+    /// timeout with [`RfcFunction::invoke_with_timeout`](crate::RfcFunction::invoke_with_timeout).
+    pub fn default_call_timeout(mut self, timeout: Duration) -> Self {
+        self.default_call_timeout = Some(timeout);
         self
     }
 
@@ -125,10 +935,19 @@ impl RfcConnectionBuilder {
     pub fn build(self) -> Result<RfcConnection> {
         let params: Result<Vec<_>> = self
             .params
-            .into_iter()
-            .map(|(k, v)| Ok((uc::from_str(&k)?, uc::from_str(&v)?)))
+            .iter()
+            .map(|(k, v)| Ok((uc::from_str(k)?, uc::from_str(v)?)))
             .collect();
-        RfcConnection::new(params?)
+        let params = params?;
+        let conn = RfcConnection::new(&params);
+        // The SDK keeps its own copies once connected, the values may hold credentials
+        #[cfg(feature = "zeroize")]
+        for (_, mut value) in params {
+            value.zeroize();
+        }
+        let mut conn = conn?;
+        conn.default_call_timeout = self.default_call_timeout;
+        Ok(conn)
     }
 }
 
@@ -137,3 +956,121 @@ impl Default for RfcConnectionBuilder {
         Self::new()
     }
 }
+
+#[cfg(feature = "zeroize")]
+impl Drop for RfcConnectionBuilder {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+
+        let secrets: Vec<_> = self
+            .params
+            .keys()
+            .filter(|key| self.is_secret(key))
+            .cloned()
+            .collect();
+        for key in secrets {
+            if let Some(value) = self.params.get_mut(&key) {
+                value.zeroize();
+            }
+        }
+    }
+}
+
+/// The values of secret parameters are not shown.
+impl fmt::Debug for RfcConnectionBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: BTreeMap<_, _> = self
+            .params
+            .iter()
+            .map(|(key, value)| {
+                let value = if self.is_secret(key) {
+                    "<redacted>"
+                } else {
+                    value.as_str()
+                };
+                (key.as_str(), value)
+            })
+            .collect();
+        f.debug_struct("RfcConnectionBuilder")
+            .field("params", &params)
+            .field("default_call_timeout", &self.default_call_timeout)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_params_test() {
+        let builder = RfcConnectionBuilder::new()
+            .set_ashost("sap.example.com")
+            .set_sysnr("00")
+            .set_client(100)
+            .set_param("codepage", "4103");
+        assert_eq!(builder.params()["ashost"], "sap.example.com");
+        assert_eq!(builder.params()["client"], "100");
+        assert_eq!(builder.params()["codepage"], "4103");
+    }
+
+    #[test]
+    fn from_env_test() {
+        env::set_var("SAPRFC_ENV_TEST_ASHOST", "sap.example.com");
+        env::set_var("SAPRFC_ENV_TEST_USER", "ALICE");
+        env::set_var("SAPRFC_ENV_TEST_PASSWD", "Secret123");
+
+        let builder = RfcConnectionBuilder::from_env("saprfc_env_test_").set_user("BOB");
+        assert_eq!(builder.params()["ashost"], "sap.example.com");
+        assert_eq!(builder.params()["user"], "BOB");
+        assert!(!builder.params().contains_key("client"));
+
+        let debug = format!("{:?}", builder);
+        assert!(debug.contains("sap.example.com"));
+        assert!(!debug.contains("Secret123"));
+    }
+
+    #[test]
+    fn secret_params_test() {
+        let builder = RfcConnectionBuilder::new()
+            .set_user("ALICE")
+            .set_passwd("Secret123")
+            .set_param("X509CERT", "MIIBcert")
+            .set_secret_param("snc_partnername", "p:CN=Hidden")
+            .set_param("lang", "EN");
+        let debug = format!("{:?}", builder);
+        assert!(!debug.contains("Secret123"));
+        assert!(!debug.contains("MIIBcert"));
+        assert!(!debug.contains("Hidden"));
+        assert!(debug.contains("ALICE"));
+        assert!(debug.contains("EN"));
+
+        // The real values are kept for opening the connection
+        assert_eq!(builder.params()["passwd"], "Secret123");
+        assert_eq!(builder.params()["snc_partnername"], "p:CN=Hidden");
+    }
+
+    #[test]
+    fn lang_param_test() {
+        let builder = RfcConnectionBuilder::new().set_lang(" en ");
+        assert_eq!(builder.params()["lang"], "EN");
+        let builder = RfcConnectionBuilder::new().set_lang("DE");
+        assert_eq!(builder.params()["lang"], "DE");
+    }
+
+    #[test]
+    fn sso_params_test() {
+        let builder = RfcConnectionBuilder::new().set_sso_ticket("AjQxMDMBABhTSO");
+        assert_eq!(builder.params()["mysapsso2"], "AjQxMDMBABhTSO");
+        assert_eq!(builder.params()["getsso2"], "1");
+        assert!(!format!("{:?}", builder).contains("AjQxMDMBABhTSO"));
+
+        // An explicit choice about the partner ticket is kept
+        let builder = RfcConnectionBuilder::new()
+            .set_getsso2(0)
+            .set_x509_cert("MIIBcert");
+        assert_eq!(builder.params()["x509cert"], "MIIBcert");
+        assert_eq!(builder.params()["getsso2"], "0");
+        assert!(!format!("{:?}", builder).contains("MIIBcert"));
+    }
+}
@@ -1,26 +1,211 @@
 use crate::{
-    error::{Result, RfcErrorInfo},
+    decimal::DecimalText,
+    display::{self, DisplayOptions},
+    error::{Result, RfcError, RfcErrorInfo},
     macros::check_rc_ok,
+    sdk,
     structure::RfcStructure,
     table::RfcTable,
+    types::RfcType,
     uc,
 };
 use sapnwrfc_sys::{
-    RfcDescribeType, RfcGetChars, RfcGetInt, RfcGetString, RfcGetStringLength, RfcGetStructure,
-    RfcGetTable, RfcSetChars, RfcSetInt, RfcSetString, DATA_CONTAINER_HANDLE, RFC_ABAP_NAME,
-    RFC_STRUCTURE_HANDLE, RFC_TABLE_HANDLE,
+    DecFloat16Buff, DecFloat16ToString, DecFloat34Buff, DecFloat34ToString, RfcDeleteAllRows,
+    RfcDescribeType, RfcGetBytes, RfcGetChars, RfcGetDecF16, RfcGetDecF34, RfcGetFieldCount,
+    RfcGetFieldDescByIndex, RfcGetFloat, RfcGetInt, RfcGetNum, RfcGetString, RfcGetStringByIndex,
+    RfcGetStringLength, RfcGetStringLengthByIndex, RfcGetStructure, RfcGetTable, RfcGetXString,
+    RfcSetBytes, RfcSetChars, RfcSetDate, RfcSetFloat, RfcSetInt, RfcSetNum, RfcSetString,
+    RfcSetStructure, RfcSetTable, RfcSetTime, RfcSetXString, DATA_CONTAINER_HANDLE, RFCTYPE,
+    RFC_ABAP_NAME, RFC_DECF16, RFC_DECF34, RFC_ERROR_INFO, RFC_FIELD_DESC, RFC_PARAMETER_DESC,
+    RFC_RC, RFC_STRUCTURE_HANDLE, RFC_TABLE_HANDLE, RFC_TYPE_DESC_HANDLE, SAP_UC, _RFCTYPE,
+    _RFC_RC,
 };
-use std::ptr;
+use std::{
+    convert::TryFrom,
+    io, ptr,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(sapnwrfc_int8)]
+use sapnwrfc_sys::{RfcGetInt8, RfcSetInt8};
+
+/// The code of `RFCTYPE_INT8`, which headers older than 7.50 do not declare.
+#[cfg(not(sapnwrfc_int8))]
+const RFCTYPE_INT8: RFCTYPE = 31;
+
+/// The type information of a field or parameter, as needed to access its value.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DataDesc {
+    pub(crate) rfc_type: RFCTYPE,
+    pub(crate) uc_length: u32,
+    pub(crate) decimals: u32,
+    pub(crate) type_desc: RFC_TYPE_DESC_HANDLE,
+}
+
+impl From<&RFC_FIELD_DESC> for DataDesc {
+    fn from(desc: &RFC_FIELD_DESC) -> Self {
+        Self {
+            rfc_type: desc.type_,
+            uc_length: desc.ucLength,
+            decimals: desc.decimals,
+            type_desc: desc.typeDescHandle,
+        }
+    }
+}
+
+impl From<&RFC_PARAMETER_DESC> for DataDesc {
+    fn from(desc: &RFC_PARAMETER_DESC) -> Self {
+        Self {
+            rfc_type: desc.type_,
+            uc_length: desc.ucLength,
+            decimals: desc.decimals,
+            type_desc: desc.typeDescHandle,
+        }
+    }
+}
+
+/// The outcome of writing a value into a fixed-length field.
+///
+/// Lengths are counted in SAP_UC code units. Fields are named by their path within the function,
+/// like `IMPORTSTRUCT.RFCCHAR4` or `RFCTABLE[0].RFCCHAR4`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetOutcome {
+    field: String,
+    provided: u32,
+    written: u32,
+}
+
+impl SetOutcome {
+    /// Get the path of the field or parameter that was written.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Get the length of the value that was provided.
+    pub fn provided(&self) -> u32 {
+        self.provided
+    }
+
+    /// Get the length of the value that was actually written.
+    pub fn written(&self) -> u32 {
+        self.written
+    }
+
+    /// Check if the provided value did not fit in the field and was truncated.
+    pub fn is_truncated(&self) -> bool {
+        self.written < self.provided
+    }
+}
+
+/// A structure or table within the root container, named only when a path is needed.
+#[derive(Debug)]
+struct PathNode {
+    parent: Option<Arc<PathNode>>,
+    /// The row of the parent table holding the structure or table, if any.
+    parent_row: Option<u32>,
+    name: RFC_ABAP_NAME,
+}
 
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct RfcDataContainer {
     handle: DATA_CONTAINER_HANDLE,
+    /// The structure or table within the root this container is, or is a row of.
+    path: Option<Arc<PathNode>>,
+    row: Option<u32>,
+    /// The truncated values of the root and of everything nested in it.
+    truncations: Arc<Mutex<Vec<SetOutcome>>>,
 }
 
 impl RfcDataContainer {
     pub(crate) fn new(handle: DATA_CONTAINER_HANDLE) -> Self {
-        Self { handle }
+        Self {
+            handle,
+            path: None,
+            row: None,
+            truncations: Arc::default(),
+        }
+    }
+
+    /// Wrap a structure or table held by this container, sharing its truncations.
+    pub(crate) fn nested(&self, handle: DATA_CONTAINER_HANDLE, name: &RFC_ABAP_NAME) -> Self {
+        Self {
+            handle,
+            path: Some(Arc::new(PathNode {
+                parent: self.path.clone(),
+                parent_row: self.row,
+                name: *name,
+            })),
+            row: None,
+            truncations: Arc::clone(&self.truncations),
+        }
+    }
+
+    /// Wrap a row of this table container, sharing its truncations.
+    pub(crate) fn row(&self, handle: DATA_CONTAINER_HANDLE, index: u32) -> Self {
+        Self {
+            handle,
+            path: self.path.clone(),
+            row: Some(index),
+            truncations: Arc::clone(&self.truncations),
+        }
+    }
+
+    /// Build the path of the container within its root, empty for the root itself.
+    ///
+    /// Rows are named by their index in brackets.
+    fn path(&self) -> String {
+        fn push_node(node: &PathNode, path: &mut String) {
+            if let Some(parent) = &node.parent {
+                push_node(parent, path);
+            }
+            push_row(node.parent_row, path);
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&uc::to_string_truncate(&node.name).unwrap_or_default());
+        }
+
+        fn push_row(row: Option<u32>, path: &mut String) {
+            if let Some(row) = row {
+                path.push_str(&format!("[{}]", row));
+            }
+        }
+
+        let mut path = String::new();
+        if let Some(node) = &self.path {
+            push_node(node, &mut path);
+        }
+        push_row(self.row, &mut path);
+        path
+    }
+
+    fn path_of(&self, name: &str) -> String {
+        let mut path = self.path();
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(name);
+        path
+    }
+
+    pub(crate) fn handle(&self) -> DATA_CONTAINER_HANDLE {
+        self.handle
+    }
+
+    /// Get the values truncated in this container or in the ones nested in it.
+    pub fn truncations(&self) -> Vec<SetOutcome> {
+        let path = self.path();
+        let truncations = self.truncations.lock().unwrap();
+        truncations
+            .iter()
+            .filter(|t| {
+                path.is_empty()
+                    || t.field
+                        .strip_prefix(&path)
+                        .is_some_and(|rest| rest.starts_with(['.', '[']))
+            })
+            .cloned()
+            .collect()
     }
 
     pub fn set_int(&mut self, name: &RFC_ABAP_NAME, value: i32) -> Result<()> {
@@ -38,21 +223,122 @@ impl RfcDataContainer {
         Ok(value)
     }
 
-    pub fn set_chars(&mut self, name: &RFC_ABAP_NAME, value: &str) -> Result<()> {
+    /// Read an integer, failing instead of truncating when an `INT8` value does not fit.
+    pub(crate) fn get_int_checked(&self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<i32> {
+        #[cfg(sapnwrfc_int8)]
+        if desc.rfc_type == _RFCTYPE::RFCTYPE_INT8 {
+            let value = self.get_i64(name, desc)?;
+            return i32::try_from(value).map_err(|_| {
+                RfcError::custom_with_code(
+                    _RFC_RC::RFC_CONVERSION_FAILURE,
+                    &format!(
+                        "Value {} of field {} does not fit in an i32",
+                        value,
+                        uc::to_string_truncate(name).unwrap_or_default()
+                    ),
+                )
+            });
+        }
+        #[cfg(not(sapnwrfc_int8))]
+        if desc.rfc_type == RFCTYPE_INT8 {
+            return Err(sdk::unsupported_before_750("INT8 values"));
+        }
+        self.get_int(name)
+    }
+
+    /// Write an integer of any size to an `INT8` field or one of the smaller integer fields.
+    pub(crate) fn set_i64(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: i64,
+        desc: &DataDesc,
+    ) -> Result<()> {
+        #[cfg(sapnwrfc_int8)]
+        if desc.rfc_type == _RFCTYPE::RFCTYPE_INT8 {
+            sdk::require_750("INT8 values")?;
+            unsafe {
+                check_rc_ok!(RfcSetInt8(self.handle, name.as_ptr(), value));
+            }
+            return Ok(());
+        }
+        #[cfg(not(sapnwrfc_int8))]
+        if desc.rfc_type == RFCTYPE_INT8 {
+            return Err(sdk::unsupported_before_750("INT8 values"));
+        }
+        let value = i32::try_from(value)
+            .map_err(|_| type_mismatch(name, desc, &format!("the value {}", value)))?;
+        self.set_int(name, value)
+    }
+
+    /// Read an integer of any size from an `INT8` field or one of the smaller integer fields.
+    pub(crate) fn get_i64(&self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<i64> {
+        #[cfg(sapnwrfc_int8)]
+        if desc.rfc_type == _RFCTYPE::RFCTYPE_INT8 {
+            sdk::require_750("INT8 values")?;
+            let mut value: i64 = 0;
+            unsafe {
+                check_rc_ok!(RfcGetInt8(self.handle, name.as_ptr(), &mut value));
+            }
+            return Ok(value);
+        }
+        #[cfg(not(sapnwrfc_int8))]
+        if desc.rfc_type == RFCTYPE_INT8 {
+            return Err(sdk::unsupported_before_750("INT8 values"));
+        }
+        self.get_int(name).map(i64::from)
+    }
+
+    pub fn set_float(&mut self, name: &RFC_ABAP_NAME, value: f64) -> Result<()> {
+        unsafe {
+            check_rc_ok!(RfcSetFloat(self.handle, name.as_ptr(), value));
+        }
+        Ok(())
+    }
+
+    pub fn get_float(&self, name: &RFC_ABAP_NAME) -> Result<f64> {
+        let mut value: f64 = 0.0;
+        unsafe {
+            check_rc_ok!(RfcGetFloat(self.handle, name.as_ptr(), &mut value));
+        }
+        Ok(value)
+    }
+
+    pub fn set_chars(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: &str,
+        size: u32,
+    ) -> Result<SetOutcome> {
         let uc_value = uc::from_str(value)?;
         unsafe {
             check_rc_ok!(RfcSetChars(
                 self.handle,
                 name.as_ptr(),
                 uc_value.as_ptr(),
-                uc_value.len() as u32
+                uc_value.len()
             ));
         }
-        Ok(())
+        let outcome = SetOutcome {
+            field: self.path_of(&uc::to_string_truncate(name)?),
+            provided: uc_value.len(),
+            written: (uc_value.len()).min(size),
+        };
+        let mut truncations = self.truncations.lock().unwrap();
+        truncations.retain(|t| t.field != outcome.field);
+        if outcome.is_truncated() {
+            log::warn!(
+                "Value for field {} was truncated from {} to {} characters",
+                outcome.field,
+                outcome.provided,
+                outcome.written
+            );
+            truncations.push(outcome.clone());
+        }
+        Ok(outcome)
     }
 
     pub fn get_chars(&self, name: &RFC_ABAP_NAME, size: u32) -> Result<String> {
-        let mut str_buf = Vec::with_capacity(size as usize);
+        let mut str_buf: Vec<SAP_UC> = vec![0; size as usize];
         unsafe {
             check_rc_ok!(RfcGetChars(
                 self.handle,
@@ -64,6 +350,46 @@ impl RfcDataContainer {
         uc::to_string(&str_buf, size)
     }
 
+    /// Write digits to a `NUMC` field, padded with leading zeros to the length of the field.
+    pub(crate) fn set_num(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: &str,
+        desc: &DataDesc,
+    ) -> Result<()> {
+        if desc.rfc_type != _RFCTYPE::RFCTYPE_NUM {
+            return Err(type_mismatch(name, desc, "digits"));
+        }
+        let uc_value = uc::from_str(&pad_num(name, value, desc.uc_length / 2)?)?;
+        unsafe {
+            check_rc_ok!(RfcSetNum(
+                self.handle,
+                name.as_ptr(),
+                uc_value.as_ptr(),
+                uc_value.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read the digits of a `NUMC` field, with their leading zeros.
+    pub(crate) fn get_num(&self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<String> {
+        if desc.rfc_type != _RFCTYPE::RFCTYPE_NUM {
+            return Err(type_mismatch(name, desc, "digits"));
+        }
+        let size = desc.uc_length / 2;
+        let mut num_buf: Vec<SAP_UC> = vec![0; size as usize];
+        unsafe {
+            check_rc_ok!(RfcGetNum(
+                self.handle,
+                name.as_ptr(),
+                num_buf.as_mut_ptr(),
+                size
+            ));
+        }
+        uc::to_string(&num_buf, size)
+    }
+
     pub fn set_string(&mut self, name: &RFC_ABAP_NAME, value: &str) -> Result<()> {
         let uc_value = uc::from_str(value)?;
         unsafe {
@@ -71,7 +397,7 @@ impl RfcDataContainer {
                 self.handle,
                 name.as_ptr(),
                 uc_value.as_ptr(),
-                uc_value.len() as u32
+                uc_value.len()
             ));
         }
         Ok(())
@@ -91,21 +417,337 @@ impl RfcDataContainer {
                 err_info
             );
         }
-        let mut str_buf = Vec::with_capacity(str_len as usize + 1);
+        read_string(str_len, |buf, str_len, err_info| unsafe {
+            RfcGetString(
+                self.handle,
+                name.as_ptr(),
+                buf.as_mut_ptr(),
+                buf.len() as u32,
+                str_len,
+                err_info,
+            )
+        })
+    }
+
+    /// Read a value as text, fixed-length character fields are read without trailing blanks.
+    pub(crate) fn get_text(&self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<String> {
+        match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_CHAR | _RFCTYPE::RFCTYPE_NUM => Ok(self
+                .get_chars(name, desc.uc_length / 2)?
+                .trim_end()
+                .to_owned()),
+            _ => self.get_string(name),
+        }
+    }
+
+    /// Write a value as text, values of fixed-length character fields must fit the field.
+    pub(crate) fn set_text(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: &str,
+        desc: &DataDesc,
+    ) -> Result<()> {
+        if desc.rfc_type != _RFCTYPE::RFCTYPE_CHAR {
+            return self.set_string(name, value);
+        }
+        let size = desc.uc_length / 2;
+        let len = uc::from_str(value)?.len();
+        if len > size {
+            return Err(RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_PARAMETER,
+                &format!(
+                    "Value of {} characters does not fit in field {} of {}",
+                    len,
+                    uc::to_string_truncate(name)?,
+                    size
+                ),
+            ));
+        }
+        self.set_chars(name, value, size).map(|_| ())
+    }
+
+    /// Read a packed or decimal float number as text with a leading sign.
+    ///
+    /// Packed numbers are written with exactly the decimals of the field.
+    pub(crate) fn get_decimal_text(&self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<String> {
+        let raw = match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_BCD => self.get_string(name)?,
+            _RFCTYPE::RFCTYPE_DECF16 => {
+                let mut value = RFC_DECF16::default();
+                unsafe {
+                    check_rc_ok!(RfcGetDecF16(self.handle, name.as_ptr(), &mut value));
+                }
+                let mut buf = DecFloat16Buff::default();
+                if unsafe { DecFloat16ToString(value, &mut buf) } != 0 {
+                    return Err(decimal_failure(
+                        name,
+                        "DECFLOAT16 value cannot be formatted",
+                    ));
+                }
+                unsafe { uc::to_string_from_ptr(&buf as *const _ as *const SAP_UC)? }
+            }
+            _RFCTYPE::RFCTYPE_DECF34 => {
+                let mut value = RFC_DECF34::default();
+                unsafe {
+                    check_rc_ok!(RfcGetDecF34(self.handle, name.as_ptr(), &mut value));
+                }
+                let mut buf = DecFloat34Buff::default();
+                if unsafe { DecFloat34ToString(value, &mut buf) } != 0 {
+                    return Err(decimal_failure(
+                        name,
+                        "DECFLOAT34 value cannot be formatted",
+                    ));
+                }
+                unsafe { uc::to_string_from_ptr(&buf as *const _ as *const SAP_UC)? }
+            }
+            _ => return Err(type_mismatch(name, desc, "a decimal number")),
+        };
+        let value = DecimalText::parse(&raw).ok_or_else(|| {
+            decimal_failure(name, &format!("value {:?} is not a decimal number", raw))
+        })?;
+        let scale = if desc.rfc_type == _RFCTYPE::RFCTYPE_BCD {
+            desc.decimals as usize
+        } else {
+            0
+        };
+        Ok(value.to_string_with_scale(scale))
+    }
+
+    /// Write a number given as text to a packed or decimal float field.
+    ///
+    /// The sign can lead or trail the digits. A value with more decimals or integer digits than
+    /// a packed field holds is an error rather than being rounded or truncated.
+    pub(crate) fn set_decimal_text(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: &str,
+        desc: &DataDesc,
+    ) -> Result<()> {
+        let parsed = DecimalText::parse(value).ok_or_else(|| {
+            decimal_failure(name, &format!("value {:?} is not a decimal number", value))
+        })?;
+        match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_BCD => {
+                let decimals = desc.decimals as usize;
+                if parsed.decimals() > decimals {
+                    return Err(decimal_failure(
+                        name,
+                        &format!(
+                            "value {} has {} decimals but only {} are allowed",
+                            parsed,
+                            parsed.decimals(),
+                            decimals
+                        ),
+                    ));
+                }
+                // Each byte packs two digits, except the last one which holds the sign
+                let int_digits = (desc.uc_length as usize * 2)
+                    .saturating_sub(1)
+                    .saturating_sub(decimals);
+                if parsed.int_digits() > int_digits {
+                    return Err(decimal_failure(
+                        name,
+                        &format!(
+                            "value {} has {} integer digits but only {} are allowed",
+                            parsed,
+                            parsed.int_digits(),
+                            int_digits
+                        ),
+                    ));
+                }
+            }
+            _RFCTYPE::RFCTYPE_DECF16 | _RFCTYPE::RFCTYPE_DECF34 => {}
+            _ => return Err(type_mismatch(name, desc, "a decimal number")),
+        }
+        self.set_string(name, &parsed.to_string())
+    }
+
+    /// Get the string form of a field by its position in the type, skipping the name lookup.
+    pub fn get_string_by_index(&self, index: u32) -> Result<String> {
+        let mut str_len = 0;
         unsafe {
-            check_rc_ok!(
-                RfcGetString(
+            check_rc_ok!(RfcGetStringLengthByIndex(self.handle, index, &mut str_len));
+        }
+        read_string(str_len, |buf, str_len, err_info| unsafe {
+            RfcGetStringByIndex(
+                self.handle,
+                index,
+                buf.as_mut_ptr(),
+                buf.len() as u32,
+                str_len,
+                err_info,
+            )
+        })
+    }
+
+    pub(crate) fn get_bytes_into(
+        &self,
+        name: &RFC_ABAP_NAME,
+        buf: &mut [u8],
+        desc: &DataDesc,
+    ) -> Result<usize> {
+        let mut err_info = RfcErrorInfo::new();
+        let size = desc.uc_length;
+        if desc.rfc_type == _RFCTYPE::RFCTYPE_XSTRING {
+            let mut xstr_len = 0;
+            let rc = unsafe {
+                RfcGetXString(
                     self.handle,
                     name.as_ptr(),
-                    str_buf.as_mut_ptr(),
-                    str_buf.capacity() as u32,
-                    &mut str_len,
-                    err_info.as_mut_ptr()
-                ),
-                err_info
-            );
+                    buf.as_mut_ptr(),
+                    buf.len() as u32,
+                    &mut xstr_len,
+                    err_info.as_mut_ptr(),
+                )
+            };
+            if rc == _RFC_RC::RFC_BUFFER_TOO_SMALL {
+                return Err(buffer_too_small(xstr_len, buf.len()));
+            }
+            check_rc_ok!(rc, err_info);
+            Ok(xstr_len as usize)
+        } else {
+            if buf.len() < size as usize {
+                return Err(buffer_too_small(size, buf.len()));
+            }
+            unsafe {
+                check_rc_ok!(
+                    RfcGetBytes(
+                        self.handle,
+                        name.as_ptr(),
+                        buf.as_mut_ptr(),
+                        size,
+                        err_info.as_mut_ptr()
+                    ),
+                    err_info
+                );
+            }
+            Ok(size as usize)
+        }
+    }
+
+    /// Write binary data to a fixed-length field, padded with zeros, or to an `XSTRING`.
+    pub(crate) fn set_bytes(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: &[u8],
+        desc: &DataDesc,
+    ) -> Result<()> {
+        match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_XSTRING => unsafe {
+                check_rc_ok!(RfcSetXString(
+                    self.handle,
+                    name.as_ptr(),
+                    value.as_ptr(),
+                    value.len() as u32
+                ));
+            },
+            _RFCTYPE::RFCTYPE_BYTE => {
+                if value.len() > desc.uc_length as usize {
+                    return Err(RfcError::custom_with_code(
+                        _RFC_RC::RFC_INVALID_PARAMETER,
+                        &format!(
+                            "Value of {} bytes does not fit in field {} of {}",
+                            value.len(),
+                            uc::to_string_truncate(name)?,
+                            desc.uc_length
+                        ),
+                    ));
+                }
+                unsafe {
+                    check_rc_ok!(RfcSetBytes(
+                        self.handle,
+                        name.as_ptr(),
+                        value.as_ptr(),
+                        value.len() as u32
+                    ));
+                }
+            }
+            _ => return Err(type_mismatch(name, desc, "binary data")),
+        }
+        Ok(())
+    }
+
+    /// Get the length in bytes of a binary value.
+    fn bytes_len(&self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<usize> {
+        let mut len = desc.uc_length;
+        if desc.rfc_type == _RFCTYPE::RFCTYPE_XSTRING {
+            unsafe {
+                check_rc_ok!(RfcGetStringLength(self.handle, name.as_ptr(), &mut len));
+            }
+        }
+        Ok(len as usize)
+    }
+
+    /// Read the whole value of a binary field, the length of an `XSTRING` is queried first.
+    pub(crate) fn get_bytes(&self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<Vec<u8>> {
+        let len = self.bytes_len(name, desc)?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let mut buf = vec![0; len];
+        let read = self.get_bytes_into(name, &mut buf, desc)?;
+        buf.truncate(read);
+        Ok(buf)
+    }
+
+    pub(crate) fn xstring_reader(
+        &self,
+        name: &RFC_ABAP_NAME,
+        desc: &DataDesc,
+    ) -> XstringReader<'_> {
+        XstringReader {
+            container: self,
+            name: *name,
+            desc: *desc,
+            buf: None,
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Write a value given as text, converted according to the type of the field.
+    pub(crate) fn set_from_str(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: &str,
+        desc: &DataDesc,
+    ) -> Result<()> {
+        match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_CHAR => self.set_chars(name, value, desc.uc_length / 2).map(|_| ()),
+            _RFCTYPE::RFCTYPE_STRUCTURE | _RFCTYPE::RFCTYPE_TABLE => {
+                Err(type_mismatch(name, desc, "a text value"))
+            }
+            _ => self.set_string(name, value),
         }
-        uc::to_string(&str_buf, str_len)
+    }
+
+    /// Read a value formatted for display according to the type of the field.
+    pub(crate) fn get_display_value(
+        &self,
+        name: &RFC_ABAP_NAME,
+        desc: &DataDesc,
+        opts: &DisplayOptions,
+    ) -> Result<String> {
+        match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_STRUCTURE => Ok("<structure>".to_owned()),
+            _RFCTYPE::RFCTYPE_TABLE => Ok("<table>".to_owned()),
+            _ => Ok(display::format_value(
+                &self.get_string(name)?,
+                desc.rfc_type,
+                desc.decimals,
+                opts,
+            )),
+        }
+    }
+
+    /// Reset a field or parameter to the initial value of its type.
+    pub(crate) fn clear(&mut self, name: &RFC_ABAP_NAME, desc: &DataDesc) -> Result<()> {
+        unsafe { set_initial(self.handle, name, desc) }
+    }
+
+    /// Reset every field of a structure to the initial value of its type.
+    pub(crate) fn clear_all(&mut self, type_desc: RFC_TYPE_DESC_HANDLE) -> Result<()> {
+        unsafe { clear_fields(self.handle, type_desc) }
     }
 
     pub fn get_structure<'param>(
@@ -127,9 +769,10 @@ impl RfcDataContainer {
         }
         let desc = unsafe { RfcDescribeType(struc, err_info.as_mut_ptr()) };
         if desc.is_null() {
-            return Err(err_info);
+            return Err(err_info.into());
         }
-        Ok(RfcStructure::new(&self.handle, struc, desc))
+        let data = self.nested(struc, name);
+        Ok(RfcStructure::new(&self.handle, data, desc))
     }
 
     pub fn get_table<'param>(&'param self, name: &RFC_ABAP_NAME) -> Result<RfcTable<'param>> {
@@ -148,116 +791,870 @@ impl RfcDataContainer {
         }
         let desc = unsafe { RfcDescribeType(table, err_info.as_mut_ptr()) };
         if desc.is_null() {
-            return Err(err_info);
+            return Err(err_info.into());
         }
-        Ok(RfcTable::new(&self.handle, table, desc))
+        let data = self.nested(table, name);
+        Ok(RfcTable::new(&self.handle, data, desc))
     }
 
-    #[cfg(feature = "chrono")]
-    pub fn set_date<Tz>(&mut self, name: &RFC_ABAP_NAME, value: chrono::Date<Tz>) -> Result<()>
-    where
-        Tz: chrono::TimeZone,
-    {
-        use chrono::Datelike;
-        use sapnwrfc_sys::RfcSetDate;
-
-        let mut uc_value = uc::from_str(&format!(
-            "{:04}{:02}{:02}",
-            value.year(),
-            value.month(),
-            value.day(),
-        ))?;
+    /// Copy a structure into a structure field or parameter of the same type.
+    pub(crate) fn set_structure(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        value: &RfcStructure,
+    ) -> Result<()> {
         unsafe {
-            check_rc_ok!(RfcSetDate(
-                self.handle,
-                name.as_ptr(),
-                uc_value.as_mut_ptr()
-            ));
+            check_rc_ok!(RfcSetStructure(self.handle, name.as_ptr(), value.handle()));
+        }
+        Ok(())
+    }
+
+    /// Copy a table into a table field or parameter with rows of the same type.
+    pub(crate) fn set_table(&mut self, name: &RFC_ABAP_NAME, value: &RfcTable) -> Result<()> {
+        unsafe {
+            check_rc_ok!(RfcSetTable(self.handle, name.as_ptr(), value.handle()));
         }
         Ok(())
     }
 
+    /// Set a date, either in a DATS field or in a character field of length 8.
     #[cfg(feature = "chrono")]
-    pub fn get_date(&self, name: &RFC_ABAP_NAME) -> Result<chrono::Date<chrono::FixedOffset>> {
+    pub(crate) fn set_date(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        desc: &DataDesc,
+        value: chrono::NaiveDate,
+    ) -> Result<()> {
+        let uc_value = uc::from_str(&value.format("%Y%m%d").to_string())?;
+        match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_DATE => unsafe {
+                check_rc_ok!(RfcSetDate(self.handle, name.as_ptr(), uc_value.as_ptr()));
+            },
+            _ if is_chars_of(desc, sapnwrfc_sys::SAP_DATE_LN) => unsafe {
+                check_rc_ok!(RfcSetChars(
+                    self.handle,
+                    name.as_ptr(),
+                    uc_value.as_ptr(),
+                    uc_value.len()
+                ));
+            },
+            _ => return Err(type_mismatch(name, desc, "a date")),
+        }
+        Ok(())
+    }
+
+    /// Get a date, either from a DATS field or from a character field of length 8.
+    ///
+    /// The initial date, all zeros or blanks, is read as `None`.
+    #[cfg(feature = "chrono")]
+    pub(crate) fn get_date(
+        &self,
+        name: &RFC_ABAP_NAME,
+        desc: &DataDesc,
+    ) -> Result<Option<chrono::NaiveDate>> {
         use sapnwrfc_sys::{RfcGetDate, SAP_DATE};
 
-        let mut date_buf: SAP_DATE = Default::default();
-        unsafe {
-            check_rc_ok!(RfcGetDate(
-                self.handle,
-                name.as_ptr(),
-                date_buf.as_mut_ptr()
-            ));
+        let date_str = match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_DATE => {
+                let mut date_buf: SAP_DATE = Default::default();
+                unsafe {
+                    check_rc_ok!(RfcGetDate(
+                        self.handle,
+                        name.as_ptr(),
+                        date_buf.as_mut_ptr()
+                    ));
+                }
+                uc::to_string(&date_buf, sapnwrfc_sys::SAP_DATE_LN)?
+            }
+            _ if is_chars_of(desc, sapnwrfc_sys::SAP_DATE_LN) => {
+                self.get_chars(name, sapnwrfc_sys::SAP_DATE_LN)?
+            }
+            _ => return Err(type_mismatch(name, desc, "a date")),
+        };
+        if is_initial(&date_str, _RFCTYPE::RFCTYPE_DATE) {
+            return Ok(None);
         }
-        let date_str = uc::to_string(&date_buf, sapnwrfc_sys::SAP_DATE_LN)?;
-        Ok(chrono::DateTime::parse_from_str(&date_str, "%Y%m%d")
-            .map_err(|err| RfcErrorInfo::custom(&err.to_string()))?
-            .date())
+        chrono::NaiveDate::parse_from_str(&date_str, "%Y%m%d")
+            .map(Some)
+            .map_err(|err| invalid_value(name, &date_str, &err))
+    }
+
+    /// Set a time of day, either in a TIMS field or in a character field of length 6.
+    #[cfg(feature = "chrono")]
+    pub(crate) fn set_time(
+        &mut self,
+        name: &RFC_ABAP_NAME,
+        desc: &DataDesc,
+        value: chrono::NaiveTime,
+    ) -> Result<()> {
+        let uc_value = uc::from_str(&value.format("%H%M%S").to_string())?;
+        match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_TIME => unsafe {
+                check_rc_ok!(RfcSetTime(self.handle, name.as_ptr(), uc_value.as_ptr()));
+            },
+            _ if is_chars_of(desc, sapnwrfc_sys::SAP_TIME_LN) => unsafe {
+                check_rc_ok!(RfcSetChars(
+                    self.handle,
+                    name.as_ptr(),
+                    uc_value.as_ptr(),
+                    uc_value.len()
+                ));
+            },
+            _ => return Err(type_mismatch(name, desc, "a time")),
+        }
+        Ok(())
+    }
+
+    /// Get a time of day, either from a TIMS field or from a character field of length 6.
+    ///
+    /// The initial time, all zeros or blanks, is read as `None` even though it is also midnight.
+    #[cfg(feature = "chrono")]
+    pub(crate) fn get_time(
+        &self,
+        name: &RFC_ABAP_NAME,
+        desc: &DataDesc,
+    ) -> Result<Option<chrono::NaiveTime>> {
+        use sapnwrfc_sys::{RfcGetTime, SAP_TIME};
+
+        let time_str = match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_TIME => {
+                let mut time_buf: SAP_TIME = Default::default();
+                unsafe {
+                    check_rc_ok!(RfcGetTime(
+                        self.handle,
+                        name.as_ptr(),
+                        time_buf.as_mut_ptr()
+                    ));
+                }
+                uc::to_string(&time_buf, sapnwrfc_sys::SAP_TIME_LN)?
+            }
+            _ if is_chars_of(desc, sapnwrfc_sys::SAP_TIME_LN) => {
+                self.get_chars(name, sapnwrfc_sys::SAP_TIME_LN)?
+            }
+            _ => return Err(type_mismatch(name, desc, "a time")),
+        };
+        if is_initial(&time_str, _RFCTYPE::RFCTYPE_TIME) {
+            return Ok(None);
+        }
+        chrono::NaiveTime::parse_from_str(&time_str, "%H%M%S")
+            .map(Some)
+            .map_err(|err| invalid_value(name, &time_str, &err))
     }
 }
 
 unsafe impl Send for RfcDataContainer {}
 
+/// Write the initial value of the described type into a field or parameter.
+///
+/// Tables are emptied and structures have each of their fields reset recursively.
+unsafe fn set_initial(
+    handle: DATA_CONTAINER_HANDLE,
+    name: &RFC_ABAP_NAME,
+    desc: &DataDesc,
+) -> Result<()> {
+    let name = name.as_ptr();
+    // Empty values are passed as an empty NUL terminated string rather than a null pointer
+    let empty: [SAP_UC; 1] = [0];
+    let mut err_info = RfcErrorInfo::new();
+    let rc = match desc.rfc_type {
+        _RFCTYPE::RFCTYPE_CHAR => {
+            RfcSetChars(handle, name, empty.as_ptr(), 0, err_info.as_mut_ptr())
+        }
+        _RFCTYPE::RFCTYPE_NUM => {
+            let zeros: Vec<SAP_UC> = vec![b'0' as SAP_UC; desc.uc_length as usize / 2];
+            RfcSetNum(
+                handle,
+                name,
+                zeros.as_ptr(),
+                zeros.len() as u32,
+                err_info.as_mut_ptr(),
+            )
+        }
+        _RFCTYPE::RFCTYPE_DATE => {
+            let zeros = [b'0' as SAP_UC; 8];
+            RfcSetDate(handle, name, zeros.as_ptr(), err_info.as_mut_ptr())
+        }
+        _RFCTYPE::RFCTYPE_TIME => {
+            let zeros = [b'0' as SAP_UC; 6];
+            RfcSetTime(handle, name, zeros.as_ptr(), err_info.as_mut_ptr())
+        }
+        _RFCTYPE::RFCTYPE_BCD | _RFCTYPE::RFCTYPE_DECF16 | _RFCTYPE::RFCTYPE_DECF34 => {
+            let zero = [b'0' as SAP_UC];
+            RfcSetString(handle, name, zero.as_ptr(), 1, err_info.as_mut_ptr())
+        }
+        _RFCTYPE::RFCTYPE_FLOAT => RfcSetFloat(handle, name, 0.0, err_info.as_mut_ptr()),
+        _RFCTYPE::RFCTYPE_INT | _RFCTYPE::RFCTYPE_INT1 | _RFCTYPE::RFCTYPE_INT2 => {
+            RfcSetInt(handle, name, 0, err_info.as_mut_ptr())
+        }
+        #[cfg(sapnwrfc_int8)]
+        _RFCTYPE::RFCTYPE_INT8 => sapnwrfc_sys::RfcSetInt8(handle, name, 0, err_info.as_mut_ptr()),
+        _RFCTYPE::RFCTYPE_STRING => {
+            RfcSetString(handle, name, empty.as_ptr(), 0, err_info.as_mut_ptr())
+        }
+        _RFCTYPE::RFCTYPE_XSTRING => {
+            let empty = [0u8];
+            RfcSetXString(handle, name, empty.as_ptr(), 0, err_info.as_mut_ptr())
+        }
+        _RFCTYPE::RFCTYPE_BYTE => {
+            let zeros = vec![0; desc.uc_length as usize];
+            RfcSetBytes(
+                handle,
+                name,
+                zeros.as_ptr(),
+                zeros.len() as u32,
+                err_info.as_mut_ptr(),
+            )
+        }
+        _RFCTYPE::RFCTYPE_TABLE => {
+            let mut table: RFC_TABLE_HANDLE = ptr::null_mut();
+            check_rc_ok!(
+                RfcGetTable(handle, name, &mut table, err_info.as_mut_ptr()),
+                err_info
+            );
+            RfcDeleteAllRows(table, err_info.as_mut_ptr())
+        }
+        _RFCTYPE::RFCTYPE_STRUCTURE => {
+            let mut struc: RFC_STRUCTURE_HANDLE = ptr::null_mut();
+            check_rc_ok!(
+                RfcGetStructure(handle, name, &mut struc, err_info.as_mut_ptr()),
+                err_info
+            );
+            return clear_fields(struc, desc.type_desc);
+        }
+        other => {
+            return Err(RfcError::custom(&format!(
+                "No initial value is known for RFC type {}",
+                other
+            )))
+        }
+    };
+    check_rc_ok!(rc, err_info);
+    Ok(())
+}
+
+/// Reset every field of a structure to its initial value.
+unsafe fn clear_fields(
+    handle: RFC_STRUCTURE_HANDLE,
+    type_desc: RFC_TYPE_DESC_HANDLE,
+) -> Result<()> {
+    let mut count = 0;
+    check_rc_ok!(RfcGetFieldCount(type_desc, &mut count));
+    for index in 0..count {
+        let mut field_desc = RFC_FIELD_DESC::default();
+        check_rc_ok!(RfcGetFieldDescByIndex(type_desc, index, &mut field_desc));
+        set_initial(handle, &field_desc.name, &(&field_desc).into())?;
+    }
+    Ok(())
+}
+
+/// Get the name and type information of every field of a structure type.
+pub(crate) fn field_descs(type_desc: RFC_TYPE_DESC_HANDLE) -> Result<Vec<(String, DataDesc)>> {
+    let mut count = 0;
+    unsafe {
+        check_rc_ok!(RfcGetFieldCount(type_desc, &mut count));
+    }
+    (0..count)
+        .map(|index| {
+            let mut field_desc = RFC_FIELD_DESC::default();
+            unsafe {
+                check_rc_ok!(RfcGetFieldDescByIndex(type_desc, index, &mut field_desc));
+            }
+            Ok((
+                uc::to_string_truncate(&field_desc.name)?,
+                (&field_desc).into(),
+            ))
+        })
+        .collect()
+}
+
+/// Convert the text of a packed or decimal float number, which may exceed the decimal precision.
+#[cfg(feature = "decimal")]
+pub(crate) fn to_decimal(text: &str) -> Result<rust_decimal::Decimal> {
+    text.parse().map_err(|err| {
+        RfcError::custom_with_code(
+            _RFC_RC::RFC_CONVERSION_FAILURE,
+            &format!("Value {} does not fit in a Decimal: {}", text, err),
+        )
+    })
+}
+
+/// Check if a field is a character field of the given length, like one holding a date or time.
+#[cfg(feature = "chrono")]
+fn is_chars_of(desc: &DataDesc, len: u32) -> bool {
+    matches!(
+        desc.rfc_type,
+        _RFCTYPE::RFCTYPE_CHAR | _RFCTYPE::RFCTYPE_NUM
+    ) && desc.uc_length / 2 == len
+}
+
+/// Check if the text form of a value is the initial value of its type, which SAP sends for any
+/// unset field.
+///
+/// Binary values are given in hex, dates and times may be blank as well as all zeros.
+pub(crate) fn is_initial(value: &str, rfc_type: RFCTYPE) -> bool {
+    let is_zero = || matches!(value.trim().parse::<f64>(), Ok(number) if number == 0.0);
+    match rfc_type {
+        _RFCTYPE::RFCTYPE_NUM | _RFCTYPE::RFCTYPE_DATE | _RFCTYPE::RFCTYPE_TIME => {
+            value.chars().all(|c| c == '0' || c == ' ')
+        }
+        _RFCTYPE::RFCTYPE_BYTE | _RFCTYPE::RFCTYPE_XSTRING => value.chars().all(|c| c == '0'),
+        _RFCTYPE::RFCTYPE_INT
+        | _RFCTYPE::RFCTYPE_INT1
+        | _RFCTYPE::RFCTYPE_INT2
+        | _RFCTYPE::RFCTYPE_FLOAT
+        | _RFCTYPE::RFCTYPE_BCD
+        | _RFCTYPE::RFCTYPE_DECF16
+        | _RFCTYPE::RFCTYPE_DECF34 => is_zero(),
+        #[cfg(sapnwrfc_int8)]
+        _RFCTYPE::RFCTYPE_INT8 => is_zero(),
+        _ => value.trim().is_empty(),
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn invalid_value(name: &RFC_ABAP_NAME, value: &str, err: &chrono::ParseError) -> RfcError {
+    RfcError::custom_with_code(
+        _RFC_RC::RFC_CONVERSION_FAILURE,
+        &format!(
+            "Value {:?} of field {} is invalid: {}",
+            value,
+            uc::to_string_truncate(name).unwrap_or_default(),
+            err
+        ),
+    )
+}
+
+/// Pad digits with leading zeros to the length of a `NUMC` field.
+fn pad_num(name: &RFC_ABAP_NAME, value: &str, len: u32) -> Result<String> {
+    let invalid = |reason: &str| {
+        RfcError::custom_with_code(
+            _RFC_RC::RFC_CONVERSION_FAILURE,
+            &format!(
+                "Value {:?} of field {} {}",
+                value,
+                uc::to_string_truncate(name).unwrap_or_default(),
+                reason
+            ),
+        )
+    };
+    if !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("must only contain digits"));
+    }
+    if value.len() > len as usize {
+        return Err(invalid(&format!(
+            "is longer than the {} digits of the field",
+            len
+        )));
+    }
+    Ok(format!("{:0>width$}", value, width = len as usize))
+}
+
+fn type_mismatch(name: &RFC_ABAP_NAME, desc: &DataDesc, expected: &str) -> RfcError {
+    RfcError::custom_with_code(
+        _RFC_RC::RFC_CONVERSION_FAILURE,
+        &format!(
+            "Field {} of type {} cannot hold {}",
+            uc::to_string_truncate(name).unwrap_or_default(),
+            RfcType::from(desc.rfc_type),
+            expected
+        ),
+    )
+}
+
+fn decimal_failure(name: &RFC_ABAP_NAME, reason: &str) -> RfcError {
+    RfcError::custom_with_code(
+        _RFC_RC::RFC_CONVERSION_FAILURE,
+        &format!(
+            "Field {}: {}",
+            uc::to_string_truncate(name).unwrap_or_default(),
+            reason
+        ),
+    )
+}
+
+fn buffer_too_small(required: u32, actual: usize) -> RfcError {
+    RfcError::custom_with_code(
+        _RFC_RC::RFC_BUFFER_TOO_SMALL,
+        &format!(
+            "Buffer too small, {} bytes required but only {} available",
+            required, actual
+        ),
+    )
+}
+
+/// A reader over the value of a binary field or parameter.
+///
+/// The SDK only reads a value whole, without an offset. A value fitting the buffer of the first
+/// read is copied straight into it. A larger one is fetched once, handed out in chunks and
+/// released after the last one, which allows streaming it into any `io::Write` with `io::copy`.
+pub struct XstringReader<'data> {
+    container: &'data RfcDataContainer,
+    name: RFC_ABAP_NAME,
+    desc: DataDesc,
+    buf: Option<Vec<u8>>,
+    pos: usize,
+    done: bool,
+}
+
+impl XstringReader<'_> {
+    fn read_chunk(&mut self, out: &mut [u8]) -> Result<usize> {
+        if self.done || out.is_empty() {
+            return Ok(0);
+        }
+        if self.buf.is_none() {
+            let len = self.container.bytes_len(&self.name, &self.desc)?;
+            if len <= out.len() {
+                self.done = true;
+                if len == 0 {
+                    return Ok(0);
+                }
+                return self
+                    .container
+                    .get_bytes_into(&self.name, &mut out[..len], &self.desc);
+            }
+            self.buf = Some(self.container.get_bytes(&self.name, &self.desc)?);
+        }
+        let buf = self.buf.as_ref().unwrap();
+        let count = (buf.len() - self.pos).min(out.len());
+        out[..count].copy_from_slice(&buf[self.pos..self.pos + count]);
+        self.pos += count;
+        if self.pos == buf.len() {
+            self.buf = None;
+            self.done = true;
+        }
+        Ok(count)
+    }
+}
+
+impl io::Read for XstringReader<'_> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.read_chunk(out).map_err(io::Error::other)
+    }
+}
+
+unsafe impl Send for XstringReader<'_> {}
+
 #[allow(clippy::single_component_path_imports)]
 pub mod macros {
     macro_rules! rfc_data_delegates {
         ($self:ident.$data:ident , | $name:ident , $desc:ident | { $($tt:tt)* }) => {
-            pub fn set_int(&mut $self, name: &str, value: i32) -> crate::error::Result<()> {
-                $self.$data.set_int(&crate::uc::from_str_to_abap_name(name)?, value)
+            fn data_desc(
+                &$self,
+                $name: &sapnwrfc_sys::RFC_ABAP_NAME,
+            ) -> crate::error::Result<crate::data_container::DataDesc> {
+                let mut $desc = Default::default();
+                $($tt)*
+                Ok(crate::data_container::DataDesc::from(&$desc))
             }
 
-            pub fn get_int(&$self, name: &str) -> crate::error::Result<i32> {
-                $self.$data.get_int(&crate::uc::from_str_to_abap_name(name)?)
+            /// Get the values that were truncated when set, here or in nested structures and tables.
+            pub fn truncations(&$self) -> Vec<crate::data_container::SetOutcome> {
+                $self.$data.truncations()
             }
 
-            pub fn set_chars(&mut $self, name: &str, value: &str) -> crate::error::Result<()> {
-                $self.$data.set_chars(&crate::uc::from_str_to_abap_name(name)?, value)
+            pub fn set_int<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: i32,
+            ) -> crate::error::Result<()> {
+                $self.$data.set_int(&crate::types::AsFieldName::to_abap_name(name)?, value)
             }
 
-            pub fn get_chars(&$self, name: &str) -> crate::error::Result<String> {
-                let $name = &crate::uc::from_str_to_abap_name(name)?;
-                let mut $desc = Default::default();
-                $($tt)*
-                $self.$data.get_chars(&$name, $desc.ucLength / 2)
+            /// Get an integer, an `INT8` value which does not fit is an error.
+            pub fn get_int<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<i32> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_int_checked(name, &desc)
+            }
+
+            /// Set an `INT8` value, or the value of a smaller integer field when it fits.
+            pub fn set_i64<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: i64,
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_i64(name, value, &desc)
+            }
+
+            /// Get the value of an `INT8` field or of any smaller integer field.
+            pub fn get_i64<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<i64> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_i64(name, &desc)
+            }
+
+            pub fn set_float<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: f64,
+            ) -> crate::error::Result<()> {
+                $self.$data.set_float(&crate::types::AsFieldName::to_abap_name(name)?, value)
+            }
+
+            pub fn get_float<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<f64> {
+                $self.$data.get_float(&crate::types::AsFieldName::to_abap_name(name)?)
+            }
+
+            pub fn set_chars<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &str,
+            ) -> crate::error::Result<crate::data_container::SetOutcome> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_chars(name, value, desc.uc_length / 2)
+            }
+
+            pub fn get_chars<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<String> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_chars(name, desc.uc_length / 2)
+            }
+
+            /// Set the digits of a `NUMC` field, padded with leading zeros.
+            pub fn set_num<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &str,
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_num(name, value, &desc)
+            }
+
+            /// Set a number in a `NUMC` field, padded with leading zeros.
+            pub fn set_num_from_u64<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: u64,
+            ) -> crate::error::Result<()> {
+                $self.set_num(name, &value.to_string())
+            }
+
+            /// Get the digits of a `NUMC` field, with their leading zeros.
+            pub fn get_num<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<String> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_num(name, &desc)
+            }
+
+            /// Set a value as text, fixed-length character fields are padded with blanks.
+            pub fn set_string<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &str,
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_text(name, value, &desc)
+            }
+
+            /// Set a value from its text form, converted according to the type of the field.
+            pub fn set_from_str<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &str,
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_from_str(name, value, &desc)
+            }
+
+            /// Get a value as text, trailing blanks of fixed-length character fields are removed.
+            pub fn get_string<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<String> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_text(name, &desc)
+            }
+
+            /// Get a value formatted for display, with the default display options.
+            pub fn get_display_value<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<String> {
+                $self.get_display_value_with(name, &Default::default())
+            }
+
+            /// Get a value formatted for display according to the type of the field.
+            pub fn get_display_value_with<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+                opts: &crate::display::DisplayOptions,
+            ) -> crate::error::Result<String> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_display_value(name, &desc, opts)
             }
 
-            pub fn set_string(&mut $self, name: &str, value: &str) -> crate::error::Result<()> {
-                $self.$data.set_string(&crate::uc::from_str_to_abap_name(name)?, value)
+            /// Set a packed or decimal float number given as text, the sign can trail the digits.
+            pub fn set_decimal_str<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &str,
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_decimal_text(name, value, &desc)
             }
 
-            pub fn get_string(&$self, name: &str) -> crate::error::Result<String> {
-                $self.$data.get_string(&crate::uc::from_str_to_abap_name(name)?)
+            /// Get a packed or decimal float number as text with a leading sign.
+            pub fn get_decimal_str<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<String> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_decimal_text(name, &desc)
             }
 
-            pub fn get_structure<'param>(
+            #[cfg(feature = "decimal")]
+            pub fn set_decimal<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: rust_decimal::Decimal,
+            ) -> crate::error::Result<()> {
+                $self.set_decimal_str(name, &value.to_string())
+            }
+
+            #[cfg(feature = "decimal")]
+            pub fn get_decimal<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<rust_decimal::Decimal> {
+                crate::data_container::to_decimal(&$self.get_decimal_str(name)?)
+            }
+
+            /// Set binary data, fixed-length fields are padded with zeros.
+            pub fn set_bytes<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &[u8],
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_bytes(name, value, &desc)
+            }
+
+            /// Get the whole value of a fixed-length binary field or an `XSTRING`.
+            pub fn get_bytes<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<Vec<u8>> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_bytes(name, &desc)
+            }
+
+            pub fn get_bytes_into<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+                buf: &mut [u8],
+            ) -> crate::error::Result<usize> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_bytes_into(name, buf, &desc)
+            }
+
+            pub fn xstring_reader<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<crate::data_container::XstringReader<'_>> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                Ok($self.$data.xstring_reader(name, &desc))
+            }
+
+            /// Get a structure field or parameter, borrowed from its parent.
+            ///
+            /// Nested structures and tables are reached by chaining the calls on the result.
+            pub fn get_structure<'param, N: crate::types::AsFieldName + ?Sized>(
                 &'param $self,
-                name: &str
+                name: &N
             ) -> crate::error::Result<crate::structure::RfcStructure<'param>> {
-                $self.$data.get_structure(&crate::uc::from_str_to_abap_name(name)?)
+                $self.$data.get_structure(&crate::types::AsFieldName::to_abap_name(name)?)
+            }
+
+            /// Copy a structure into a structure field or parameter of the same type.
+            pub fn set_structure<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &crate::structure::RfcStructure,
+            ) -> crate::error::Result<()> {
+                $self.$data.set_structure(&crate::types::AsFieldName::to_abap_name(name)?, value)
             }
 
-            pub fn get_table<'param>(
+            /// Copy a table into a table field or parameter with rows of the same type.
+            pub fn set_table<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: &crate::table::RfcTable,
+            ) -> crate::error::Result<()> {
+                $self.$data.set_table(&crate::types::AsFieldName::to_abap_name(name)?, value)
+            }
+
+            /// Get a table field or parameter, borrowed from its parent.
+            pub fn get_table<'param, N: crate::types::AsFieldName + ?Sized>(
                 &'param $self,
-                name: &str
+                name: &N
             ) -> crate::error::Result<crate::table::RfcTable<'param>> {
-                $self.$data.get_table(&crate::uc::from_str_to_abap_name(name)?)
+                $self.$data.get_table(&crate::types::AsFieldName::to_abap_name(name)?)
+            }
+
+            /// Set a date in a DATS field or a character field of length 8.
+            #[cfg(feature = "chrono")]
+            pub fn set_date<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: chrono::NaiveDate,
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_date(name, &desc, value)
+            }
+
+            /// Get a date, `None` when the field holds the initial date.
+            #[cfg(feature = "chrono")]
+            pub fn get_date<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<Option<chrono::NaiveDate>> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_date(name, &desc)
             }
 
+            /// Set a time of day in a TIMS field or a character field of length 6.
             #[cfg(feature = "chrono")]
-            pub fn set_date<Tz>(&mut $self, name: &str, value: chrono::Date<Tz>) -> crate::error::Result<()>
-            where
-                Tz: chrono::TimeZone,
-                Tz::Offset: std::fmt::Display,
-            {
-                $self.$data.set_date(&crate::uc::from_str_to_abap_name(name)?, value)
+            pub fn set_time<N: crate::types::AsFieldName + ?Sized>(
+                &mut $self,
+                name: &N,
+                value: chrono::NaiveTime,
+            ) -> crate::error::Result<()> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.set_time(name, &desc, value)
             }
 
+            /// Get a time of day, `None` when the field holds the initial time.
             #[cfg(feature = "chrono")]
-            pub fn get_date(&$self, name: &str) -> crate::error::Result<chrono::Date<chrono::FixedOffset>> {
-                $self.$data.get_date(&crate::uc::from_str_to_abap_name(name)?)
+            pub fn get_time<N: crate::types::AsFieldName + ?Sized>(
+                &$self,
+                name: &N,
+            ) -> crate::error::Result<Option<chrono::NaiveTime>> {
+                let name = &crate::types::AsFieldName::to_abap_name(name)?;
+                let desc = $self.data_desc(name)?;
+                $self.$data.get_time(name, &desc)
             }
         };
     }
 
     pub(crate) use rfc_data_delegates;
 }
+
+/// Read a string of the given length into a zeroed buffer, growing it once when the SDK asks
+/// for more room.
+///
+/// Only the code units the SDK reports as written are converted.
+fn read_string<F>(len: u32, mut read: F) -> Result<String>
+where
+    F: FnMut(&mut [SAP_UC], &mut u32, *mut RFC_ERROR_INFO) -> RFC_RC,
+{
+    let mut err_info = RfcErrorInfo::new();
+    let mut buf: Vec<SAP_UC> = vec![0; len as usize + 1];
+    let mut res_len = 0;
+    let mut rc = read(&mut buf, &mut res_len, err_info.as_mut_ptr());
+    if rc == _RFC_RC::RFC_BUFFER_TOO_SMALL {
+        // The required length is reported back in place of the written one
+        buf = vec![0; res_len as usize + 1];
+        rc = read(&mut buf, &mut res_len, err_info.as_mut_ptr());
+    }
+    check_rc_ok!(rc, err_info);
+    buf.truncate(res_len as usize);
+    uc::to_string(&buf, res_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_read(
+        value: &'static str,
+    ) -> impl FnMut(&mut [SAP_UC], &mut u32, *mut RFC_ERROR_INFO) -> RFC_RC {
+        move |buf, res_len, _| {
+            *res_len = value.len() as u32;
+            if buf.len() < value.len() + 1 {
+                return _RFC_RC::RFC_BUFFER_TOO_SMALL;
+            }
+            for (unit, byte) in buf.iter_mut().zip(value.bytes()) {
+                *unit = SAP_UC::from(byte);
+            }
+            _RFC_RC::RFC_OK
+        }
+    }
+
+    #[test]
+    fn read_string_exact_length_test() {
+        assert_eq!(read_string(4, fake_read("Fizz")).unwrap(), "Fizz");
+        assert_eq!(read_string(0, fake_read("")).unwrap(), "");
+    }
+
+    #[test]
+    fn read_string_grow_test() {
+        // The value got longer than the queried length, the buffer grows to what is reported
+        assert_eq!(read_string(2, fake_read("FizzBuzz")).unwrap(), "FizzBuzz");
+    }
+
+    #[test]
+    fn read_string_shorter_test() {
+        // Only the written units are converted, not the rest of the zeroed buffer
+        assert_eq!(read_string(8, fake_read("Fizz")).unwrap(), "Fizz");
+    }
+
+    #[test]
+    fn pad_num_test() {
+        let name = uc::from_str_to_abap_name("MATNR").unwrap();
+        assert_eq!(pad_num(&name, "4711", 18).unwrap(), "000000000000004711");
+        assert_eq!(pad_num(&name, "", 4).unwrap(), "0000");
+        assert_eq!(pad_num(&name, "1234", 4).unwrap(), "1234");
+
+        let err = pad_num(&name, "12345", 4).unwrap_err();
+        assert_eq!(err.code(), crate::error::RfcErrorCode::ConversionFailure);
+        assert!(err.message().contains("MATNR"));
+        assert!(pad_num(&name, "-12", 4).is_err());
+        assert!(pad_num(&name, "\u{0663}", 4).is_err());
+    }
+
+    #[test]
+    fn is_initial_test() {
+        assert!(is_initial("00000000", _RFCTYPE::RFCTYPE_DATE));
+        assert!(is_initial("        ", _RFCTYPE::RFCTYPE_DATE));
+        assert!(!is_initial("20240131", _RFCTYPE::RFCTYPE_DATE));
+        assert!(is_initial("0.000", _RFCTYPE::RFCTYPE_BCD));
+        assert!(is_initial("   ", _RFCTYPE::RFCTYPE_CHAR));
+
+        // Binary values are read in hex
+        assert!(is_initial("000000", _RFCTYPE::RFCTYPE_BYTE));
+        assert!(is_initial("", _RFCTYPE::RFCTYPE_XSTRING));
+        assert!(!is_initial("00FF00", _RFCTYPE::RFCTYPE_BYTE));
+    }
+}
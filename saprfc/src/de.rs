@@ -0,0 +1,396 @@
+use crate::{
+    error::RfcError,
+    owned::{OwnedStructure, OwnedTable, OwnedValue},
+};
+use sapnwrfc_sys::_RFC_RC;
+use serde::{
+    de::{
+        self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess,
+        Unexpected, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+use std::{error, fmt};
+
+/// Deserialize a value from named fields, like the parameters of a function.
+pub(crate) fn from_fields<T: DeserializeOwned>(
+    fields: &[(String, OwnedValue)],
+) -> Result<T, Error> {
+    T::deserialize(FieldsDeserializer(Fields::new(
+        fields.iter().map(|(name, value)| (name.as_str(), value)),
+    )))
+}
+
+/// Deserialize a value from the fields of a structure.
+pub(crate) fn from_structure<T: DeserializeOwned>(structure: &OwnedStructure) -> Result<T, Error> {
+    T::deserialize(FieldsDeserializer(Fields::new(
+        structure
+            .fields()
+            .iter()
+            .map(|field| (field.name(), field.value())),
+    )))
+}
+
+/// A deserialization failure with the path of the field it occurred in.
+#[derive(Debug)]
+pub(crate) struct Error {
+    path: String,
+    message: String,
+}
+
+impl Error {
+    fn in_field(mut self, name: &str) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("{}{}", name, self.path)
+        } else {
+            format!("{}.{}", name, self.path)
+        };
+        self
+    }
+
+    fn in_row(mut self, index: usize) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("[{}]{}", index, self.path)
+        } else {
+            format!("[{}].{}", index, self.path)
+        };
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            path: String::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl From<Error> for RfcError {
+    fn from(err: Error) -> Self {
+        RfcError::custom_with_code(
+            _RFC_RC::RFC_CONVERSION_FAILURE,
+            &format!("Cannot deserialize {}", err),
+        )
+    }
+}
+
+/// Named fields read as a map.
+///
+/// When read into a struct, the names are matched to the fields of the struct without regard to
+/// case, as ABAP names are uppercase.
+struct Fields<'a, I> {
+    iter: I,
+    pending: Option<(&'a str, &'a OwnedValue)>,
+    targets: &'static [&'static str],
+}
+
+impl<'a, I> Fields<'a, I> {
+    fn new(iter: I) -> Self {
+        Self {
+            iter,
+            pending: None,
+            targets: &[],
+        }
+    }
+
+    fn with_targets(mut self, targets: &'static [&'static str]) -> Self {
+        self.targets = targets;
+        self
+    }
+}
+
+impl<'de, 'a, I> MapAccess<'de> for Fields<'a, I>
+where
+    I: Iterator<Item = (&'a str, &'a OwnedValue)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((name, value)) => {
+                self.pending = Some((name, value));
+                let key = self
+                    .targets
+                    .iter()
+                    .find(|target| target.eq_ignore_ascii_case(name))
+                    .copied()
+                    .unwrap_or(name);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (name, value) = self.pending.take().expect("Value requested before its key");
+        seed.deserialize(ValueDeserializer(value))
+            .map_err(|err| err.in_field(name))
+    }
+}
+
+/// Named fields read as a map or a struct.
+struct FieldsDeserializer<'a, I>(Fields<'a, I>);
+
+impl<'de, 'a, I> de::Deserializer<'de> for FieldsDeserializer<'a, I>
+where
+    I: Iterator<Item = (&'a str, &'a OwnedValue)>,
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(self.0)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(self.0.with_targets(fields))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// The rows of a table read as a sequence.
+///
+/// Rows of an elementary line type, with a single unnamed field, are read as their value.
+struct Rows<'a> {
+    table: &'a OwnedTable,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> for Rows<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let row = match self.table.rows().get(self.index) {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let index = self.index;
+        self.index += 1;
+        let result = match row.fields() {
+            [field] if field.name().is_empty() => {
+                seed.deserialize(ValueDeserializer(field.value()))
+            }
+            fields => seed.deserialize(FieldsDeserializer(Fields::new(
+                fields.iter().map(|field| (field.name(), field.value())),
+            ))),
+        };
+        result.map(Some).map_err(|err| err.in_row(index))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.table.rows().len() - self.index)
+    }
+}
+
+/// Elementary values are parsed from their string form as needed by the target type.
+struct ValueDeserializer<'a>(&'a OwnedValue);
+
+impl<'a> ValueDeserializer<'a> {
+    fn text(&self) -> Result<&'a str, Error> {
+        match self.0 {
+            OwnedValue::Text(value) => Ok(value),
+            OwnedValue::Structure(_) => Err(de::Error::invalid_type(
+                Unexpected::Map,
+                &"an elementary value",
+            )),
+            OwnedValue::Table(_) => Err(de::Error::invalid_type(
+                Unexpected::Seq,
+                &"an elementary value",
+            )),
+        }
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let text = self.text()?.trim();
+                match text.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(err) => Err(de::Error::custom(format!("invalid value {:?}, {}", text, err))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            OwnedValue::Text(value) => visitor.visit_str(value.trim_end()),
+            OwnedValue::Structure(value) => visitor.visit_map(Fields::new(
+                value
+                    .fields()
+                    .iter()
+                    .map(|field| (field.name(), field.value())),
+            )),
+            OwnedValue::Table(value) => visitor.visit_seq(Rows {
+                table: value,
+                index: 0,
+            }),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    /// ABAP flags are `X` when set and blank otherwise.
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.text()?.trim() {
+            "X" => visitor.visit_bool(true),
+            "" => visitor.visit_bool(false),
+            other => Err(de::Error::invalid_value(
+                Unexpected::Str(other),
+                &"X or blank",
+            )),
+        }
+    }
+
+    /// Blank elementary values are read as `None`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            OwnedValue::Text(value) if value.trim().is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.0 {
+            OwnedValue::Structure(value) => FieldsDeserializer(Fields::new(
+                value
+                    .fields()
+                    .iter()
+                    .map(|field| (field.name(), field.value())),
+            ))
+            .deserialize_struct(name, fields, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.text()?.trim().into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier
+        ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sapnwrfc_sys::_RFCTYPE;
+
+    fn table(field: &str, values: &[&str]) -> OwnedValue {
+        let rows = values
+            .iter()
+            .map(|value| {
+                OwnedStructure::from_values(
+                    "LINE",
+                    vec![(
+                        field,
+                        _RFCTYPE::RFCTYPE_CHAR,
+                        OwnedValue::Text(value.to_string()),
+                    )],
+                )
+            })
+            .collect();
+        OwnedValue::Table(OwnedTable::from_rows("LINE", rows))
+    }
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Lines<T> {
+        lines: Vec<T>,
+    }
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Line {
+        text: String,
+    }
+
+    #[test]
+    fn elementary_rows_test() {
+        let fields = vec![("LINES".to_owned(), table("", &["First", "Second  "]))];
+        let lines: Lines<String> = from_fields(&fields).unwrap();
+        assert_eq!(lines.lines, ["First", "Second"]);
+    }
+
+    #[test]
+    fn single_field_rows_test() {
+        // A structure with one named field is not flattened
+        let fields = vec![("LINES".to_owned(), table("TEXT", &["First"]))];
+        let lines: Lines<Line> = from_fields(&fields).unwrap();
+        assert_eq!(
+            lines.lines,
+            [Line {
+                text: "First".to_owned()
+            }]
+        );
+        let err = from_fields::<Lines<String>>(&fields).unwrap_err();
+        assert!(err.to_string().starts_with("LINES[0]"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+
+/// The largest exponent accepted in scientific notation, beyond what a `DECFLOAT34` can hold.
+const MAX_EXPONENT: i64 = 6200;
+
+/// A decimal number split into its digits, as read from or written to a packed or decimal float
+/// field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DecimalText {
+    negative: bool,
+    /// The integer digits, without leading zeros.
+    int: String,
+    /// The fractional digits, without trailing zeros.
+    frac: String,
+}
+
+impl DecimalText {
+    /// Parse a number in plain, ABAP or scientific notation.
+    ///
+    /// The sign can lead or trail the digits, as ABAP writes negative numbers like `123.45-`.
+    pub(crate) fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let (negative, unsigned) = if let Some(rest) = raw.strip_prefix('-') {
+            (true, rest)
+        } else if let Some(rest) = raw.strip_suffix('-') {
+            (true, rest)
+        } else {
+            (
+                false,
+                raw.strip_prefix('+')
+                    .or_else(|| raw.strip_suffix('+'))
+                    .unwrap_or(raw),
+            )
+        };
+        let unsigned = unsigned.trim();
+        let (mantissa, exponent) = match unsigned.find(['E', 'e']) {
+            Some(pos) => {
+                let exponent: i64 = unsigned[pos + 1..].parse().ok()?;
+                if exponent.abs() > MAX_EXPONENT {
+                    return None;
+                }
+                (&unsigned[..pos], exponent)
+            }
+            None => (unsigned, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int.is_empty() && frac.is_empty()
+            || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        // Shift the decimal point by the exponent over the digits of the mantissa
+        let digits: String = int.chars().chain(frac.chars()).collect();
+        let point = int.len() as i64 + exponent;
+        let (int, frac) = if point <= 0 {
+            (String::new(), "0".repeat(-point as usize) + &digits)
+        } else if point as usize >= digits.len() {
+            (
+                digits.clone() + &"0".repeat(point as usize - digits.len()),
+                String::new(),
+            )
+        } else {
+            let (int, frac) = digits.split_at(point as usize);
+            (int.to_owned(), frac.to_owned())
+        };
+
+        let int = int.trim_start_matches('0').to_owned();
+        let frac = frac.trim_end_matches('0').to_owned();
+        let negative = negative && !(int.is_empty() && frac.is_empty());
+        Some(Self {
+            negative,
+            int,
+            frac,
+        })
+    }
+
+    /// Get the number of integer digits, zero for a number below one.
+    pub(crate) fn int_digits(&self) -> usize {
+        self.int.len()
+    }
+
+    /// Get the number of significant fractional digits.
+    pub(crate) fn decimals(&self) -> usize {
+        self.frac.len()
+    }
+
+    /// Format the number with a leading minus sign and at least the given number of decimals.
+    pub(crate) fn to_string_with_scale(&self, scale: usize) -> String {
+        let mut out = String::with_capacity(self.int.len() + self.frac.len().max(scale) + 3);
+        if self.negative {
+            out.push('-');
+        }
+        if self.int.is_empty() {
+            out.push('0');
+        } else {
+            out.push_str(&self.int);
+        }
+        if !self.frac.is_empty() || scale > 0 {
+            out.push('.');
+            out.push_str(&self.frac);
+            for _ in self.frac.len()..scale {
+                out.push('0');
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for DecimalText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_with_scale(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DecimalText;
+
+    fn normalize(raw: &str) -> Option<String> {
+        DecimalText::parse(raw).map(|value| value.to_string())
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(normalize("123.45").as_deref(), Some("123.45"));
+        assert_eq!(normalize(" 00123.4500 ").as_deref(), Some("123.45"));
+        assert_eq!(normalize("+7").as_deref(), Some("7"));
+        assert_eq!(normalize(".5").as_deref(), Some("0.5"));
+        assert_eq!(normalize("5.").as_deref(), Some("5"));
+        assert_eq!(normalize("0.000").as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn sign_test() {
+        assert_eq!(normalize("-123.45").as_deref(), Some("-123.45"));
+        assert_eq!(normalize("123.45-").as_deref(), Some("-123.45"));
+        assert_eq!(normalize("123.45 -").as_deref(), Some("-123.45"));
+        assert_eq!(normalize("1+").as_deref(), Some("1"));
+        assert_eq!(normalize("0.00-").as_deref(), Some("0"));
+        assert_eq!(normalize("-1-"), None);
+    }
+
+    #[test]
+    fn exponent_test() {
+        assert_eq!(normalize("1.5E+3").as_deref(), Some("1500"));
+        assert_eq!(normalize("-12345E-2").as_deref(), Some("-123.45"));
+        assert_eq!(normalize("1e-3").as_deref(), Some("0.001"));
+        assert_eq!(normalize("1E"), None);
+        assert_eq!(normalize("1E99999"), None);
+    }
+
+    #[test]
+    fn invalid_test() {
+        assert_eq!(normalize(""), None);
+        assert_eq!(normalize("-"), None);
+        assert_eq!(normalize("."), None);
+        assert_eq!(normalize("1.2.3"), None);
+        assert_eq!(normalize("1,5"), None);
+        assert_eq!(normalize("abc"), None);
+    }
+
+    #[test]
+    fn scale_test() {
+        let value = DecimalText::parse("-12.5").unwrap();
+        assert_eq!(value.int_digits(), 2);
+        assert_eq!(value.decimals(), 1);
+        assert_eq!(value.to_string_with_scale(3), "-12.500");
+        assert_eq!(value.to_string_with_scale(0), "-12.5");
+        assert_eq!(
+            DecimalText::parse("0").unwrap().to_string_with_scale(2),
+            "0.00"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use sapnwrfc_sys::{RFCTYPE, _RFCTYPE};
+
+/// Options to format values for display with `get_display_value_with`.
+///
+/// Date and time formats are patterns where `YYYY`, `MM` and `DD`, respectively `HH`, `MM` and
+/// `SS`, are replaced by the parts of the value.
+#[derive(Clone, Debug)]
+pub struct DisplayOptions {
+    date_format: String,
+    time_format: String,
+    strip_numc_zeros: bool,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            date_format: "YYYY-MM-DD".to_owned(),
+            time_format: "HH:MM:SS".to_owned(),
+            strip_numc_zeros: false,
+        }
+    }
+}
+
+impl DisplayOptions {
+    /// Get the default options, with ISO dates and times and numeric texts as is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the pattern of dates, `YYYY-MM-DD` by default.
+    pub fn set_date_format(mut self, format: &str) -> Self {
+        self.date_format = format.to_owned();
+        self
+    }
+
+    /// Set the pattern of times, `HH:MM:SS` by default.
+    pub fn set_time_format(mut self, format: &str) -> Self {
+        self.time_format = format.to_owned();
+        self
+    }
+
+    /// Set whether leading zeros of numeric texts are removed.
+    pub fn set_strip_numc_zeros(mut self, strip: bool) -> Self {
+        self.strip_numc_zeros = strip;
+        self
+    }
+}
+
+/// Format the string form of a value as ABAP would write it.
+pub(crate) fn format_value(
+    raw: &str,
+    rfc_type: RFCTYPE,
+    decimals: u32,
+    opts: &DisplayOptions,
+) -> String {
+    match rfc_type {
+        _RFCTYPE::RFCTYPE_CHAR | _RFCTYPE::RFCTYPE_STRING => raw.trim_end().to_owned(),
+        _RFCTYPE::RFCTYPE_NUM if opts.strip_numc_zeros => {
+            let stripped = raw.trim().trim_start_matches('0');
+            if stripped.is_empty() && !raw.trim().is_empty() {
+                "0".to_owned()
+            } else {
+                stripped.to_owned()
+            }
+        }
+        _RFCTYPE::RFCTYPE_BCD => format_decimal(raw, decimals),
+        _RFCTYPE::RFCTYPE_DATE => format_parts(raw, &opts.date_format, &["YYYY", "MM", "DD"])
+            .unwrap_or_else(|| raw.trim().to_owned()),
+        _RFCTYPE::RFCTYPE_TIME => format_parts(raw, &opts.time_format, &["HH", "MM", "SS"])
+            .unwrap_or_else(|| raw.trim().to_owned()),
+        _ => raw.trim().to_owned(),
+    }
+}
+
+/// Format a packed number with exactly the given decimals and a leading minus sign.
+///
+/// Extra decimals are rounded half away from zero, like ABAP does.
+fn format_decimal(raw: &str, decimals: u32) -> String {
+    let raw = raw.trim();
+    let negative = raw.starts_with('-') || raw.ends_with('-');
+    let digits = raw.trim_matches(|c| c == '-' || c == '+');
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return raw.to_owned();
+    }
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let decimals = decimals as usize;
+    let mut kept: Vec<u8> = int
+        .bytes()
+        .chain(frac.bytes().chain(std::iter::repeat(b'0')).take(decimals))
+        .collect();
+    if frac
+        .as_bytes()
+        .get(decimals)
+        .is_some_and(|&digit| digit >= b'5')
+    {
+        round_up(&mut kept);
+    }
+    let (int, frac) = kept.split_at(kept.len() - decimals);
+    let int = match std::str::from_utf8(int).unwrap().trim_start_matches('0') {
+        "" => "0",
+        int => int,
+    };
+    let frac = std::str::from_utf8(frac).unwrap();
+    let zero = int == "0" && frac.chars().all(|c| c == '0');
+    let mut out = String::with_capacity(int.len() + frac.len() + 2);
+    if negative && !zero {
+        out.push('-');
+    }
+    out.push_str(int);
+    if !frac.is_empty() {
+        out.push('.');
+        out.push_str(frac);
+    }
+    out
+}
+
+/// Add one to the last of the decimal digits, carrying over to the previous ones.
+fn round_up(digits: &mut Vec<u8>) {
+    for digit in digits.iter_mut().rev() {
+        if *digit == b'9' {
+            *digit = b'0';
+        } else {
+            *digit += 1;
+            return;
+        }
+    }
+    digits.insert(0, b'1');
+}
+
+/// Substitute the parts of a date or time into a pattern, an initial date gives an empty string.
+///
+/// The parts are taken in order from the digits of the value, 4 digits for the first part of a
+/// date and 2 for the others.
+fn format_parts(raw: &str, pattern: &str, tokens: &[&str]) -> Option<String> {
+    let raw = raw.trim();
+    if tokens[0] == "YYYY" && raw.chars().all(|c| c == '0') {
+        return Some(String::new());
+    }
+    let width: usize = tokens.iter().map(|token| token.len()).sum();
+    if raw.len() != width || !raw.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut out = pattern.to_owned();
+    let mut start = 0;
+    for token in tokens {
+        let part = &raw[start..start + token.len()];
+        out = out.replacen(token, part, 1);
+        start += token.len();
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display(raw: &str, rfc_type: RFCTYPE) -> String {
+        format_value(raw, rfc_type, 2, &DisplayOptions::new())
+    }
+
+    #[test]
+    fn display_bcd() {
+        assert_eq!(display("-12.5", _RFCTYPE::RFCTYPE_BCD), "-12.50");
+        assert_eq!(display("12.50-", _RFCTYPE::RFCTYPE_BCD), "-12.50");
+        assert_eq!(display("0001234.567", _RFCTYPE::RFCTYPE_BCD), "1234.57");
+        assert_eq!(display("1234.564", _RFCTYPE::RFCTYPE_BCD), "1234.56");
+        assert_eq!(display("9.995", _RFCTYPE::RFCTYPE_BCD), "10.00");
+        assert_eq!(display("-0.005", _RFCTYPE::RFCTYPE_BCD), "-0.01");
+        assert_eq!(display("-0.004", _RFCTYPE::RFCTYPE_BCD), "0.00");
+        assert_eq!(
+            format_value("1.23456", _RFCTYPE::RFCTYPE_BCD, 4, &DisplayOptions::new()),
+            "1.2346"
+        );
+        assert_eq!(
+            format_value("-2.5", _RFCTYPE::RFCTYPE_BCD, 0, &DisplayOptions::new()),
+            "-3"
+        );
+        assert_eq!(display("-0.00", _RFCTYPE::RFCTYPE_BCD), "0.00");
+        assert_eq!(display(".5", _RFCTYPE::RFCTYPE_BCD), "0.50");
+        assert_eq!(
+            format_value("-42", _RFCTYPE::RFCTYPE_BCD, 0, &DisplayOptions::new()),
+            "-42"
+        );
+        assert_eq!(display("1E+3", _RFCTYPE::RFCTYPE_BCD), "1E+3");
+    }
+
+    #[test]
+    fn display_date_time() {
+        assert_eq!(display("20240131", _RFCTYPE::RFCTYPE_DATE), "2024-01-31");
+        assert_eq!(display("00000000", _RFCTYPE::RFCTYPE_DATE), "");
+        assert_eq!(display("        ", _RFCTYPE::RFCTYPE_DATE), "");
+        assert_eq!(display("2024013", _RFCTYPE::RFCTYPE_DATE), "2024013");
+        assert_eq!(display("235901", _RFCTYPE::RFCTYPE_TIME), "23:59:01");
+        assert_eq!(display("000000", _RFCTYPE::RFCTYPE_TIME), "00:00:00");
+
+        let opts = DisplayOptions::new()
+            .set_date_format("DD.MM.YYYY")
+            .set_time_format("HHhMM");
+        assert_eq!(
+            format_value("20240131", _RFCTYPE::RFCTYPE_DATE, 0, &opts),
+            "31.01.2024"
+        );
+        assert_eq!(
+            format_value("235901", _RFCTYPE::RFCTYPE_TIME, 0, &opts),
+            "23h59"
+        );
+    }
+
+    #[test]
+    fn display_numc() {
+        assert_eq!(display("000123", _RFCTYPE::RFCTYPE_NUM), "000123");
+
+        let opts = DisplayOptions::new().set_strip_numc_zeros(true);
+        assert_eq!(
+            format_value("000123", _RFCTYPE::RFCTYPE_NUM, 0, &opts),
+            "123"
+        );
+        assert_eq!(format_value("0000", _RFCTYPE::RFCTYPE_NUM, 0, &opts), "0");
+    }
+
+    #[test]
+    fn display_text() {
+        assert_eq!(display("  Text   ", _RFCTYPE::RFCTYPE_CHAR), "  Text");
+        assert_eq!(display("    42", _RFCTYPE::RFCTYPE_INT), "42");
+        assert_eq!(display("1.5E+00", _RFCTYPE::RFCTYPE_FLOAT), "1.5E+00");
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{connection::RfcConnectionBuilder, error::Result, sdk, RfcConnection};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The text sent through the echo step, with characters outside of Latin-1.
+const ECHO_TEXT: &str = "Grüße aus Köln – 東京 – Ελληνικά";
+
+/// The function modules looked up by default in the metadata step.
+const DEFAULT_FUNCTIONS: &[&str] = &["RFC_PING", "RFC_SYSTEM_INFO", "STFC_CONNECTION"];
+
+/// A step of the suite run on the open connection.
+type Check = fn(&RfcConnection, &DoctorOptions, &mut StepReport);
+
+/// How bad the outcome of a diagnostic step is, in increasing order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ok => f.write_str("OK"),
+            Self::Warning => f.write_str("WARNING"),
+            Self::Error => f.write_str("ERROR"),
+        }
+    }
+}
+
+/// Options of the diagnostic suite.
+#[derive(Clone, Debug)]
+pub struct DoctorOptions {
+    ping_samples: usize,
+    functions: Vec<String>,
+    call_timeout: Duration,
+}
+
+impl Default for DoctorOptions {
+    fn default() -> Self {
+        Self {
+            ping_samples: 5,
+            functions: DEFAULT_FUNCTIONS
+                .iter()
+                .map(|&name| name.to_owned())
+                .collect(),
+            call_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl DoctorOptions {
+    /// Get the default options, 5 pings and a few standard function modules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of pings sent to measure the latency.
+    pub fn set_ping_samples(mut self, samples: usize) -> Self {
+        self.ping_samples = samples.max(1);
+        self
+    }
+
+    /// Set the function modules whose metadata fetch is timed.
+    pub fn set_functions<I, S>(mut self, functions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.functions = functions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the time allowed to each call, 30 seconds by default.
+    pub fn set_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+}
+
+/// The outcome of one step of the suite.
+#[derive(Clone, Debug, Serialize)]
+pub struct StepReport {
+    pub name: String,
+    pub severity: Severity,
+    /// How long the step took, in milliseconds.
+    pub duration_ms: Option<f64>,
+    /// What the step measured or found.
+    pub details: BTreeMap<String, String>,
+    pub error: Option<String>,
+}
+
+impl StepReport {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            severity: Severity::Ok,
+            duration_ms: None,
+            details: BTreeMap::new(),
+            error: None,
+        }
+    }
+
+    fn detail(&mut self, key: &str, value: impl ToString) {
+        self.details.insert(key.to_owned(), value.to_string());
+    }
+
+    fn fail(&mut self, severity: Severity, error: impl ToString) {
+        self.severity = self.severity.max(severity);
+        self.error = Some(error.to_string());
+    }
+}
+
+/// The outcome of the whole suite.
+#[derive(Clone, Debug, Serialize)]
+pub struct DoctorReport {
+    /// The worst severity of the steps.
+    pub severity: Severity,
+    pub steps: Vec<StepReport>,
+}
+
+impl DoctorReport {
+    /// Get the exit code of a process reporting on the suite, 0, 1 or 2 by severity.
+    pub fn exit_code(&self) -> i32 {
+        self.severity as i32
+    }
+
+    /// Get the report as a JSON document.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Report is always serializable")
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            write!(f, "[{:<7}] {}", step.severity, step.name)?;
+            if let Some(duration) = step.duration_ms {
+                write!(f, " ({:.1} ms)", duration)?;
+            }
+            writeln!(f)?;
+            for (key, value) in &step.details {
+                writeln!(f, "          {}: {}", key, value)?;
+            }
+            if let Some(error) = &step.error {
+                writeln!(f, "          error: {}", error)?;
+            }
+        }
+        write!(f, "Overall: {}", self.severity)
+    }
+}
+
+/// Run the diagnostic suite against the destination of a connection builder.
+///
+/// Every step runs even when a previous one failed, steps needing the connection are reported
+/// as errors when it could not be opened.
+pub fn run(builder: RfcConnectionBuilder, opts: &DoctorOptions) -> DoctorReport {
+    let mut steps = Vec::new();
+
+    let mut step = StepReport::new("SDK version");
+    let (major, minor, patch) = sdk::version();
+    step.detail("version", format!("{}.{}.{}", major, minor, patch));
+    steps.push(step);
+
+    let mut step = StepReport::new("Open connection");
+    let start = Instant::now();
+    let conn = match builder.default_call_timeout(opts.call_timeout).build() {
+        Ok(conn) => Some(conn),
+        Err(err) => {
+            step.fail(Severity::Error, err);
+            None
+        }
+    };
+    step.duration_ms = Some(millis(start.elapsed()));
+    steps.push(step);
+
+    let checks: [(&str, Check); 5] = [
+        ("Connection attributes", attributes),
+        ("Ping latency", ping),
+        ("String echo", string_echo),
+        ("Structure echo", structure_echo),
+        ("Metadata fetch", metadata),
+    ];
+    for (name, check) in checks.iter() {
+        let mut step = StepReport::new(name);
+        match &conn {
+            Some(conn) => {
+                let start = Instant::now();
+                check(conn, opts, &mut step);
+                step.duration_ms = Some(millis(start.elapsed()));
+            }
+            None => step.fail(
+                Severity::Error,
+                "Skipped, the connection could not be opened",
+            ),
+        }
+        steps.push(step);
+    }
+
+    DoctorReport {
+        severity: steps
+            .iter()
+            .map(|step| step.severity)
+            .max()
+            .unwrap_or(Severity::Ok),
+        steps,
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn attributes(conn: &RfcConnection, _opts: &DoctorOptions, step: &mut StepReport) {
+    match conn.attributes() {
+        Ok(attrs) => {
+            step.detail("dest", attrs.dest);
+            step.detail("host", attrs.host);
+            step.detail("partner_host", attrs.partner_host);
+            step.detail("sys_number", attrs.sys_number);
+            step.detail("sys_id", attrs.sys_id);
+            step.detail("client", attrs.client);
+            step.detail("user", attrs.user);
+            step.detail("language", attrs.language);
+            step.detail("codepage", attrs.codepage);
+            step.detail("partner_rel", attrs.partner_rel);
+            step.detail("kernel_rel", attrs.kernel_rel);
+        }
+        Err(err) => step.fail(Severity::Error, err),
+    }
+}
+
+fn ping(conn: &RfcConnection, opts: &DoctorOptions, step: &mut StepReport) {
+    let mut samples = Vec::with_capacity(opts.ping_samples);
+    for _ in 0..opts.ping_samples {
+        let start = Instant::now();
+        match conn.cancel_after(opts.call_timeout, "Ping", || conn.ping()) {
+            Ok(()) => samples.push(millis(start.elapsed())),
+            Err(err) => {
+                step.fail(Severity::Error, err);
+                break;
+            }
+        }
+    }
+    step.detail("samples", samples.len());
+    if !samples.is_empty() {
+        let min = samples.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = samples.iter().copied().fold(0.0, f64::max);
+        let avg = samples.iter().sum::<f64>() / samples.len() as f64;
+        step.detail("min_ms", format!("{:.1}", min));
+        step.detail("avg_ms", format!("{:.1}", avg));
+        step.detail("max_ms", format!("{:.1}", max));
+    }
+}
+
+fn string_echo(conn: &RfcConnection, _opts: &DoctorOptions, step: &mut StepReport) {
+    let result = (|| -> Result<String> {
+        let mut func = conn.get_function("SCP_STRING_ECHO")?;
+        func.set_string("IMP", ECHO_TEXT)?;
+        func.invoke()?;
+        func.get_string("EXP")
+    })();
+    match result {
+        Ok(echo) if echo == ECHO_TEXT => step.detail("text", echo),
+        Ok(echo) => step.fail(
+            Severity::Error,
+            format!("Sent {:?} but received {:?}", ECHO_TEXT, echo),
+        ),
+        Err(err) => step.fail(Severity::Error, err),
+    }
+}
+
+fn structure_echo(conn: &RfcConnection, _opts: &DoctorOptions, step: &mut StepReport) {
+    let result = (|| -> Result<Option<String>> {
+        let func = conn.get_function("STFC_STRUCTURE")?;
+        {
+            let mut impstruct = func.get_structure("IMPORTSTRUCT")?;
+            impstruct.set_int("RFCINT4", 112357)?;
+            impstruct.set_chars("RFCCHAR4", "Fizz")?;
+        }
+        {
+            let mut table = func.get_table("RFCTABLE")?;
+            let mut row = table.append_row()?;
+            row.set_int("RFCINT4", 42)?;
+            row.set_chars("RFCCHAR4", "Buzz")?;
+        }
+        func.invoke()?;
+
+        let expstruct = func.get_structure("ECHOSTRUCT")?;
+        let (int, chars) = (
+            expstruct.get_int("RFCINT4")?,
+            expstruct.get_chars("RFCCHAR4")?,
+        );
+        if int != 112357 || chars != "Fizz" {
+            return Ok(Some(format!("Structure echoed as {} and {:?}", int, chars)));
+        }
+        let table = func.get_table("RFCTABLE")?;
+        let first = table.get_first_row()?;
+        let (int, chars) = (first.get_int("RFCINT4")?, first.get_chars("RFCCHAR4")?);
+        if int != 42 || chars != "Buzz" {
+            return Ok(Some(format!("Table row echoed as {} and {:?}", int, chars)));
+        }
+        step.detail("rows", table.row_count()?);
+        Ok(None)
+    })();
+    match result {
+        Ok(None) => {}
+        Ok(Some(mismatch)) => step.fail(Severity::Error, mismatch),
+        Err(err) => step.fail(Severity::Error, err),
+    }
+}
+
+/// A function module missing from the system is only a warning, the list is user provided.
+fn metadata(conn: &RfcConnection, opts: &DoctorOptions, step: &mut StepReport) {
+    let mut failed = Vec::new();
+    for name in &opts.functions {
+        let start = Instant::now();
+        let what = format!("Lookup of function {}", name.to_uppercase());
+        match conn.cancel_after(opts.call_timeout, &what, || conn.prefetch_function(name)) {
+            Ok(()) => step.detail(name, format!("{:.1} ms", millis(start.elapsed()))),
+            Err(err) => {
+                step.detail(name, "failed");
+                failed.push(format!("{}: {}", name, err));
+            }
+        }
+    }
+    if !failed.is_empty() {
+        step.fail(Severity::Warning, failed.join("; "));
+    }
+}
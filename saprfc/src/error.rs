@@ -1,11 +1,14 @@
 use crate::uc;
-use sapnwrfc_sys::{RFC_ERROR_INFO, _RFC_ERROR_GROUP, _RFC_RC};
+use sapnwrfc_sys::{RFC_ERROR_GROUP, RFC_ERROR_INFO, RFC_RC, _RFC_ERROR_GROUP, _RFC_RC};
 use std::{error, fmt, result, string};
 
-pub type Result<T> = result::Result<T, RfcErrorInfo>;
+pub type Result<T> = result::Result<T, RfcError>;
+
+/// The key of the errors for calls refused by a rate limiter.
+pub const THROTTLED_KEY: &str = "THROTTLED";
 
 #[repr(transparent)]
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct RfcErrorInfo {
     inner: RFC_ERROR_INFO,
 }
@@ -16,14 +19,64 @@ impl RfcErrorInfo {
     }
 
     pub fn custom(message: &str) -> Self {
+        Self::custom_with_code(_RFC_RC::RFC_UNKNOWN_ERROR, message)
+    }
+
+    /// Create an error raising an ABAP exception when returned by a server function.
+    pub fn abap_exception(key: &str, message: &str) -> Self {
+        let mut slf = Self::custom_with_code(_RFC_RC::RFC_ABAP_EXCEPTION, message);
+        slf.inner.group = _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE;
+        uc::from_str_to_slice(key, &mut slf.inner.key).expect("Invalid ABAP exception key string");
+        slf
+    }
+
+    /// Create an error for an ABAP message reported by a function, like in a BAPI `RETURN`.
+    ///
+    /// The key is the message class followed by the number, like `M3305`.
+    pub(crate) fn abap_message(
+        msg_type: &str,
+        class: &str,
+        number: &str,
+        vars: [&str; 4],
+        message: &str,
+    ) -> Self {
+        let mut slf = Self::custom_with_code(_RFC_RC::RFC_ABAP_MESSAGE, message);
+        slf.inner.group = _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE;
+        let key = format!("{}{}", class, number);
+        uc::from_str_to_slice_truncate(&key, &mut slf.inner.key);
+        uc::from_str_to_slice_truncate(msg_type, &mut slf.inner.abapMsgType);
+        uc::from_str_to_slice_truncate(class, &mut slf.inner.abapMsgClass);
+        uc::from_str_to_slice_truncate(number, &mut slf.inner.abapMsgNumber);
+        let [v1, v2, v3, v4] = vars;
+        uc::from_str_to_slice_truncate(v1, &mut slf.inner.abapMsgV1);
+        uc::from_str_to_slice_truncate(v2, &mut slf.inner.abapMsgV2);
+        uc::from_str_to_slice_truncate(v3, &mut slf.inner.abapMsgV3);
+        uc::from_str_to_slice_truncate(v4, &mut slf.inner.abapMsgV4);
+        slf
+    }
+
+    pub(crate) fn custom_with_code(code: RFC_RC, message: &str) -> Self {
         let mut slf = Self::new();
-        slf.inner.code = _RFC_RC::RFC_UNKNOWN_ERROR;
+        slf.inner.code = code;
         slf.inner.group = _RFC_ERROR_GROUP::EXTERNAL_APPLICATION_FAILURE;
-        uc::from_str_to_slice(message, &mut slf.inner.message)
-            .expect("Invalid custom error message string");
+        uc::from_str_to_slice_truncate(message, &mut slf.inner.message);
         slf
     }
 
+    #[cfg(feature = "rate-limit")]
+    pub(crate) fn with_key(mut self, key: &str) -> Self {
+        uc::from_str_to_slice(key, &mut self.inner.key).expect("Invalid custom error key string");
+        self
+    }
+
+    pub fn code(&self) -> RFC_RC {
+        self.inner.code
+    }
+
+    pub fn group(&self) -> RFC_ERROR_GROUP {
+        self.inner.group
+    }
+
     pub fn key(&self) -> String {
         uc::to_string_truncate(&self.inner.key).expect("Invalid RFC error key string")
     }
@@ -32,6 +85,96 @@ impl RfcErrorInfo {
         uc::to_string_truncate(&self.inner.message).expect("Invalid RFC error message string")
     }
 
+    /// Get the class of the ABAP message, like `M3`.
+    pub fn abap_msg_class(&self) -> String {
+        uc::to_string_truncate(&self.inner.abapMsgClass).expect("Invalid ABAP message string")
+    }
+
+    /// Get the type of the ABAP message, like `E`.
+    pub fn abap_msg_type(&self) -> String {
+        uc::to_string_truncate(&self.inner.abapMsgType).expect("Invalid ABAP message string")
+    }
+
+    /// Get the number of the ABAP message within its class.
+    pub fn abap_msg_number(&self) -> String {
+        uc::to_string_truncate(&self.inner.abapMsgNumber).expect("Invalid ABAP message string")
+    }
+
+    /// Get the four variables of the ABAP message.
+    pub fn abap_msg_vars(&self) -> [String; 4] {
+        [
+            &self.inner.abapMsgV1,
+            &self.inner.abapMsgV2,
+            &self.inner.abapMsgV3,
+            &self.inner.abapMsgV4,
+        ]
+        .map(|var| uc::to_string_truncate(var).expect("Invalid ABAP message string"))
+    }
+
+    /// Get the message with the `&` placeholders replaced by the ABAP message variables.
+    pub fn formatted_abap_message(&self) -> Option<String> {
+        let template = self.message();
+        if template.trim().is_empty() {
+            return None;
+        }
+        let vars = [
+            &self.inner.abapMsgV1,
+            &self.inner.abapMsgV2,
+            &self.inner.abapMsgV3,
+            &self.inner.abapMsgV4,
+        ]
+        .iter()
+        .map(|var| uc::to_string_truncate(*var).map(|v| v.trim_end().to_owned()))
+        .collect::<result::Result<Vec<_>, _>>()
+        .ok()?;
+        Some(format_abap_message(&template, &vars))
+    }
+
+    /// Check if the error leaves the connection it happened on unusable.
+    ///
+    /// Errors raised by the ABAP code of a function, like exceptions, leave it usable.
+    pub fn breaks_connection(&self) -> bool {
+        matches!(
+            self.inner.code,
+            _RFC_RC::RFC_COMMUNICATION_FAILURE
+                | _RFC_RC::RFC_CLOSED
+                | _RFC_RC::RFC_CANCELED
+                | _RFC_RC::RFC_INVALID_HANDLE
+        ) || self.inner.group == _RFC_ERROR_GROUP::COMMUNICATION_FAILURE
+    }
+
+    /// Check if the error is a rejected logon, like an expired password.
+    pub fn is_logon_failure(&self) -> bool {
+        self.inner.code == _RFC_RC::RFC_LOGON_FAILURE
+            || self.inner.group == _RFC_ERROR_GROUP::LOGON_FAILURE
+    }
+
+    /// Check if the error is a call refused by a rate limiter.
+    pub fn is_throttled(&self) -> bool {
+        self.matches_key(THROTTLED_KEY)
+    }
+
+    /// Check if the error has the given key.
+    pub fn matches_key(&self, key: &str) -> bool {
+        self.key() == key
+    }
+
+    /// Check if the error matches all of the given code, group and key.
+    pub fn matches(
+        &self,
+        code: Option<RFC_RC>,
+        group: Option<RFC_ERROR_GROUP>,
+        key: Option<&str>,
+    ) -> bool {
+        code.iter().all(|&code| self.inner.code == code)
+            && group.iter().all(|&group| self.inner.group == group)
+            && key.iter().all(|key| self.matches_key(key))
+    }
+
+    pub(crate) fn into_inner(self) -> RFC_ERROR_INFO {
+        self.inner
+    }
+
     pub(crate) fn as_mut_ptr(&mut self) -> *mut RFC_ERROR_INFO {
         &mut self.inner
     }
@@ -39,6 +182,64 @@ impl RfcErrorInfo {
 
 unsafe impl Send for RfcErrorInfo {}
 
+/// Join names for an error message, listing only the first few of a long list.
+pub(crate) fn join_names<S: AsRef<str>>(names: &[S]) -> String {
+    const MAX_NAMES: usize = 10;
+    let mut joined = names
+        .iter()
+        .take(MAX_NAMES)
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > MAX_NAMES {
+        joined.push_str(&format!(", … ({} more)", names.len() - MAX_NAMES));
+    }
+    joined
+}
+
+/// Substitute the placeholders of an ABAP message template.
+///
+/// `&1` to `&4` take the matching variable, each bare `&` takes the next variable in order and
+/// `&&` is a literal `&`. Placeholders without a value are removed.
+fn format_abap_message(template: &str, vars: &[String]) -> String {
+    let var = |index: usize| vars.get(index).map_or("", String::as_str);
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '&' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('&') => {
+                chars.next();
+                out.push('&');
+            }
+            Some(&d @ '1'..='4') => {
+                chars.next();
+                out.push_str(var(d as usize - '1' as usize));
+            }
+            _ => {
+                out.push_str(var(next));
+                next += 1;
+            }
+        }
+    }
+    out.trim().to_owned()
+}
+
+/// Errors are equal when they have the same code, group and key, the message is ignored.
+impl PartialEq for RfcErrorInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.code == other.inner.code
+            && self.inner.group == other.inner.group
+            && self.inner.key == other.inner.key
+    }
+}
+
+impl Eq for RfcErrorInfo {}
+
 impl fmt::Display for RfcErrorInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_fmt(format_args!("{}: {}", self.key(), self.message()))
@@ -86,8 +287,664 @@ impl fmt::Debug for RfcErrorInfo {
 
 impl error::Error for RfcErrorInfo {}
 
+/// An error classified by what failed, from the group of the error reported by the SDK.
+///
+/// Every variant holds the full error information.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RfcError {
+    /// The function module raised a classic exception or an error message.
+    AbapException(RfcErrorInfo),
+    /// The function module aborted with a runtime error, like a short dump.
+    AbapRuntimeError(RfcErrorInfo),
+    /// The logon to the backend was refused.
+    LogonFailure(RfcErrorInfo),
+    /// The connection was lost or cancelled and cannot be used anymore.
+    CommunicationFailure(RfcErrorInfo),
+    Other(RfcErrorInfo),
+}
+
+impl RfcError {
+    /// Create an error which is not reported by the SDK.
+    pub fn custom(message: &str) -> Self {
+        RfcErrorInfo::custom(message).into()
+    }
+
+    /// Create an error raising an ABAP exception when returned by a server function.
+    pub fn abap_exception(key: &str, message: &str) -> Self {
+        RfcErrorInfo::abap_exception(key, message).into()
+    }
+
+    pub(crate) fn custom_with_code(code: RFC_RC, message: &str) -> Self {
+        RfcErrorInfo::custom_with_code(code, message).into()
+    }
+
+    /// Get the full error information.
+    pub fn info(&self) -> &RfcErrorInfo {
+        match self {
+            Self::AbapException(info)
+            | Self::AbapRuntimeError(info)
+            | Self::LogonFailure(info)
+            | Self::CommunicationFailure(info)
+            | Self::Other(info) => info,
+        }
+    }
+
+    pub fn into_info(self) -> RfcErrorInfo {
+        match self {
+            Self::AbapException(info)
+            | Self::AbapRuntimeError(info)
+            | Self::LogonFailure(info)
+            | Self::CommunicationFailure(info)
+            | Self::Other(info) => info,
+        }
+    }
+
+    pub fn code(&self) -> RfcErrorCode {
+        self.info().code().into()
+    }
+
+    pub fn group(&self) -> RfcErrorGroup {
+        self.info().group().into()
+    }
+
+    pub fn key(&self) -> String {
+        self.info().key()
+    }
+
+    pub fn message(&self) -> String {
+        self.info().message()
+    }
+
+    /// Get the message with the `&` placeholders replaced by the ABAP message variables.
+    pub fn formatted_abap_message(&self) -> Option<String> {
+        self.info().formatted_abap_message()
+    }
+
+    /// Check if the error leaves the connection it happened on unusable.
+    pub fn breaks_connection(&self) -> bool {
+        self.info().breaks_connection()
+    }
+
+    /// Check if the error is a rejected logon, like an expired password.
+    pub fn is_logon_failure(&self) -> bool {
+        self.info().is_logon_failure()
+    }
+
+    /// Check if the error is a call refused by a rate limiter.
+    pub fn is_throttled(&self) -> bool {
+        self.info().is_throttled()
+    }
+
+    /// Check if the error has the given key.
+    pub fn matches_key(&self, key: &str) -> bool {
+        self.info().matches_key(key)
+    }
+
+    /// Check if the error matches all of the given code, group and key.
+    pub fn matches(
+        &self,
+        code: Option<RFC_RC>,
+        group: Option<RFC_ERROR_GROUP>,
+        key: Option<&str>,
+    ) -> bool {
+        self.info().matches(code, group, key)
+    }
+}
+
+impl From<RfcErrorInfo> for RfcError {
+    fn from(info: RfcErrorInfo) -> Self {
+        match info.group() {
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE => Self::AbapException(info),
+            _RFC_ERROR_GROUP::ABAP_RUNTIME_FAILURE => Self::AbapRuntimeError(info),
+            _ if info.is_logon_failure() => Self::LogonFailure(info),
+            _RFC_ERROR_GROUP::COMMUNICATION_FAILURE => Self::CommunicationFailure(info),
+            _ if info.breaks_connection() => Self::CommunicationFailure(info),
+            _ => Self::Other(info),
+        }
+    }
+}
+
+impl From<RfcError> for RfcErrorInfo {
+    fn from(err: RfcError) -> Self {
+        err.into_info()
+    }
+}
+
+impl fmt::Display for RfcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.info(), f)
+    }
+}
+
+impl error::Error for RfcError {}
+
+impl From<GetFunctionError> for RfcError {
+    fn from(src: GetFunctionError) -> Self {
+        src.source.into()
+    }
+}
+
+impl From<string::FromUtf8Error> for RfcError {
+    fn from(src: string::FromUtf8Error) -> Self {
+        RfcErrorInfo::from(src).into()
+    }
+}
+
+/// The return code of a failed SDK call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RfcErrorCode {
+    Ok,
+    CommunicationFailure,
+    LogonFailure,
+    AbapRuntimeFailure,
+    AbapMessage,
+    AbapException,
+    Closed,
+    Canceled,
+    Timeout,
+    MemoryInsufficient,
+    VersionMismatch,
+    InvalidProtocol,
+    SerializationFailure,
+    InvalidHandle,
+    Retry,
+    ExternalFailure,
+    Executed,
+    NotFound,
+    NotSupported,
+    IllegalState,
+    InvalidParameter,
+    CodepageConversionFailure,
+    ConversionFailure,
+    BufferTooSmall,
+    TableMoveBof,
+    TableMoveEof,
+    StartSapguiFailure,
+    AbapClassException,
+    UnknownError,
+    AuthorizationFailure,
+    AuthenticationFailure,
+    CryptolibFailure,
+    IoFailure,
+    LockingFailure,
+    /// A code this version of the crate does not know about.
+    Other(RFC_RC),
+}
+
+impl From<RFC_RC> for RfcErrorCode {
+    fn from(code: RFC_RC) -> Self {
+        match code {
+            _RFC_RC::RFC_OK => Self::Ok,
+            _RFC_RC::RFC_COMMUNICATION_FAILURE => Self::CommunicationFailure,
+            _RFC_RC::RFC_LOGON_FAILURE => Self::LogonFailure,
+            _RFC_RC::RFC_ABAP_RUNTIME_FAILURE => Self::AbapRuntimeFailure,
+            _RFC_RC::RFC_ABAP_MESSAGE => Self::AbapMessage,
+            _RFC_RC::RFC_ABAP_EXCEPTION => Self::AbapException,
+            _RFC_RC::RFC_CLOSED => Self::Closed,
+            _RFC_RC::RFC_CANCELED => Self::Canceled,
+            _RFC_RC::RFC_TIMEOUT => Self::Timeout,
+            _RFC_RC::RFC_MEMORY_INSUFFICIENT => Self::MemoryInsufficient,
+            _RFC_RC::RFC_VERSION_MISMATCH => Self::VersionMismatch,
+            _RFC_RC::RFC_INVALID_PROTOCOL => Self::InvalidProtocol,
+            _RFC_RC::RFC_SERIALIZATION_FAILURE => Self::SerializationFailure,
+            _RFC_RC::RFC_INVALID_HANDLE => Self::InvalidHandle,
+            _RFC_RC::RFC_RETRY => Self::Retry,
+            _RFC_RC::RFC_EXTERNAL_FAILURE => Self::ExternalFailure,
+            _RFC_RC::RFC_EXECUTED => Self::Executed,
+            _RFC_RC::RFC_NOT_FOUND => Self::NotFound,
+            _RFC_RC::RFC_NOT_SUPPORTED => Self::NotSupported,
+            _RFC_RC::RFC_ILLEGAL_STATE => Self::IllegalState,
+            _RFC_RC::RFC_INVALID_PARAMETER => Self::InvalidParameter,
+            _RFC_RC::RFC_CODEPAGE_CONVERSION_FAILURE => Self::CodepageConversionFailure,
+            _RFC_RC::RFC_CONVERSION_FAILURE => Self::ConversionFailure,
+            _RFC_RC::RFC_BUFFER_TOO_SMALL => Self::BufferTooSmall,
+            _RFC_RC::RFC_TABLE_MOVE_BOF => Self::TableMoveBof,
+            _RFC_RC::RFC_TABLE_MOVE_EOF => Self::TableMoveEof,
+            _RFC_RC::RFC_START_SAPGUI_FAILURE => Self::StartSapguiFailure,
+            _RFC_RC::RFC_ABAP_CLASS_EXCEPTION => Self::AbapClassException,
+            _RFC_RC::RFC_UNKNOWN_ERROR => Self::UnknownError,
+            _RFC_RC::RFC_AUTHORIZATION_FAILURE => Self::AuthorizationFailure,
+            _RFC_RC::RFC_AUTHENTICATION_FAILURE => Self::AuthenticationFailure,
+            _RFC_RC::RFC_CRYPTOLIB_FAILURE => Self::CryptolibFailure,
+            _RFC_RC::RFC_IO_FAILURE => Self::IoFailure,
+            _RFC_RC::RFC_LOCKING_FAILURE => Self::LockingFailure,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// The group of an error, telling which side failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RfcErrorGroup {
+    Ok,
+    AbapApplicationFailure,
+    AbapRuntimeFailure,
+    LogonFailure,
+    CommunicationFailure,
+    ExternalRuntimeFailure,
+    ExternalApplicationFailure,
+    ExternalAuthorizationFailure,
+    ExternalAuthenticationFailure,
+    CryptolibFailure,
+    LockingFailure,
+    /// A group this version of the crate does not know about.
+    Other(RFC_ERROR_GROUP),
+}
+
+impl From<RFC_ERROR_GROUP> for RfcErrorGroup {
+    fn from(group: RFC_ERROR_GROUP) -> Self {
+        match group {
+            _RFC_ERROR_GROUP::OK => Self::Ok,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE => Self::AbapApplicationFailure,
+            _RFC_ERROR_GROUP::ABAP_RUNTIME_FAILURE => Self::AbapRuntimeFailure,
+            _RFC_ERROR_GROUP::LOGON_FAILURE => Self::LogonFailure,
+            _RFC_ERROR_GROUP::COMMUNICATION_FAILURE => Self::CommunicationFailure,
+            _RFC_ERROR_GROUP::EXTERNAL_RUNTIME_FAILURE => Self::ExternalRuntimeFailure,
+            _RFC_ERROR_GROUP::EXTERNAL_APPLICATION_FAILURE => Self::ExternalApplicationFailure,
+            _RFC_ERROR_GROUP::EXTERNAL_AUTHORIZATION_FAILURE => Self::ExternalAuthorizationFailure,
+            _RFC_ERROR_GROUP::EXTERNAL_AUTHENTICATION_FAILURE => {
+                Self::ExternalAuthenticationFailure
+            }
+            _RFC_ERROR_GROUP::CRYPTOLIB_FAILURE => Self::CryptolibFailure,
+            _RFC_ERROR_GROUP::LOCKING_FAILURE => Self::LockingFailure,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// The reason a function module could not be obtained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GetFunctionErrorKind {
+    /// No function module exists with the name.
+    NotFound,
+    /// The function module exists but cannot be called over RFC.
+    NotRemoteEnabled,
+    /// The user is not allowed to read the function module metadata.
+    NoAuthorization,
+    Other,
+}
+
+/// The error returned when getting a function module fails.
+#[derive(Debug)]
+pub struct GetFunctionError {
+    kind: GetFunctionErrorKind,
+    name: String,
+    source: RfcErrorInfo,
+}
+
+impl GetFunctionError {
+    pub(crate) fn new(name: &str, source: RfcErrorInfo) -> Self {
+        let kind = match source.key().as_str() {
+            "FU_NOT_FOUND" => GetFunctionErrorKind::NotFound,
+            "FU_NOT_REMOTE" | "CALL_FUNCTION_NOT_REMOTE" => GetFunctionErrorKind::NotRemoteEnabled,
+            "RFC_NO_AUTHORITY" | "NO_AUTHORITY" => GetFunctionErrorKind::NoAuthorization,
+            _ if source.code() == _RFC_RC::RFC_AUTHORIZATION_FAILURE => {
+                GetFunctionErrorKind::NoAuthorization
+            }
+            _ => GetFunctionErrorKind::Other,
+        };
+        Self {
+            kind,
+            name: name.to_owned(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> GetFunctionErrorKind {
+        self.kind
+    }
+
+    /// Get the function module name as it was looked up.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the error reported by the SDK.
+    pub fn info(&self) -> &RfcErrorInfo {
+        &self.source
+    }
+}
+
+impl fmt::Display for GetFunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            GetFunctionErrorKind::NotFound => "does not exist",
+            GetFunctionErrorKind::NotRemoteEnabled => "is not remote enabled",
+            GetFunctionErrorKind::NoAuthorization => "metadata access is not authorized",
+            GetFunctionErrorKind::Other => "could not be obtained",
+        };
+        write!(f, "Function {} {}: {}", self.name, reason, self.source)
+    }
+}
+
+impl error::Error for GetFunctionError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<GetFunctionError> for RfcErrorInfo {
+    fn from(src: GetFunctionError) -> Self {
+        src.source
+    }
+}
+
+/// The error returned when filling one of several rows fails, with the index of the row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowError {
+    row: usize,
+    source: RfcError,
+}
+
+impl RowError {
+    pub(crate) fn new(row: usize, source: RfcError) -> Self {
+        Self { row, source }
+    }
+
+    /// Get the index of the faulty row among the rows being filled.
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// Get the error as it was reported for the row.
+    pub fn error(&self) -> &RfcError {
+        &self.source
+    }
+
+    pub fn into_error(self) -> RfcError {
+        self.source
+    }
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Row {}: {}", self.row, self.source)
+    }
+}
+
+impl error::Error for RowError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<RowError> for RfcError {
+    fn from(src: RowError) -> Self {
+        src.source
+    }
+}
+
 impl From<string::FromUtf8Error> for RfcErrorInfo {
     fn from(src: string::FromUtf8Error) -> Self {
         Self::custom(&src.to_string())
     }
 }
+
+/// Assert that a result is an error with the given key, and optionally the given code.
+///
+/// The expected and actual errors are shown on mismatch.
+#[macro_export]
+macro_rules! assert_rfc_err {
+    ($result:expr, $key:expr) => {
+        match $result {
+            Ok(value) => panic!(
+                "assertion failed: expected RFC error with key {:?}, got Ok({:?})",
+                $key, value
+            ),
+            Err(err) => {
+                let err: $crate::RfcErrorInfo = err.into();
+                if !err.matches_key($key) {
+                    panic!(
+                        "assertion failed: RFC error mismatch\n  expected key: {:?}\n      actual: {:?}",
+                        $key, err
+                    );
+                }
+            }
+        }
+    };
+    ($result:expr, $code:expr, $key:expr) => {
+        match $result {
+            Ok(value) => panic!(
+                "assertion failed: expected RFC error {:?} with key {:?}, got Ok({:?})",
+                $code, $key, value
+            ),
+            Err(err) => {
+                let err: $crate::RfcErrorInfo = err.into();
+                if !err.matches(Some($code), None, Some($key)) {
+                    panic!(
+                        "assertion failed: RFC error mismatch\n  expected code: {:?}, key: {:?}\n       actual: {:?}",
+                        $code, $key, err
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(code: RFC_RC, group: RFC_ERROR_GROUP, key: &str, message: &str) -> RfcErrorInfo {
+        let mut err = RfcErrorInfo::custom_with_code(code, message);
+        err.inner.group = group;
+        uc::from_str_to_slice(key, &mut err.inner.key).unwrap();
+        err
+    }
+
+    #[test]
+    fn error_equality() {
+        let a = error(
+            _RFC_RC::RFC_ABAP_EXCEPTION,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE,
+            "NOT_FOUND",
+            "Material does not exist",
+        );
+        let b = error(
+            _RFC_RC::RFC_ABAP_EXCEPTION,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE,
+            "NOT_FOUND",
+            "Some other text",
+        );
+        assert_eq!(a, b);
+
+        let c = error(
+            _RFC_RC::RFC_ABAP_EXCEPTION,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE,
+            "NO_AUTH",
+            "Material does not exist",
+        );
+        assert_ne!(a, c);
+
+        let d = error(
+            _RFC_RC::RFC_ABAP_MESSAGE,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE,
+            "NOT_FOUND",
+            "Material does not exist",
+        );
+        assert_ne!(a, d);
+
+        let e = error(
+            _RFC_RC::RFC_ABAP_EXCEPTION,
+            _RFC_ERROR_GROUP::ABAP_RUNTIME_FAILURE,
+            "NOT_FOUND",
+            "Material does not exist",
+        );
+        assert_ne!(a, e);
+    }
+
+    #[test]
+    fn abap_message_error() {
+        let err: RfcError = RfcErrorInfo::abap_message(
+            "E",
+            "01",
+            "124",
+            ["NOT_A_USER", "", "", ""],
+            "User NOT_A_USER does not exist",
+        )
+        .into();
+        assert!(matches!(err, RfcError::AbapException(_)));
+        assert_eq!(err.code(), RfcErrorCode::AbapMessage);
+        let info = err.info();
+        assert!(info.matches_key("01124"));
+        assert_eq!(info.abap_msg_type(), "E");
+        assert_eq!(info.abap_msg_class(), "01");
+        assert_eq!(info.abap_msg_number(), "124");
+        assert_eq!(info.abap_msg_vars()[0], "NOT_A_USER");
+        assert_eq!(err.message(), "User NOT_A_USER does not exist");
+    }
+
+    #[test]
+    fn long_message_truncated() {
+        let message = "x".repeat(2000);
+        let err = RfcError::custom_with_code(_RFC_RC::RFC_INVALID_PARAMETER, &message);
+        assert_eq!(err.code(), RfcErrorCode::InvalidParameter);
+        let truncated = err.message();
+        assert!(truncated.ends_with('…'));
+        assert!(truncated.chars().count() < 512);
+
+        let names: Vec<_> = (0..200).map(|index| format!("FIELD{}", index)).collect();
+        let joined = join_names(&names);
+        assert!(joined.starts_with("FIELD0, FIELD1, "));
+        assert!(joined.ends_with("FIELD9, … (190 more)"));
+        assert_eq!(join_names(&["A", "B"]), "A, B");
+    }
+
+    #[test]
+    fn error_matches() {
+        let err = error(
+            _RFC_RC::RFC_ABAP_EXCEPTION,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE,
+            "NOT_FOUND",
+            "Material does not exist",
+        );
+        assert!(err.matches_key("NOT_FOUND"));
+        assert!(!err.matches_key("NOT"));
+        assert!(err.matches(None, None, None));
+        assert!(err.matches(Some(_RFC_RC::RFC_ABAP_EXCEPTION), None, Some("NOT_FOUND")));
+        assert!(err.matches(None, Some(_RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE), None));
+        assert!(!err.matches(Some(_RFC_RC::RFC_ABAP_MESSAGE), None, Some("NOT_FOUND")));
+        assert!(!err.matches(None, None, Some("NO_AUTH")));
+
+        assert_rfc_err!(Err::<(), _>(err), _RFC_RC::RFC_ABAP_EXCEPTION, "NOT_FOUND");
+    }
+
+    #[test]
+    fn error_classification() {
+        let err = RfcError::from(error(
+            _RFC_RC::RFC_ABAP_EXCEPTION,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE,
+            "NOT_FOUND",
+            "Material does not exist",
+        ));
+        assert!(matches!(err, RfcError::AbapException(ref info) if info.key() == "NOT_FOUND"));
+        assert_eq!(err.code(), RfcErrorCode::AbapException);
+        assert_eq!(err.group(), RfcErrorGroup::AbapApplicationFailure);
+        assert!(!err.breaks_connection());
+
+        let err = RfcError::from(error(
+            _RFC_RC::RFC_COMMUNICATION_FAILURE,
+            _RFC_ERROR_GROUP::COMMUNICATION_FAILURE,
+            "",
+            "Connection reset by peer",
+        ));
+        assert!(matches!(err, RfcError::CommunicationFailure(_)));
+        assert!(err.breaks_connection());
+
+        // The group alone is enough, whatever the code reported with it
+        let err = RfcError::from(error(
+            _RFC_RC::RFC_UNKNOWN_ERROR,
+            _RFC_ERROR_GROUP::COMMUNICATION_FAILURE,
+            "",
+            "Partner not reached",
+        ));
+        assert!(err.breaks_connection());
+
+        let err = RfcError::from(error(
+            _RFC_RC::RFC_LOGON_FAILURE,
+            _RFC_ERROR_GROUP::LOGON_FAILURE,
+            "RFC_ERROR_LOGON_FAILURE",
+            "Password logon no longer possible",
+        ));
+        assert!(matches!(err, RfcError::LogonFailure(_)));
+
+        let err = RfcError::from(error(
+            _RFC_RC::RFC_ABAP_RUNTIME_FAILURE,
+            _RFC_ERROR_GROUP::ABAP_RUNTIME_FAILURE,
+            "COMPUTE_INT_ZERODIVIDE",
+            "Division by zero",
+        ));
+        assert!(matches!(err, RfcError::AbapRuntimeError(_)));
+
+        let err = RfcError::custom("Something else");
+        assert!(matches!(err, RfcError::Other(_)));
+        assert_eq!(err.code(), RfcErrorCode::UnknownError);
+        assert_eq!(RfcErrorCode::from(9999), RfcErrorCode::Other(9999));
+
+        // Converting back keeps the full information
+        let info = RfcErrorInfo::from(err.clone());
+        assert_eq!(info.message(), "Something else");
+        assert_eq!(RfcError::from(info), err);
+    }
+
+    #[test]
+    fn abap_message_numbered() {
+        let vars = vec!["4711".to_owned(), "1000".to_owned()];
+        assert_eq!(
+            format_abap_message("Material &1 does not exist in plant &2", &vars),
+            "Material 4711 does not exist in plant 1000"
+        );
+        assert_eq!(
+            format_abap_message("Plant &2 has no material &1", &vars),
+            "Plant 1000 has no material 4711"
+        );
+    }
+
+    #[test]
+    fn abap_message_unnumbered() {
+        let vars = vec!["4711".to_owned(), "1000".to_owned()];
+        assert_eq!(
+            format_abap_message("Material & does not exist in plant &", &vars),
+            "Material 4711 does not exist in plant 1000"
+        );
+        assert_eq!(
+            format_abap_message("Material && plant: & &", &vars),
+            "Material & plant: 4711 1000"
+        );
+    }
+
+    #[test]
+    fn abap_message_mixed() {
+        let vars = vec!["A".to_owned(), "B".to_owned(), "C".to_owned()];
+        assert_eq!(format_abap_message("&3 & &1 &", &vars), "C A A B");
+    }
+
+    #[test]
+    fn abap_message_missing_values() {
+        let vars = vec!["4711".to_owned(), String::new()];
+        assert_eq!(
+            format_abap_message("Material &1 not found &2", &vars),
+            "Material 4711 not found"
+        );
+        assert_eq!(format_abap_message("& & & & &", &[]), "");
+        assert_eq!(
+            format_abap_message("  No placeholders  ", &vars),
+            "No placeholders"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "RFC error mismatch")]
+    fn assert_rfc_err_mismatch() {
+        let err = error(
+            _RFC_RC::RFC_ABAP_EXCEPTION,
+            _RFC_ERROR_GROUP::ABAP_APPLICATION_FAILURE,
+            "NOT_FOUND",
+            "Material does not exist",
+        );
+        assert_rfc_err!(Err::<(), _>(err), "NO_AUTH");
+    }
+}
@@ -0,0 +1,88 @@
+use crate::error::RfcErrorInfo;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+type Listener = dyn Fn(ConnectionEvent) + Send + Sync;
+
+/// The listeners of the whole process with their subscription id.
+static LISTENERS: RwLock<Vec<(u64, Arc<Listener>)>> = RwLock::new(Vec::new());
+
+static NEXT_SUBSCRIPTION: AtomicU64 = AtomicU64::new(1);
+
+/// What happened to a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionEventKind {
+    /// The connection was opened.
+    Opened,
+    /// The connection was closed.
+    Closed,
+    /// A failure left the connection unusable.
+    Broken,
+    /// The connection was opened again after a failure, see
+    /// [`RfcConnection::reopen`](crate::RfcConnection::reopen).
+    Reconnected,
+    /// A pool checked the connection and handed it out again.
+    Recycled,
+}
+
+/// An event in the life of a connection.
+#[derive(Clone, Debug)]
+pub struct ConnectionEvent {
+    /// The process-wide id of the connection, see [`RfcConnection::id`](crate::RfcConnection::id).
+    pub connection_id: u64,
+    /// The destination of the connection, empty when connected without one.
+    pub dest: String,
+    /// The id of the system the connection is logged on to.
+    pub sys_id: String,
+    pub kind: ConnectionEventKind,
+    /// The failure which caused the event, if any.
+    pub error: Option<RfcErrorInfo>,
+}
+
+/// A handle to stop receiving events, returned by [`subscribe`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct Subscription(u64);
+
+/// Call a listener with the events of every connection of the process.
+///
+/// Listeners are called synchronously on the thread of the connection and should return
+/// quickly. A panic in a listener is logged and does not affect the connection.
+pub fn subscribe<F>(listener: F) -> Subscription
+where
+    F: Fn(ConnectionEvent) + Send + Sync + 'static,
+{
+    let id = NEXT_SUBSCRIPTION.fetch_add(1, Ordering::Relaxed);
+    LISTENERS.write().unwrap().push((id, Arc::new(listener)));
+    Subscription(id)
+}
+
+/// Stop calling a listener.
+pub fn unsubscribe(subscription: Subscription) {
+    LISTENERS
+        .write()
+        .unwrap()
+        .retain(|(id, _)| *id != subscription.0);
+}
+
+/// Send an event to every listener.
+pub(crate) fn emit(event: ConnectionEvent) {
+    // Listeners are called outside of the lock so they can subscribe or unsubscribe
+    let listeners: Vec<_> = match LISTENERS.read() {
+        Ok(listeners) if !listeners.is_empty() => listeners
+            .iter()
+            .map(|(_, listener)| Arc::clone(listener))
+            .collect(),
+        _ => return,
+    };
+    for listener in listeners {
+        let event = event.clone();
+        if panic::catch_unwind(AssertUnwindSafe(|| listener(event))).is_err() {
+            log::error!("Connection event listener panicked");
+        }
+    }
+}
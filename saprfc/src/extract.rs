@@ -0,0 +1,193 @@
+use crate::{
+    error::{Result, RfcError, RfcErrorCode},
+    function::RfcFunction,
+    pool::{self, Manager, Pool},
+    RfcConnection,
+};
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use deadpool::managed::{Object, PoolError};
+use std::fmt;
+
+/// An axum extractor checking out a connection from the [`Pool`] held in the application state.
+pub struct RfcConn(pub Object<Manager>);
+
+impl RfcConn {
+    /// Run a closure with the connection on a blocking thread.
+    ///
+    /// The closure first waits for the rate limiter of the pool, if any.
+    pub async fn interact<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&RfcConnection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        pool::interact(&self.0, f).await
+    }
+
+    /// Call a function module on a blocking thread.
+    ///
+    /// The `setup` closure provides the parameters before the invocation and the `extract`
+    /// closure reads the results after. The call first waits for the rate limiter of the pool, if
+    /// any.
+    pub async fn call<S, E, T>(&self, name: &str, setup: S, extract: E) -> Result<T>
+    where
+        S: FnOnce(&mut RfcFunction) -> Result<()> + Send + 'static,
+        E: FnOnce(&RfcFunction) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        pool::call(&self.0, name, setup, extract).await
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RfcConn
+where
+    Pool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = RfcRejection;
+
+    async fn from_request_parts(
+        _parts: &mut Parts,
+        state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let pool = Pool::from_ref(state);
+        Ok(Self(pool.get().await?))
+    }
+}
+
+/// A rejection responding with a status matching the failure of a checkout or a call.
+///
+/// Refused logons respond with `401 Unauthorized`, or `403 Forbidden` when authorizations are
+/// missing, ABAP exceptions with `422 Unprocessable Entity`, communication failures and pool
+/// exhaustion with `503 Service Unavailable` and anything else with `500 Internal Server Error`.
+#[derive(Debug)]
+pub struct RfcRejection {
+    status: StatusCode,
+    message: String,
+}
+
+impl RfcRejection {
+    /// Get the status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+impl fmt::Display for RfcRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<PoolError<RfcError>> for RfcRejection {
+    fn from(err: PoolError<RfcError>) -> Self {
+        match err {
+            PoolError::Backend(err) => err.into(),
+            err => Self {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<RfcError> for RfcRejection {
+    fn from(err: RfcError) -> Self {
+        let status = match &err {
+            _ if err.code() == RfcErrorCode::AuthorizationFailure => StatusCode::FORBIDDEN,
+            RfcError::LogonFailure(_) => StatusCode::UNAUTHORIZED,
+            RfcError::AbapException(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            RfcError::CommunicationFailure(_) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for RfcRejection {
+    fn into_response(self) -> Response {
+        (self.status, self.message).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use deadpool::Runtime;
+    use tower::ServiceExt;
+
+    async fn echo(conn: RfcConn) -> std::result::Result<String, RfcRejection> {
+        Ok(conn
+            .call(
+                "SCP_STRING_ECHO",
+                |func| func.set_string("IMP", "Hello from axum"),
+                |func| func.get_string("EXP"),
+            )
+            .await?)
+    }
+
+    #[test]
+    fn rejection_status_test() {
+        use crate::error::RfcErrorInfo;
+        use deadpool::managed::TimeoutType;
+
+        let status = |err: RfcError| RfcRejection::from(err).status();
+        assert_eq!(
+            status(RfcError::LogonFailure(RfcErrorInfo::custom("Bad password"))),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(RfcError::abap_exception("NOT_FOUND", "No such user")),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(RfcError::CommunicationFailure(RfcErrorInfo::custom(
+                "Connection lost"
+            ))),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            status(RfcError::custom("Conversion failed")),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        // Pool exhaustion is unavailability, failures to connect keep their own status
+        let exhausted = PoolError::<RfcError>::Timeout(TimeoutType::Wait);
+        assert_eq!(
+            RfcRejection::from(exhausted).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        let refused = PoolError::Backend(RfcError::LogonFailure(RfcErrorInfo::custom("Locked")));
+        assert_eq!(
+            RfcRejection::from(refused).status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn extractor_test() {
+        let manager = Manager::new(
+            RfcConnection::builder().set_param("dest", "TEST"),
+            Runtime::Tokio1,
+        );
+        let pool = Pool::builder(manager).max_size(2).build().unwrap();
+        let app = Router::new().route("/echo", get(echo)).with_state(pool);
+
+        let response = app
+            .oneshot(Request::get("/echo").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"Hello from axum");
+    }
+}
@@ -1,43 +1,394 @@
 use crate::{
-    data_container::{macros::rfc_data_delegates, RfcDataContainer},
-    error::{Result, RfcErrorInfo},
-    macros::{check_rc_ok, is_rc_err},
+    connection::RfcConnection,
+    data_container::{is_initial, macros::rfc_data_delegates, RfcDataContainer},
+    error::{Result, RfcError, RfcErrorInfo},
+    macros::{assert_rc_ok, check_rc_ok, is_rc_err},
+    parameter::{RfcParamMut, RfcParamRef, RfcParameterDesc},
+    structure::RfcStructure,
+    uc,
 };
+#[cfg(feature = "serde")]
+use crate::owned::{OwnedStructure, OwnedTable, OwnedValue};
+use std::{marker::PhantomData, ops::Deref, os::raw::c_int, time::Duration};
 use sapnwrfc_sys::{
-    self, RfcDestroyFunction, RfcDestroyFunctionDesc, RfcGetParameterDescByName, RfcInvoke,
-    RFC_CONNECTION_HANDLE, RFC_FUNCTION_DESC_HANDLE, RFC_FUNCTION_HANDLE, _RFC_RC,
+    self, RfcCreateFunction, RfcDestroyFunction, RfcDestroyFunctionDesc, RfcGetFunctionName,
+    RfcGetParameterCount, RfcGetParameterDescByIndex, RfcGetParameterDescByName, RfcInvoke,
+    RfcIsParameterActive, RfcSetParameterActive, RFC_ABAP_NAME, RFC_FUNCTION_DESC_HANDLE,
+    RFC_FUNCTION_HANDLE, RFC_PARAMETER_DESC, _RFCTYPE, _RFC_DIRECTION, _RFC_RC,
 };
 
+/// The connection of a function, either borrowed or owned by the function itself.
+#[derive(Debug)]
+enum ConnRef<'conn> {
+    Borrowed(&'conn RfcConnection),
+    Owned(RfcConnection),
+}
+
+impl Deref for ConnRef<'_> {
+    type Target = RfcConnection;
+
+    fn deref(&self) -> &RfcConnection {
+        match self {
+            Self::Borrowed(conn) => conn,
+            Self::Owned(conn) => conn,
+        }
+    }
+}
+
 /// A remote enabled RFC function module.
 #[derive(Debug)]
 pub struct RfcFunction<'conn> {
-    conn_handle: &'conn RFC_CONNECTION_HANDLE,
+    conn: ConnRef<'conn>,
     handle: RFC_FUNCTION_HANDLE,
     desc: RFC_FUNCTION_DESC_HANDLE,
+    /// Whether the description is released with the function, instead of by its own owner.
+    owns_desc: bool,
+    /// Whether the function is destroyed on drop, instead of by the SDK which lent it.
+    owns_handle: bool,
     data: RfcDataContainer,
 }
 
+/// A function owning its connection, which can be stored or moved to another thread freely.
+pub type OwnedRfcFunction = RfcFunction<'static>;
+
+impl OwnedRfcFunction {
+    pub(crate) fn new_owned(
+        conn: RfcConnection,
+        handle: RFC_FUNCTION_HANDLE,
+        desc: RFC_FUNCTION_DESC_HANDLE,
+    ) -> Self {
+        Self {
+            conn: ConnRef::Owned(conn),
+            handle,
+            desc,
+            owns_desc: true,
+            owns_handle: true,
+            data: RfcDataContainer::new(handle),
+        }
+    }
+}
+
 impl<'conn> RfcFunction<'conn> {
     pub(crate) fn new(
-        conn_handle: &'conn RFC_CONNECTION_HANDLE,
+        conn: &'conn RfcConnection,
         handle: RFC_FUNCTION_HANDLE,
         desc: RFC_FUNCTION_DESC_HANDLE,
     ) -> Self {
         Self {
-            conn_handle,
+            conn: ConnRef::Borrowed(conn),
             handle,
             desc,
+            owns_desc: true,
+            owns_handle: true,
             data: RfcDataContainer::new(handle),
         }
     }
 
-    pub fn invoke(&self) -> Result<()> {
+    /// Wrap a function created from a description it does not own, which must outlive it.
+    fn with_borrowed_desc(
+        conn: &'conn RfcConnection,
+        handle: RFC_FUNCTION_HANDLE,
+        desc: RFC_FUNCTION_DESC_HANDLE,
+    ) -> Self {
+        let mut func = Self::new(conn, handle, desc);
+        func.owns_desc = false;
+        func
+    }
+
+    /// Wrap a function and description owned by the SDK, like the ones of a server call.
+    pub(crate) fn borrowed(
+        conn: &'conn RfcConnection,
+        handle: RFC_FUNCTION_HANDLE,
+        desc: RFC_FUNCTION_DESC_HANDLE,
+    ) -> Self {
+        let mut func = Self::with_borrowed_desc(conn, handle, desc);
+        func.owns_handle = false;
+        func
+    }
+
+    pub(crate) fn handle(&self) -> RFC_FUNCTION_HANDLE {
+        self.handle
+    }
+
+    pub(crate) fn data(&self) -> &RfcDataContainer {
+        &self.data
+    }
+
+    pub(crate) fn data_mut(&mut self) -> &mut RfcDataContainer {
+        &mut self.data
+    }
+
+    fn parameter_desc(&self, name: &RFC_ABAP_NAME) -> Result<RFC_PARAMETER_DESC> {
+        let mut desc = RFC_PARAMETER_DESC::default();
         unsafe {
-            check_rc_ok!(RfcInvoke(*self.conn_handle, self.handle));
+            check_rc_ok!(RfcGetParameterDescByName(
+                self.desc,
+                name.as_ptr(),
+                &mut desc
+            ));
+        }
+        Ok(desc)
+    }
+
+    /// Get a parameter to provide before invoking the function.
+    ///
+    /// Fails for export parameters, which cannot be provided.
+    pub fn import_parameter(&mut self, name: &str) -> Result<RfcParamMut<'_, 'conn>> {
+        let desc = self.parameter_desc(&uc::from_str_to_abap_name(name)?)?;
+        RfcParamMut::new(self, &desc)
+    }
+
+    /// Get a parameter to read a result of the function.
+    ///
+    /// Fails for import parameters, which do not hold results.
+    pub fn export_parameter(&self, name: &str) -> Result<RfcParamRef<'_, 'conn>> {
+        let desc = self.parameter_desc(&uc::from_str_to_abap_name(name)?)?;
+        RfcParamRef::new(self, &desc)
+    }
+
+    /// Get the number of parameters of the function.
+    pub fn parameter_count(&self) -> Result<u32> {
+        parameter_count(self.desc)
+    }
+
+    /// Get the description of a parameter by its position, an index out of range is an error.
+    pub fn get_parameter_by_index(&self, index: u32) -> Result<RfcParameterDesc> {
+        parameter_by_index(self.desc, index)
+    }
+
+    /// Iterate over the descriptions of all the parameters, in declaration order.
+    pub fn parameters(&self) -> Result<Parameters<'_, 'conn>> {
+        Parameters::new(self.desc)
+    }
+
+    /// Include or exclude a parameter from the call.
+    ///
+    /// Inactive parameters are not sent to or returned from the backend, which saves the transfer
+    /// of large tables which are not needed.
+    pub fn set_parameter_active(&mut self, name: &str, active: bool) -> Result<()> {
+        let name = uc::from_str_to_abap_name(name)?;
+        unsafe {
+            check_rc_ok!(RfcSetParameterActive(
+                self.handle,
+                name.as_ptr(),
+                active as c_int
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check whether a parameter is included in the call.
+    pub fn is_parameter_active(&self, name: &str) -> Result<bool> {
+        let name = uc::from_str_to_abap_name(name)?;
+        let mut active = 0;
+        unsafe {
+            check_rc_ok!(RfcIsParameterActive(
+                self.handle,
+                name.as_ptr(),
+                &mut active
+            ));
+        }
+        Ok(active != 0)
+    }
+
+    /// Get the name of the function module.
+    pub fn name(&self) -> String {
+        function_name(self.desc)
+    }
+
+    /// Invoke the function within the default call timeout of the connection, if any.
+    pub fn invoke(&self) -> Result<()> {
+        self.invoke_with_timeout(self.conn.default_call_timeout())
+    }
+
+    /// Invoke the function, cancelling the call if it takes longer than the timeout.
+    ///
+    /// The timeout replaces the default call timeout of the connection, `None` lets the call run
+    /// without limit. A cancelled call breaks the connection.
+    pub fn invoke_with_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        match timeout {
+            Some(timeout) => {
+                let what = format!("Call of function {}", self.name());
+                self.conn
+                    .cancel_after(timeout, &what, || self.invoke_unbounded())
+            }
+            None => self.invoke_unbounded(),
+        }
+    }
+
+    fn invoke_unbounded(&self) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe { RfcInvoke(self.conn.handle(), self.handle, err_info.as_mut_ptr()) })
+        {
+            return Err(self.conn.track_error(err_info).into());
         }
         Ok(())
     }
 
+    /// Invoke the function and fail on the error messages reported in its `RETURN` parameter.
+    ///
+    /// Messages of type `E` and `A` are failures, as reported by BAPIs. The error holds the class,
+    /// number and variables of the first one, its key is the class followed by the number.
+    /// Functions without a `RETURN` parameter are only invoked.
+    pub fn invoke_checked(&self) -> Result<()> {
+        self.invoke()?;
+        let name = uc::from_str_to_abap_name("RETURN")?;
+        let desc = match self.parameter_desc(&name) {
+            Ok(desc) if desc.direction != _RFC_DIRECTION::RFC_IMPORT => desc,
+            _ => return Ok(()),
+        };
+        let failure = |message: &RfcStructure| -> Result<Option<RfcErrorInfo>> {
+            let msg_type = message.get_string("TYPE")?;
+            if !matches!(msg_type.trim(), "E" | "A") {
+                return Ok(None);
+            }
+            // Older return types lack some of the fields of BAPIRET2
+            let text = |field: &str| -> Result<String> {
+                if message.field_desc_by_name(field).is_err() {
+                    return Ok(String::new());
+                }
+                Ok(message.get_string(field)?.trim_end().to_owned())
+            };
+            let vars = [
+                text("MESSAGE_V1")?,
+                text("MESSAGE_V2")?,
+                text("MESSAGE_V3")?,
+                text("MESSAGE_V4")?,
+            ];
+            Ok(Some(RfcErrorInfo::abap_message(
+                msg_type.trim(),
+                &text("ID")?,
+                &text("NUMBER")?,
+                [&vars[0], &vars[1], &vars[2], &vars[3]],
+                &text("MESSAGE")?,
+            )))
+        };
+        let failure = match desc.type_ {
+            _RFCTYPE::RFCTYPE_STRUCTURE => failure(&self.data.get_structure(&name)?)?,
+            _RFCTYPE::RFCTYPE_TABLE => {
+                let table = self.data.get_table(&name)?;
+                let mut found = None;
+                for index in 0..table.row_count()? {
+                    found = failure(&table.get_row(index)?)?;
+                    if found.is_some() {
+                        break;
+                    }
+                }
+                found
+            }
+            _ => None,
+        };
+        match failure {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Invoke the function with [`invoke_checked`](Self::invoke_checked) and deserialize its
+    /// export, changing and table parameters into a value.
+    ///
+    /// Parameters map to the fields of the value by name, tables to sequences of rows.
+    #[cfg(feature = "serde")]
+    pub fn invoke_returning<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        self.invoke_checked()?;
+        let exports = self.exports()?;
+        Ok(crate::de::from_fields(&exports)?)
+    }
+
+    /// Copy the values of all the parameters which are not import only.
+    #[cfg(feature = "serde")]
+    fn exports(&self) -> Result<Vec<(String, OwnedValue)>> {
+        let mut count = 0;
+        unsafe {
+            check_rc_ok!(RfcGetParameterCount(self.desc, &mut count));
+        }
+        let mut exports = Vec::new();
+        for index in 0..count {
+            let mut desc = RFC_PARAMETER_DESC::default();
+            unsafe {
+                check_rc_ok!(RfcGetParameterDescByIndex(self.desc, index, &mut desc));
+            }
+            if desc.direction == _RFC_DIRECTION::RFC_IMPORT {
+                continue;
+            }
+            let value = match desc.type_ {
+                _RFCTYPE::RFCTYPE_STRUCTURE => OwnedValue::Structure(
+                    OwnedStructure::from_structure(&self.data.get_structure(&desc.name)?)?,
+                ),
+                _RFCTYPE::RFCTYPE_TABLE => {
+                    OwnedValue::Table(OwnedTable::from_table(&self.data.get_table(&desc.name)?)?)
+                }
+                _ => OwnedValue::Text(self.data.get_text(&desc.name, &(&desc).into())?),
+            };
+            exports.push((uc::to_string_truncate(&desc.name)?, value));
+        }
+        Ok(exports)
+    }
+
+    /// Invoke the function, refusing to proceed if any value was truncated, also in the fields of
+    /// structures and table rows.
+    pub fn invoke_validated(&self) -> Result<()> {
+        if let Some(outcome) = self.truncations().first() {
+            return Err(RfcError::custom(&format!(
+                "Value for {} was truncated from {} to {} characters",
+                outcome.field(),
+                outcome.provided(),
+                outcome.written()
+            )));
+        }
+        self.invoke()
+    }
+
+    /// Reset a parameter to the initial value of its type.
+    ///
+    /// Table parameters are emptied and structure parameters have all their fields reset.
+    pub fn clear_parameter(&mut self, name: &str) -> Result<()> {
+        let name = &uc::from_str_to_abap_name(name)?;
+        let desc = self.data_desc(name)?;
+        self.data.clear(name, &desc)
+    }
+
+    /// Write the declared default value into every optional import parameter still initial.
+    ///
+    /// Defaults given as ABAP expressions, like `SY-DATUM`, cannot be evaluated, the parameters
+    /// having one are reported as skipped.
+    pub fn apply_defaults(&mut self) -> Result<AppliedDefaults> {
+        let mut count = 0;
+        unsafe {
+            check_rc_ok!(RfcGetParameterCount(self.desc, &mut count));
+        }
+        let mut outcome = AppliedDefaults::default();
+        for index in 0..count {
+            let mut desc = RFC_PARAMETER_DESC::default();
+            unsafe {
+                check_rc_ok!(RfcGetParameterDescByIndex(self.desc, index, &mut desc));
+            }
+            if desc.direction != _RFC_DIRECTION::RFC_IMPORT
+                || desc.optional == 0
+                || matches!(
+                    desc.type_,
+                    _RFCTYPE::RFCTYPE_STRUCTURE | _RFCTYPE::RFCTYPE_TABLE
+                )
+            {
+                continue;
+            }
+            let name = uc::to_string_truncate(&desc.name)?;
+            let default = uc::to_string_truncate(&desc.defaultValue)?;
+            let default = default.trim();
+            if default.is_empty() || !is_initial(&self.data.get_string(&desc.name)?, desc.type_) {
+                continue;
+            }
+            match parse_default(default) {
+                Some(value) => {
+                    self.data.set_string(&desc.name, &value)?;
+                    outcome.applied.push(name);
+                }
+                None => outcome.skipped.push((name, default.to_owned())),
+            }
+        }
+        Ok(outcome)
+    }
+
     rfc_data_delegates!(self.data, |name, desc| {
         unsafe {
             check_rc_ok!(RfcGetParameterDescByName(
@@ -46,26 +397,198 @@ impl<'conn> RfcFunction<'conn> {
                 &mut desc
             ));
         }
-        println!("{:?}", &desc);
     });
 }
 
 impl Drop for RfcFunction<'_> {
     fn drop(&mut self) {
+        // The connection of an owned function is only released with the fields, after this.
         let mut err_info = RfcErrorInfo::new();
         unsafe {
-            if is_rc_err!(RfcDestroyFunction(self.handle, err_info.as_mut_ptr())) {
+            if self.owns_handle
+                && is_rc_err!(RfcDestroyFunction(self.handle, err_info.as_mut_ptr()))
+            {
                 log::warn!("Function destroy failed: {}", err_info);
             }
-
-            let rc = RfcDestroyFunctionDesc(self.desc, err_info.as_mut_ptr());
-            // Call to RfcDestroyFunctionDesc fails with RFC_ILLEGAL_STATE when the function
-            // description is held in a cache. The can safely be silenced for this case.
-            if is_rc_err!(rc) && rc != _RFC_RC::RFC_ILLEGAL_STATE {
-                log::warn!("Function description destroy failed: {}", err_info);
+            if self.owns_desc {
+                destroy_function_desc(self.desc);
             }
         }
     }
 }
 
 unsafe impl Send for RfcFunction<'_> {}
+
+/// Destroy a function description, unless it is held in the cache of the SDK.
+unsafe fn destroy_function_desc(desc: RFC_FUNCTION_DESC_HANDLE) {
+    let mut err_info = RfcErrorInfo::new();
+    let rc = RfcDestroyFunctionDesc(desc, err_info.as_mut_ptr());
+    // Call to RfcDestroyFunctionDesc fails with RFC_ILLEGAL_STATE when the function
+    // description is held in a cache. The can safely be silenced for this case.
+    if is_rc_err!(rc) && rc != _RFC_RC::RFC_ILLEGAL_STATE {
+        log::warn!("Function description destroy failed: {}", err_info);
+    }
+}
+
+fn function_name(desc: RFC_FUNCTION_DESC_HANDLE) -> String {
+    let mut err_info = RfcErrorInfo::new();
+    let mut uc_name: RFC_ABAP_NAME = Default::default();
+    unsafe {
+        assert_rc_ok!(
+            RfcGetFunctionName(desc, uc_name.as_mut_ptr(), err_info.as_mut_ptr()),
+            "Unexpected failure with RfcGetFunctionName"
+        );
+    }
+    uc::to_string_truncate(&uc_name).expect("Unexpected string decode failure with name")
+}
+
+fn parameter_count(desc: RFC_FUNCTION_DESC_HANDLE) -> Result<u32> {
+    let mut count = 0;
+    unsafe {
+        check_rc_ok!(RfcGetParameterCount(desc, &mut count));
+    }
+    Ok(count)
+}
+
+fn parameter_by_index(desc: RFC_FUNCTION_DESC_HANDLE, index: u32) -> Result<RfcParameterDesc> {
+    let mut param_desc = RFC_PARAMETER_DESC::default();
+    unsafe {
+        check_rc_ok!(RfcGetParameterDescByIndex(desc, index, &mut param_desc));
+    }
+    RfcParameterDesc::from_desc(&param_desc)
+}
+
+/// The parameters written by [`RfcFunction::apply_defaults`] and the ones it had to skip.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AppliedDefaults {
+    /// The names of the parameters which were written.
+    pub applied: Vec<String>,
+    /// The names of the parameters left initial with their default expression, like `SY-DATUM`.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// The description of a remote enabled function module, its parameters without any values.
+///
+/// Functions created from a description skip the lookup of the description on the backend.
+#[derive(Debug)]
+pub struct RfcFunctionDesc<'conn> {
+    conn: &'conn RfcConnection,
+    desc: RFC_FUNCTION_DESC_HANDLE,
+}
+
+impl<'conn> RfcFunctionDesc<'conn> {
+    pub(crate) fn new(conn: &'conn RfcConnection, desc: RFC_FUNCTION_DESC_HANDLE) -> Self {
+        Self { conn, desc }
+    }
+
+    /// Get the name of the function module.
+    pub fn name(&self) -> String {
+        function_name(self.desc)
+    }
+
+    /// Get the number of parameters of the function.
+    pub fn parameter_count(&self) -> Result<u32> {
+        parameter_count(self.desc)
+    }
+
+    /// Get the description of a parameter by its position, an index out of range is an error.
+    pub fn get_parameter_by_index(&self, index: u32) -> Result<RfcParameterDesc> {
+        parameter_by_index(self.desc, index)
+    }
+
+    /// Iterate over the descriptions of all the parameters, in declaration order.
+    pub fn parameters(&self) -> Result<Parameters<'_, 'conn>> {
+        Parameters::new(self.desc)
+    }
+
+    /// Create a function to call with this description.
+    ///
+    /// The function borrows the description, which is only released once no function uses it.
+    pub fn create_function(&self) -> Result<RfcFunction<'_>> {
+        let mut err_info = RfcErrorInfo::new();
+        let func = unsafe { RfcCreateFunction(self.desc, err_info.as_mut_ptr()) };
+        if func.is_null() {
+            return Err(err_info.into());
+        }
+        Ok(RfcFunction::with_borrowed_desc(self.conn, func, self.desc))
+    }
+}
+
+impl Drop for RfcFunctionDesc<'_> {
+    fn drop(&mut self) {
+        unsafe { destroy_function_desc(self.desc) }
+    }
+}
+
+unsafe impl Send for RfcFunctionDesc<'_> {}
+
+/// An iterator over the parameter descriptions of a function.
+pub struct Parameters<'func, 'conn> {
+    desc: RFC_FUNCTION_DESC_HANDLE,
+    index: u32,
+    count: u32,
+    _func: PhantomData<&'func &'conn RfcConnection>,
+}
+
+impl Parameters<'_, '_> {
+    fn new(desc: RFC_FUNCTION_DESC_HANDLE) -> Result<Self> {
+        Ok(Self {
+            desc,
+            index: 0,
+            count: parameter_count(desc)?,
+            _func: PhantomData,
+        })
+    }
+}
+
+impl Iterator for Parameters<'_, '_> {
+    type Item = Result<RfcParameterDesc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+        let desc = parameter_by_index(self.desc, self.index);
+        self.index += 1;
+        Some(desc)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Parse an ABAP default value literal, expressions give `None`.
+fn parse_default(default: &str) -> Option<String> {
+    if let Some(quote) = default.chars().next().filter(|&c| c == '\'' || c == '`') {
+        if default.len() >= 2 && default.ends_with(quote) {
+            let literal = &default[1..default.len() - 1];
+            return Some(literal.replace(&format!("{0}{0}", quote), &quote.to_string()));
+        }
+    }
+    if default.eq_ignore_ascii_case("SPACE") {
+        return Some(" ".to_owned());
+    }
+    if default.parse::<f64>().is_ok() {
+        return Some(default.to_owned());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_default_test() {
+        assert_eq!(parse_default("'|'").as_deref(), Some("|"));
+        assert_eq!(parse_default("'It''s'").as_deref(), Some("It's"));
+        assert_eq!(parse_default("SPACE").as_deref(), Some(" "));
+        assert_eq!(parse_default("42").as_deref(), Some("42"));
+
+        // Expressions are skipped by apply_defaults
+        assert_eq!(parse_default("SY-DATUM"), None);
+        assert_eq!(parse_default("SY-UNAME"), None);
+    }
+}
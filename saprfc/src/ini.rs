@@ -0,0 +1,172 @@
+use crate::error::{join_names, Result, RfcError};
+use sapnwrfc_sys::_RFC_RC;
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+const INI_FILE_NAME: &str = "sapnwrfc.ini";
+
+/// The directory of the ini file given to the SDK, if any.
+static INI_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// List the destinations defined in the `sapnwrfc.ini` file the SDK would use.
+pub fn list_ini_destinations() -> Result<Vec<String>> {
+    let path = locate_ini()?;
+    read_destinations(&path)
+}
+
+/// Check that a destination is defined before trying to connect with it.
+pub(crate) fn check_destination(name: &str) -> Result<()> {
+    let path = locate_ini()?;
+    let destinations = read_destinations(&path)?;
+    if destinations
+        .iter()
+        .any(|dest| dest.eq_ignore_ascii_case(name))
+    {
+        return Ok(());
+    }
+    Err(RfcError::custom_with_code(
+        _RFC_RC::RFC_INVALID_PARAMETER,
+        &format!(
+            "Destination {} is not defined in {}, available destinations are: {}",
+            name,
+            shorten_path(&path),
+            join_names(&destinations)
+        ),
+    ))
+}
+
+/// Show a path for an error message, keeping only the end of a long one.
+fn shorten_path(path: &Path) -> String {
+    const MAX_PATH_CHARS: usize = 120;
+    let shown = path.display().to_string();
+    let count = shown.chars().count();
+    if count <= MAX_PATH_CHARS {
+        return shown;
+    }
+    let tail: String = shown.chars().skip(count - MAX_PATH_CHARS).collect();
+    format!("…{}", tail)
+}
+
+/// Remember the directory of the ini file given to the SDK, to look it up in the same place.
+pub(crate) fn set_ini_dir(dir: &Path) {
+    *INI_DIR.write().unwrap() = Some(dir.to_owned());
+}
+
+fn locate_ini() -> Result<PathBuf> {
+    let cwd = env::current_dir().unwrap_or_default();
+    let ini_dir = INI_DIR.read().unwrap().clone();
+    find_ini(env::var_os("RFC_INI"), ini_dir.as_deref(), &cwd).ok_or_else(|| {
+        RfcError::custom_with_code(
+            _RFC_RC::RFC_NOT_FOUND,
+            &format!(
+                "No {} file found, set RFC_INI or place it in {}",
+                INI_FILE_NAME,
+                shorten_path(&cwd)
+            ),
+        )
+    })
+}
+
+/// Find the ini file in the same order as the SDK: the `RFC_INI` variable, the configured ini
+/// directory and then the working directory.
+fn find_ini(rfc_ini: Option<OsString>, ini_dir: Option<&Path>, cwd: &Path) -> Option<PathBuf> {
+    if let Some(path) = rfc_ini.map(PathBuf::from) {
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+    ini_dir
+        .into_iter()
+        .chain(Some(cwd))
+        .map(|dir| dir.join(INI_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn read_destinations(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| RfcError::custom(&format!("Could not read {}: {}", path.display(), err)))?;
+    Ok(parse_destinations(&content))
+}
+
+/// Get the names of the destinations, each one starts with a `DEST=<name>` line.
+fn parse_destinations(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("DEST"))
+        .map(|(_, value)| value.trim().to_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn ini_parse_destinations() {
+        let content = "\
+# Comment with DEST=COMMENTED
+DEST=TEST
+USER=tester
+ASHOST=localhost
+
+  dest = PROD
+MSHOST=msg.example.com
+";
+        assert_eq!(parse_destinations(content), vec!["TEST", "PROD"]);
+        assert!(parse_destinations("").is_empty());
+    }
+
+    #[test]
+    fn ini_shorten_path() {
+        assert_eq!(
+            shorten_path(Path::new("/etc/sapnwrfc.ini")),
+            "/etc/sapnwrfc.ini"
+        );
+        let long = Path::new("/deep").join("d".repeat(300)).join(INI_FILE_NAME);
+        let shown = shorten_path(&long);
+        assert_eq!(shown.chars().count(), 121);
+        assert!(shown.starts_with('…'));
+        assert!(shown.ends_with("/sapnwrfc.ini"));
+    }
+
+    #[test]
+    fn ini_lookup_order() {
+        // The fixtures are removed with the directories at the end of the test
+        let env_dir = TempDir::new().unwrap();
+        let env_ini = env_dir.path().join("custom.ini");
+        fs::write(&env_ini, "DEST=FROM_ENV\n").unwrap();
+        let conf_dir = TempDir::new().unwrap();
+        let conf_dir = conf_dir.path();
+        fs::write(conf_dir.join(INI_FILE_NAME), "DEST=FROM_CONF\n").unwrap();
+        let cwd = TempDir::new().unwrap();
+        let cwd = cwd.path();
+        fs::write(cwd.join(INI_FILE_NAME), "DEST=FROM_CWD\n").unwrap();
+        let empty = TempDir::new().unwrap();
+        let empty = empty.path();
+
+        assert_eq!(
+            find_ini(Some(env_ini.clone().into()), Some(conf_dir), cwd),
+            Some(env_ini.clone())
+        );
+        assert_eq!(
+            find_ini(None, Some(conf_dir), cwd),
+            Some(conf_dir.join(INI_FILE_NAME))
+        );
+        assert_eq!(
+            find_ini(Some(empty.join("missing.ini").into()), Some(empty), cwd),
+            Some(cwd.join(INI_FILE_NAME))
+        );
+        assert_eq!(find_ini(None, None, empty), None);
+
+        assert_eq!(read_destinations(&env_ini).unwrap(), vec!["FROM_ENV"]);
+    }
+}
@@ -1,20 +1,73 @@
 pub mod connection;
 mod data_container;
+#[cfg(feature = "serde")]
+mod de;
+mod decimal;
+mod display;
 pub mod error;
+pub mod events;
 pub mod function;
+mod ini;
+pub mod owned;
+pub mod parameter;
+pub mod sdk;
+#[cfg(feature = "serde")]
+mod ser;
+pub mod server;
+pub mod session;
 pub mod structure;
 pub mod table;
+pub mod throughput;
+pub mod transaction;
+pub mod types;
 mod uc;
+pub mod value;
+
+#[cfg(feature = "actor")]
+pub mod actor;
 
 #[cfg(feature = "deadpool")]
 pub mod pool;
 
+#[cfg(all(feature = "axum", feature = "deadpool"))]
+pub mod extract;
+
+#[cfg(feature = "rate-limit")]
+pub mod limit;
+
+#[cfg(feature = "doctor")]
+pub mod doctor;
+
+#[cfg(feature = "macros")]
+pub use saprfc_macros::rfc_handler;
+
+// Lets the code generated by the macros refer to this crate by name in its own tests
+#[cfg(all(test, feature = "macros"))]
+extern crate self as saprfc;
+
 pub use crate::{
-    connection::{RfcConnection, RfcConnectionBuilder},
-    error::RfcErrorInfo,
-    function::RfcFunction,
+    connection::{
+        ConnectionAttributes, ConnectionCancelHandle, HealthReport, RfcConnection,
+        RfcConnectionBuilder,
+    },
+    data_container::{SetOutcome, XstringReader},
+    display::DisplayOptions,
+    error::{
+        GetFunctionError, GetFunctionErrorKind, RfcError, RfcErrorCode, RfcErrorGroup,
+        RfcErrorInfo, RowError,
+    },
+    function::{AppliedDefaults, OwnedRfcFunction, RfcFunction, RfcFunctionDesc},
+    ini::list_ini_destinations,
+    owned::{OwnedStructure, OwnedTable, OwnedValue},
+    parameter::{RfcParamMut, RfcParamRef, RfcParameterDesc},
+    server::{RfcServer, RfcServerBuilder},
+    session::StatefulSession,
     structure::RfcStructure,
-    table::RfcTable,
+    table::{DuplicateKeys, RfcTable, UnknownFields},
+    throughput::RfcThroughput,
+    transaction::RfcTransaction,
+    types::{AsFieldName, FieldName, RfcDirection, RfcFieldDesc, RfcType, RfcTypeDesc},
+    value::Value,
 };
 
 #[allow(clippy::single_component_path_imports)]
@@ -30,7 +83,7 @@ mod macros {
     macro_rules! check_rc_ok {
         ($expr:expr , $error:ident) => {
             if crate::macros::is_rc_err!($expr) {
-                return Err($error);
+                return Err($error.into());
             }
         };
         ($fn:ident ( $($args:expr),+ ) ) => {
@@ -61,6 +114,32 @@ mod macros {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "macros")]
+    struct EchoText(String);
+
+    #[cfg(feature = "macros")]
+    impl server::FromFunction for EchoText {
+        fn from_function(func: &RfcFunction) -> error::Result<Self> {
+            Ok(Self(func.get_string("IMP")?))
+        }
+    }
+
+    #[cfg(feature = "macros")]
+    impl server::IntoFunction for EchoText {
+        fn into_function(self, func: &mut RfcFunction) -> error::Result<()> {
+            func.set_string("EXP", &self.0)
+        }
+    }
+
+    #[cfg(feature = "macros")]
+    #[rfc_handler("SCP_STRING_ECHO")]
+    fn shouting_echo(input: EchoText) -> error::Result<EchoText> {
+        if input.0 == "FAIL" {
+            return Err(RfcError::abap_exception("FAILED", "Asked to fail"));
+        }
+        Ok(EchoText(input.0.to_uppercase()))
+    }
+
     #[test]
     fn smoke_test() {
         // Simple invalid connection negative test
@@ -119,6 +198,2299 @@ mod tests {
             assert_eq!(expstruct.get_chars("RFCCHAR1").unwrap(), "X");
             assert_eq!(expstruct.get_chars("RFCCHAR2").unwrap(), "AB");
             assert_eq!(expstruct.get_chars("RFCCHAR4").unwrap(), "Fizz");
+            assert_eq!(expstruct.get_string("RFCCHAR4").unwrap(), "Fizz");
+            assert_eq!(expstruct.get_string("RFCCHAR2").unwrap(), "AB");
+        }
+
+        // Binary floating point values keep their precision
+        {
+            let func = conn.get_function("STFC_STRUCTURE").unwrap();
+
+            let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+            impstruct.set_float("RFCFLOAT", 1.0 / 3.0).unwrap();
+            assert!(impstruct.set_float("RFCCHAR4", 1.5).is_err());
+
+            func.invoke().unwrap();
+
+            let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+            assert_eq!(expstruct.get_float("RFCFLOAT").unwrap(), 1.0 / 3.0);
+
+            let param = func.export_parameter("RESPTEXT").unwrap();
+            assert!(!param.is_float());
+        }
+
+        // The i64 accessors work on every integer type
+        {
+            let func = conn.get_function("STFC_STRUCTURE").unwrap();
+
+            let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+            impstruct.set_i64("RFCINT1", 42).unwrap();
+            impstruct.set_i64("RFCINT4", -112357).unwrap();
+            // Values beyond the field are refused and leave the previous one in place
+            let err = impstruct.set_i64("RFCINT4", 1 << 40).unwrap_err();
+            assert_eq!(err.code(), RfcErrorCode::ConversionFailure);
+            assert!(err.message().contains("RFCINT4"));
+            assert!(impstruct
+                .set_i64("RFCINT4", i64::from(i32::MIN) - 1)
+                .is_err());
+            assert_eq!(impstruct.get_i64("RFCINT4").unwrap(), -112357);
+
+            func.invoke().unwrap();
+
+            let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+            assert_eq!(expstruct.get_i64("RFCINT1").unwrap(), 42);
+            assert_eq!(expstruct.get_i64("RFCINT4").unwrap(), -112357);
+            assert_eq!(expstruct.get_int("RFCINT4").unwrap(), -112357);
+        }
+
+        // Binary values are padded to fixed-length fields
+        {
+            let func = conn.get_function("STFC_STRUCTURE").unwrap();
+
+            let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+            impstruct.set_bytes("RFCHEX3", &[0xCA, 0xFE]).unwrap();
+            assert!(impstruct.set_bytes("RFCHEX3", &[1, 2, 3, 4]).is_err());
+            assert!(impstruct.set_bytes("RFCINT4", &[1]).is_err());
+
+            func.invoke().unwrap();
+
+            let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+            assert_eq!(expstruct.get_bytes("RFCHEX3").unwrap(), [0xCA, 0xFE, 0x00]);
+        }
+
+        // Text accessors work the same on fixed-length and variable-length fields
+        {
+            let func = conn.get_function("STFC_STRUCTURE").unwrap();
+
+            let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+            impstruct.set_string("RFCCHAR4", "Ab").unwrap();
+            assert_eq!(impstruct.get_chars("RFCCHAR4").unwrap(), "Ab  ");
+            assert_eq!(impstruct.get_string("RFCCHAR4").unwrap(), "Ab");
+            assert!(impstruct.set_string("RFCCHAR2", "ABCD").is_err());
+        }
+
+        // Truncation reporting on fixed-length fields
+        {
+            let func = conn.get_function("STFC_STRUCTURE").unwrap();
+
+            let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+            let outcome = impstruct.set_chars("RFCCHAR4", "Buzz").unwrap();
+            assert!(!outcome.is_truncated());
+            let outcome = impstruct.set_chars("RFCCHAR2", "ABCD").unwrap();
+            assert!(outcome.is_truncated());
+            assert_eq!(outcome.provided(), 4);
+            assert_eq!(outcome.written(), 2);
+            assert_eq!(impstruct.truncations().len(), 1);
+            drop(impstruct);
+
+            // Truncations in structure fields and table rows are seen by the function
+            let err = func.invoke_validated().unwrap_err();
+            assert!(err.message().contains("IMPORTSTRUCT.RFCCHAR2"));
+            let mut table = func.get_table("RFCTABLE").unwrap();
+            table
+                .append_row()
+                .unwrap()
+                .set_chars("RFCCHAR2", "XYZ")
+                .unwrap();
+            drop(table);
+            let fields: Vec<_> = func
+                .truncations()
+                .iter()
+                .map(|t| t.field().to_owned())
+                .collect();
+            assert_eq!(fields, ["IMPORTSTRUCT.RFCCHAR2", "RFCTABLE[0].RFCCHAR2"]);
+
+            // Writing a value which fits clears the truncation of the field
+            func.get_structure("IMPORTSTRUCT")
+                .unwrap()
+                .set_chars("RFCCHAR2", "AB")
+                .unwrap();
+            func.get_table("RFCTABLE")
+                .unwrap()
+                .get_row(0)
+                .unwrap()
+                .set_chars("RFCCHAR2", "XY")
+                .unwrap();
+            func.invoke_validated().unwrap();
+        }
+
+        // Binary reads into a buffer and through a reader must agree
+        {
+            use std::io::Read;
+
+            let func = conn.get_function("STFC_STRUCTURE").unwrap();
+            func.invoke().unwrap();
+
+            let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+            let mut buf = [0; 16];
+            let len = expstruct.get_bytes_into("RFCHEX3", &mut buf).unwrap();
+            assert_eq!(len, 3);
+            assert!(expstruct.get_bytes_into("RFCHEX3", &mut [0; 2]).is_err());
+
+            let mut streamed = Vec::new();
+            expstruct
+                .xstring_reader("RFCHEX3")
+                .unwrap()
+                .read_to_end(&mut streamed)
+                .unwrap();
+            assert_eq!(&buf[..len], &streamed[..]);
+        }
+
+        // Cleared fields and parameters must reach the backend as initial values
+        {
+            let mut func = conn.get_function("STFC_STRUCTURE").unwrap();
+
+            let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+            impstruct.set_int("RFCINT4", 112357).unwrap();
+            impstruct.set_chars("RFCCHAR4", "Fizz").unwrap();
+            impstruct.clear_field("RFCINT4").unwrap();
+
+            func.invoke().unwrap();
+
+            let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+            assert_eq!(expstruct.get_int("RFCINT4").unwrap(), 0);
+            assert_eq!(expstruct.get_chars("RFCCHAR4").unwrap(), "Fizz");
+
+            func.clear_parameter("IMPORTSTRUCT").unwrap();
+            func.invoke().unwrap();
+
+            let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+            assert_eq!(expstruct.get_chars("RFCCHAR4").unwrap(), "    ");
+        }
+    }
+
+    #[test]
+    fn non_ascii_string_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let mut func = conn.get_function("SCP_STRING_ECHO").unwrap();
+        for value in ["Grüße", "日本語", "Smile 😀", ""] {
+            func.set_string("IMP", value).unwrap();
+            func.invoke().unwrap();
+            assert_eq!(func.get_string("EXP").unwrap(), value);
+        }
+
+        // Character fields count code units, not UTF-8 bytes
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let outcome = func
+            .get_structure("IMPORTSTRUCT")
+            .unwrap()
+            .set_chars("RFCCHAR4", "Grüß")
+            .unwrap();
+        assert!(!outcome.is_truncated());
+        func.invoke().unwrap();
+        let echo = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(echo.get_chars("RFCCHAR4").unwrap(), "Grüß");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn date_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 3, 14).unwrap();
+
+        let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+        impstruct.set_date("RFCDATE", date).unwrap();
+        // Character fields not exactly 8 long cannot carry a date
+        assert!(impstruct.set_date("RFCCHAR4", date).is_err());
+        // Neither can integer fields
+        assert!(impstruct.set_date("RFCINT4", date).is_err());
+
+        func.invoke().unwrap();
+
+        let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(expstruct.get_date("RFCDATE").unwrap(), Some(date));
+        assert!(expstruct.get_date("RFCINT4").is_err());
+
+        // Table rows share the same date accessors as structures
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        {
+            let mut table = func.get_table("RFCTABLE").unwrap();
+            let mut row = table.append_row().unwrap();
+            row.set_date("RFCDATE", date).unwrap();
+        }
+
+        func.invoke().unwrap();
+
+        let table = func.get_table("RFCTABLE").unwrap();
+        let row = table.get_first_row().unwrap();
+        assert_eq!(row.get_date("RFCDATE").unwrap(), Some(date));
+
+        // Character fields of length 8 hold dates as YYYYMMDD
+        let mut info = conn
+            .get_type_desc("RFCSI")
+            .unwrap()
+            .create_structure()
+            .unwrap();
+        info.set_date("RFCDATABS", date).unwrap();
+        assert_eq!(info.get_string("RFCDATABS").unwrap(), "20210314");
+        assert_eq!(info.get_date("RFCDATABS").unwrap(), Some(date));
+        info.set_chars("RFCDATABS", "").unwrap();
+        assert_eq!(info.get_date("RFCDATABS").unwrap(), None);
+        info.set_chars("RFCDATABS", "NOT_DATE").unwrap();
+        assert!(info.get_date("RFCDATABS").is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn time_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let time = chrono::NaiveTime::from_hms_opt(13, 37, 42).unwrap();
+
+        let mut impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+        impstruct.set_time("RFCTIME", time).unwrap();
+        assert!(impstruct.set_time("RFCCHAR4", time).is_err());
+        assert!(impstruct.set_time("RFCDATE", time).is_err());
+
+        func.invoke().unwrap();
+
+        let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(expstruct.get_time("RFCTIME").unwrap(), Some(time));
+
+        // Fields left unset come back as zeros and are read as the initial value
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        func.invoke().unwrap();
+
+        let expstruct = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(expstruct.get_time("RFCTIME").unwrap(), None);
+        assert_eq!(expstruct.get_date("RFCDATE").unwrap(), None);
+    }
+
+    #[test]
+    fn pretty_table_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let opts = table::PrettyOptions::new()
+            .set_max_width(10)
+            .set_max_rows(Some(2))
+            .set_columns(vec!["RFCINT4", "RFCCHAR4", "RFCDATA1"]);
+
+        assert_eq!(
+            func.get_table("RFCTABLE")
+                .unwrap()
+                .to_pretty_string(&opts)
+                .unwrap(),
+            "\
++---------+----------+----------+
+| RFCINT4 | RFCCHAR4 | RFCDATA1 |
++---------+----------+----------+
+"
+        );
+
+        for &(int, chars, data) in &[
+            (1, "A", "Short"),
+            (22, "BB", "A much longer text"),
+            (333, "CCCC", "Third"),
+        ] {
+            let mut table = func.get_table("RFCTABLE").unwrap();
+            let mut row = table.append_row().unwrap();
+            row.set_int("RFCINT4", int).unwrap();
+            row.set_chars("RFCCHAR4", chars).unwrap();
+            row.set_chars("RFCDATA1", data).unwrap();
+        }
+
+        assert_eq!(
+            func.get_table("RFCTABLE")
+                .unwrap()
+                .to_pretty_string(&opts)
+                .unwrap(),
+            "\
++---------+----------+------------+
+| RFCINT4 | RFCCHAR4 | RFCDATA1   |
++---------+----------+------------+
+| 1       | A        | Short      |
+| 22      | BB       | A much lo… |
++---------+----------+------------+
+… and 1 more rows
+"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        {
+            let mut is = func.get_structure("IMPORTSTRUCT").unwrap();
+            is.set_int("RFCINT4", 4711).unwrap();
+            is.set_chars("RFCCHAR4", "SNAP").unwrap();
+            is.set_string("RFCDATA1", "Plant master data").unwrap();
+        }
+        func.invoke().unwrap();
+
+        let echo =
+            OwnedStructure::from_structure(&func.get_structure("ECHOSTRUCT").unwrap()).unwrap();
+        let bytes = bincode::serialize(&echo).unwrap();
+        let reloaded: OwnedStructure = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(reloaded, echo);
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        reloaded
+            .apply_to(&mut func.get_structure("IMPORTSTRUCT").unwrap())
+            .unwrap();
+        func.invoke().unwrap();
+
+        let es = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(es.get_int("RFCINT4").unwrap(), 4711);
+        assert_eq!(es.get_chars("RFCCHAR4").unwrap(), "SNAP");
+        assert_eq!(OwnedStructure::from_structure(&es).unwrap(), echo);
+
+        // Snapshots of another format version are rejected
+        let mut bytes = bytes;
+        bytes[0] = 0xFF;
+        assert!(bincode::deserialize::<OwnedStructure>(&bytes).is_err());
+
+        // Table rows of the same type accept the snapshot as well
+        assert!(reloaded
+            .apply_to(&mut func.get_table("RFCTABLE").unwrap().append_row().unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn copy_rows_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let src = conn.get_function("STFC_STRUCTURE").unwrap();
+        {
+            let mut is = src.get_structure("IMPORTSTRUCT").unwrap();
+            is.set_int("RFCINT4", 42).unwrap();
+            is.set_chars("RFCCHAR4", "COPY").unwrap();
+        }
+        {
+            let mut table = src.get_table("RFCTABLE").unwrap();
+            let mut row = table.append_row().unwrap();
+            row.set_int("RFCINT4", 1).unwrap();
+            row.set_chars("RFCCHAR4", "ROW1").unwrap();
+        }
+        src.invoke().unwrap();
+
+        let dst = conn.get_function("STFC_STRUCTURE").unwrap();
+        {
+            let mut table = dst.get_table("RFCTABLE").unwrap();
+            table
+                .append_row_copy(&src.get_structure("ECHOSTRUCT").unwrap())
+                .unwrap();
+            table
+                .extend_from_table(&src.get_table("RFCTABLE").unwrap())
+                .unwrap();
+        }
+
+        let src_table = src.get_table("RFCTABLE").unwrap();
+        let dst_table = dst.get_table("RFCTABLE").unwrap();
+        assert_eq!(
+            dst_table.row_count().unwrap(),
+            src_table.row_count().unwrap() + 1
+        );
+
+        let row = dst_table.get_first_row().unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 42);
+        assert_eq!(row.get_chars("RFCCHAR4").unwrap(), "COPY");
+
+        let row = dst_table.get_row(1).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 1);
+        assert_eq!(row.get_chars("RFCCHAR4").unwrap(), "ROW1");
+
+        // Rows of a table fetched over another connection
+        let other = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let func = other.get_function("STFC_STRUCTURE").unwrap();
+        func.get_table("RFCTABLE")
+            .unwrap()
+            .extend_from_table(&src_table)
+            .unwrap();
+        assert_eq!(
+            func.get_table("RFCTABLE").unwrap().row_count().unwrap(),
+            src_table.row_count().unwrap()
+        );
+
+        // Rows of another type are refused and leave the table as it was
+        let mut messages = conn
+            .get_type_desc("BAPIRET2")
+            .unwrap()
+            .create_table()
+            .unwrap();
+        messages.append_row().unwrap();
+        let mut table = func.get_table("RFCTABLE").unwrap();
+        let count = table.row_count().unwrap();
+        assert!(table.extend_from_table(&messages).is_err());
+        assert_eq!(table.row_count().unwrap(), count);
+    }
+
+    #[test]
+    fn merge_from_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        // Two pages of results, each echoed with an extra row appended by the function
+        let call = |rows: &[(i32, &str)]| {
+            let func = conn.get_function("STFC_STRUCTURE").unwrap();
+            {
+                let mut table = func.get_table("RFCTABLE").unwrap();
+                for (int, chars) in rows {
+                    let mut row = table.append_row().unwrap();
+                    row.set_int("RFCINT4", *int).unwrap();
+                    row.set_chars("RFCCHAR4", chars).unwrap();
+                }
+            }
+            func.invoke().unwrap();
+            func
+        };
+        let first = call(&[(1, "A"), (2, "B")]);
+        let second = call(&[(3, "C"), (2, "B")]);
+        let first_count = first.get_table("RFCTABLE").unwrap().row_count().unwrap();
+        let second_count = second.get_table("RFCTABLE").unwrap().row_count().unwrap();
+
+        let mut merged = first.get_table("RFCTABLE").unwrap();
+        let added = merged
+            .merge_from(&second.get_table("RFCTABLE").unwrap())
+            .unwrap();
+        assert_eq!(added, second_count);
+        assert_eq!(merged.row_count().unwrap(), first_count + second_count);
+        let row = merged.get_row(first_count).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 3);
+        assert_eq!(row.get_chars("RFCCHAR4").unwrap(), "C");
+
+        // Only rows with a new key are added, including the rows merged just before
+        let third = call(&[(2, "B"), (4, "D")]);
+        let added = merged
+            .merge_from_dedup(
+                &third.get_table("RFCTABLE").unwrap(),
+                &["RFCINT4", "RFCCHAR4"],
+            )
+            .unwrap();
+        assert_eq!(added, 1);
+        let row = merged.get_last_row().unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 4);
+
+        // Rows of another type are refused with the first difference
+        let mut read = conn.get_function("RFC_READ_TABLE").unwrap();
+        read.set_chars("QUERY_TABLE", "T000").unwrap();
+        let err = merged
+            .merge_from(&read.get_table("FIELDS").unwrap())
+            .unwrap_err();
+        assert!(err.message().contains("field 1"), "{}", err);
+        assert_eq!(merged.row_count().unwrap(), first_count + second_count + 1);
+
+        // Tables of another connection are copied field by field
+        let other = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let func = other.get_function("STFC_STRUCTURE").unwrap();
+        let added = func
+            .get_table("RFCTABLE")
+            .unwrap()
+            .merge_from(&merged)
+            .unwrap();
+        assert_eq!(added, merged.row_count().unwrap());
+
+        // Rows of a type with the same layout but another name are copied field by field
+        let copy_type = renamed_type(&conn, "RFCTEST", "ZRFCTEST_COPY");
+        let mut copy = copy_type.create_table().unwrap();
+        assert_eq!(copy.name(), "ZRFCTEST_COPY");
+        let added = copy.merge_from(&merged).unwrap();
+        assert_eq!(added, merged.row_count().unwrap());
+        let row = copy.get_row(first_count).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 3);
+        assert_eq!(row.get_chars("RFCCHAR4").unwrap(), "C");
+        let row = copy.get_last_row().unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 4);
+    }
+
+    #[test]
+    fn parameters_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        assert_eq!(func.parameter_count().unwrap(), 4);
+
+        let params = func
+            .parameters()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(params.len(), 4);
+
+        let find = |name: &str| params.iter().find(|param| param.name() == name).unwrap();
+        assert!(find("IMPORTSTRUCT").is_import());
+        assert!(find("ECHOSTRUCT").is_export());
+        assert!(find("RESPTEXT").is_export());
+        assert!(find("RFCTABLE").is_table());
+        assert_eq!(find("RFCTABLE").rfc_type(), RfcType::Table);
+        assert!(!find("RFCTABLE").is_import());
+        assert_eq!(find("RFCTABLE").direction(), RfcDirection::Tables);
+        assert_eq!(find("RESPTEXT").rfc_type(), RfcType::Char);
+        assert_eq!(find("RESPTEXT").length(), 255);
+        assert_eq!(find("RESPTEXT").default_value(), None);
+        assert_eq!(find("IMPORTSTRUCT").length(), 0);
+
+        assert_eq!(func.get_parameter_by_index(0).unwrap(), params[0]);
+        assert!(func.get_parameter_by_index(4).is_err());
+        assert!(func.get_parameter_by_index(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn describe_function_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let desc = conn.describe_function("stfc_structure").unwrap();
+        assert_eq!(desc.name(), "STFC_STRUCTURE");
+        assert_eq!(desc.parameter_count().unwrap(), 4);
+        let params = desc
+            .parameters()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(desc.get_parameter_by_index(0).unwrap(), params[0]);
+
+        // Functions are created repeatedly from the same description
+        for value in ["First", "Second"] {
+            let mut func = desc.create_function().unwrap();
+            func.get_structure("IMPORTSTRUCT")
+                .unwrap()
+                .set_chars("RFCCHAR4", value)
+                .unwrap();
+            func.invoke().unwrap();
+            let echo = func.get_structure("ECHOSTRUCT").unwrap();
+            assert_eq!(echo.get_string("RFCCHAR4").unwrap(), &value[..4]);
+            drop(echo);
+            func.clear_parameter("IMPORTSTRUCT").unwrap();
+        }
+        drop(desc);
+
+        // An evicted description is fetched again
+        conn.evict_function_desc("STFC_STRUCTURE").unwrap();
+        assert_eq!(
+            conn.describe_function("STFC_STRUCTURE")
+                .unwrap()
+                .parameter_count()
+                .unwrap(),
+            4
+        );
+
+        // A description no longer cached is only released by its owner, after its functions
+        let desc = conn.describe_function("STFC_STRUCTURE").unwrap();
+        conn.evict_function_desc("STFC_STRUCTURE").unwrap();
+        let first = desc.create_function().unwrap();
+        let second = desc.create_function().unwrap();
+        drop(first);
+        second.invoke().unwrap();
+        drop(second);
+        assert_eq!(desc.name(), "STFC_STRUCTURE");
+        drop(desc);
+
+        let err = conn
+            .describe_function("INVALID_TEST_FUNCTION_NAME")
+            .unwrap_err();
+        assert_eq!(err.kind(), GetFunctionErrorKind::NotFound);
+    }
+
+    #[test]
+    fn offline_data_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        // Prepared without any function, the description can go away before the data
+        let type_desc = conn.get_type_desc("rfctest").unwrap();
+        assert_eq!(type_desc.name().unwrap(), "RFCTEST");
+        let mut structure = type_desc.create_structure().unwrap();
+        let mut table = type_desc.create_table().unwrap();
+        drop(type_desc);
+        structure.set_int("RFCINT4", 4711).unwrap();
+        structure.set_chars("RFCCHAR4", "Prep").unwrap();
+        table
+            .append_rows_with(3, |index, row| row.set_int("RFCINT4", index as i32))
+            .unwrap();
+
+        let mut func = conn.get_function("STFC_STRUCTURE").unwrap();
+        func.import_parameter("IMPORTSTRUCT")
+            .unwrap()
+            .set_structure(&structure)
+            .unwrap();
+        func.set_table("RFCTABLE", &table).unwrap();
+        func.invoke().unwrap();
+
+        let echo = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(echo.get_int("RFCINT4").unwrap(), 4711);
+        assert_eq!(echo.get_string("RFCCHAR4").unwrap(), "Prep");
+        assert_eq!(func.get_table("RFCTABLE").unwrap().row_count().unwrap(), 4);
+
+        // The parameters hold copies, the prepared data is left as is
+        assert_eq!(table.row_count().unwrap(), 3);
+        assert!(conn.get_type_desc("NOT_A_TEST_TYPE").is_err());
+    }
+
+    #[test]
+    fn structure_copy_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        {
+            let mut imp = func.get_structure("IMPORTSTRUCT").unwrap();
+            imp.set_int("RFCINT4", 4711).unwrap();
+            imp.set_chars("RFCCHAR4", "Tmpl").unwrap();
+            imp.set_float("RFCFLOAT", 0.1 + 0.2).unwrap();
+            imp.set_bytes("RFCHEX3", &[0xCA, 0xFE, 0x01]).unwrap();
+        }
+        func.invoke().unwrap();
+
+        // The echoed structure serves as the template of the next call
+        {
+            let mut imp = func.get_structure("IMPORTSTRUCT").unwrap();
+            imp.clear().unwrap();
+            assert_eq!(imp.get_int("RFCINT4").unwrap(), 0);
+            assert_eq!(imp.get_string("RFCCHAR4").unwrap(), "");
+
+            imp.copy_from(&func.get_structure("ECHOSTRUCT").unwrap())
+                .unwrap();
+            imp.set_chars("RFCCHAR4", "Next").unwrap();
+        }
+        func.invoke().unwrap();
+        let echo = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(echo.get_int("RFCINT4").unwrap(), 4711);
+        // Values are copied with their types, without a round trip through text
+        assert_eq!(echo.get_float("RFCFLOAT").unwrap(), 0.1 + 0.2);
+        assert_eq!(echo.get_bytes("RFCHEX3").unwrap(), [0xCA, 0xFE, 0x01]);
+        assert_eq!(echo.get_string("RFCCHAR4").unwrap(), "Next");
+        drop(echo);
+
+        let other = conn
+            .get_type_desc("BAPIRET2")
+            .unwrap()
+            .create_structure()
+            .unwrap();
+        let err = func
+            .get_structure("IMPORTSTRUCT")
+            .unwrap()
+            .copy_from(&other)
+            .unwrap_err();
+        assert!(err.message().contains("BAPIRET2"));
+    }
+
+    #[test]
+    fn field_descs_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let impstruct = func.get_structure("IMPORTSTRUCT").unwrap();
+
+        let fields = impstruct
+            .fields()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(fields.len() as u32, impstruct.field_count());
+        assert_eq!(impstruct.field_desc_by_index(0).unwrap(), fields[0]);
+        assert!(impstruct
+            .field_desc_by_index(impstruct.field_count())
+            .is_err());
+
+        let int = impstruct.field_desc_by_name("RFCINT4").unwrap();
+        assert_eq!(int.name(), "RFCINT4");
+        assert_eq!(int.rfc_type(), RfcType::Int);
+        assert_eq!(int.uc_length(), 4);
+        let chars = impstruct.field_desc_by_name("RFCCHAR4").unwrap();
+        assert_eq!(chars.rfc_type(), RfcType::Char);
+        assert_eq!(chars.nuc_length(), 4);
+        assert_eq!(chars.uc_length(), 8);
+        assert!(impstruct.field_desc_by_name("NOT_A_FIELD").is_err());
+
+        // Tables describe the fields of their rows
+        let table = func.get_table("RFCTABLE").unwrap();
+        assert_eq!(
+            table.field_desc_by_name("RFCHEX3").unwrap().rfc_type(),
+            RfcType::Byte
+        );
+        assert_eq!(table.fields().unwrap().count() as u32, table.field_count());
+    }
+
+    #[test]
+    fn decimal_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        // The internal amount is a packed number of 23 digits with 4 decimals
+        let mut func = conn.get_function("BAPI_CURRENCY_CONV_TO_EXTERNAL").unwrap();
+        func.set_string("CURRENCY", "USD").unwrap();
+        {
+            let mut param = func.import_parameter("AMOUNT_INTERNAL").unwrap();
+            assert!(param.is_decimal());
+
+            // The trailing sign of ABAP is accepted and the value is read with the field decimals
+            param.set_decimal_str("123.45-").unwrap();
+            assert_eq!(param.get_decimal_str().unwrap(), "-123.4500");
+
+            // Trailing zeros are not decimals which would be lost
+            param.set_decimal_str("0.12340").unwrap();
+            assert_eq!(param.get_decimal_str().unwrap(), "0.1234");
+
+            assert!(param.set_decimal_str("1.23456").is_err());
+            // 19 integer digits fit the field, one more does not
+            param.set_decimal_str("1234567890123456789").unwrap();
+            assert_eq!(param.get_decimal_str().unwrap(), "1234567890123456789.0000");
+            assert!(param.set_decimal_str("12345678901234567890").is_err());
+            assert!(param.set_decimal_str("12,5").is_err());
         }
+        assert!(func.set_decimal_str("CURRENCY", "1").is_err());
+
+        func.set_decimal_str("AMOUNT_INTERNAL", "-123.45").unwrap();
+        func.invoke().unwrap();
+        assert_eq!(
+            func.get_decimal_str("AMOUNT_EXTERNAL").unwrap(),
+            "-123.4500"
+        );
+
+        #[cfg(feature = "decimal")]
+        {
+            let amount: rust_decimal::Decimal = "-98.7654".parse().unwrap();
+            func.set_decimal("AMOUNT_INTERNAL", amount).unwrap();
+            assert_eq!(func.get_decimal("AMOUNT_INTERNAL").unwrap(), amount);
+            assert!(func
+                .set_decimal("AMOUNT_INTERNAL", "0.00001".parse().unwrap())
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn xstring_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        // Only the description is needed to round trip a value through the parameter
+        let mut func = conn.get_function("SCMS_XSTRING_TO_BINARY").unwrap();
+        {
+            let mut param = func.import_parameter("BUFFER").unwrap();
+            assert!(param.is_bytes());
+
+            param.set_bytes(&[]).unwrap();
+            assert_eq!(param.get_bytes().unwrap(), Vec::<u8>::new());
+
+            let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+            param.set_bytes(&payload).unwrap();
+            assert_eq!(param.get_bytes().unwrap(), payload);
+        }
+        assert_eq!(func.get_bytes("BUFFER").unwrap().len(), 4 * 1024 * 1024);
+
+        // A value larger than the buffer of the reads comes in several chunks
+        {
+            use std::{
+                collections::hash_map::DefaultHasher,
+                hash::Hasher,
+                io::{self, Read},
+            };
+
+            let checksum = |bytes: &[u8]| {
+                let mut hasher = DefaultHasher::new();
+                hasher.write(bytes);
+                hasher.finish()
+            };
+            let expected = checksum(&func.get_bytes("BUFFER").unwrap());
+
+            let mut reader = func.xstring_reader("BUFFER").unwrap();
+            let mut chunk = [0; 64 * 1024];
+            let (mut chunks, mut streamed) = (0, Vec::new());
+            loop {
+                let len = reader.read(&mut chunk).unwrap();
+                if len == 0 {
+                    break;
+                }
+                assert!(len <= chunk.len());
+                chunks += 1;
+                streamed.extend_from_slice(&chunk[..len]);
+            }
+            assert_eq!(chunks, 64);
+            assert_eq!(checksum(&streamed), expected);
+            assert_eq!(reader.read(&mut chunk).unwrap(), 0);
+
+            let mut copied = Vec::new();
+            io::copy(&mut func.xstring_reader("BUFFER").unwrap(), &mut copied).unwrap();
+            assert_eq!(checksum(&copied), expected);
+        }
+    }
+
+    #[test]
+    fn scalar_table_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let mut func = conn.get_function("RFC_READ_TABLE").unwrap();
+        func.set_chars("QUERY_TABLE", "T000").unwrap();
+        {
+            let mut fields = func.get_table("FIELDS").unwrap();
+            assert!(!fields.is_scalar().unwrap());
+            assert!(fields.append_scalar_row("MANDT").is_err());
+            let mut row = fields.append_row().unwrap();
+            row.set_chars("FIELDNAME", "MANDT").unwrap();
+        }
+        {
+            let mut options = func.get_table("OPTIONS").unwrap();
+            assert!(options.is_scalar().unwrap());
+            options.append_scalar_row("MANDT = '000'").unwrap();
+            assert_eq!(
+                options.get_scalar_row(0).unwrap().trim_end(),
+                "MANDT = '000'"
+            );
+        }
+        func.invoke().unwrap();
+
+        let data = func.get_table("DATA").unwrap();
+        let rows: Vec<_> = data
+            .scalar_rows()
+            .unwrap()
+            .map(|row| row.map(|value| value.trim_end().to_owned()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows, vec!["000"]);
+    }
+
+    #[test]
+    fn apply_defaults_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let mut func = conn.get_function("RFC_READ_TABLE").unwrap();
+        let outcome = func.apply_defaults().unwrap();
+        assert!(outcome.applied.iter().any(|name| name == "DELIMITER"));
+        assert!(outcome.skipped.is_empty());
+
+        // Values already provided are left alone
+        let mut func = conn.get_function("RFC_READ_TABLE").unwrap();
+        func.set_chars("DELIMITER", "|").unwrap();
+        let outcome = func.apply_defaults().unwrap();
+        assert!(!outcome.applied.iter().any(|name| name == "DELIMITER"));
+        assert_eq!(func.get_chars("DELIMITER").unwrap(), "|");
+    }
+
+    #[test]
+    fn parameter_direction_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let mut func = conn.get_function("STFC_STRUCTURE").unwrap();
+        assert!(func.export_parameter("IMPORTSTRUCT").is_err());
+        assert!(func.import_parameter("ECHOSTRUCT").is_err());
+        assert!(func.import_parameter("RFCTABLE").is_ok());
+        assert!(func.export_parameter("RFCTABLE").is_ok());
+        {
+            let param = func.import_parameter("IMPORTSTRUCT").unwrap();
+            let mut is = param.get_structure().unwrap();
+            is.set_int("RFCINT4", 4711).unwrap();
+        }
+        func.invoke().unwrap();
+
+        let echo = func
+            .export_parameter("ECHOSTRUCT")
+            .unwrap()
+            .get_structure()
+            .unwrap();
+        assert_eq!(
+            echo.get("RFCINT4"),
+            Some(&OwnedValue::Text("4711".to_owned()))
+        );
+    }
+
+    /// Declare a structure type locally, for layouts the dictionary does not have.
+    fn declare_type(
+        name: &str,
+        fields: &[sapnwrfc_sys::RFC_FIELD_DESC],
+        nuc_length: u32,
+        uc_length: u32,
+    ) -> RfcTypeDesc {
+        use sapnwrfc_sys::{RfcAddTypeField, RfcCreateTypeDesc, RfcSetTypeLength, _RFC_RC};
+
+        let name = uc::from_str(name).unwrap();
+        let mut err_info = error::RfcErrorInfo::new();
+        unsafe {
+            let handle = RfcCreateTypeDesc(name.as_ptr(), err_info.as_mut_ptr());
+            assert!(!handle.is_null(), "{}", err_info);
+            for field in fields {
+                let rc = RfcAddTypeField(handle, field, err_info.as_mut_ptr());
+                assert_eq!(rc, _RFC_RC::RFC_OK, "{}", err_info);
+            }
+            let rc = RfcSetTypeLength(handle, nuc_length, uc_length, err_info.as_mut_ptr());
+            assert_eq!(rc, _RFC_RC::RFC_OK, "{}", err_info);
+            RfcTypeDesc::new(handle)
+        }
+    }
+
+    /// Declare a copy of a dictionary structure type under another name.
+    fn renamed_type(conn: &RfcConnection, type_name: &str, name: &str) -> RfcTypeDesc {
+        use sapnwrfc_sys::{RfcGetFieldCount, RfcGetFieldDescByIndex, RfcGetTypeDesc, RfcGetTypeLength};
+
+        let type_name = uc::from_str(type_name).unwrap();
+        let mut err_info = error::RfcErrorInfo::new();
+        unsafe {
+            // The description of the dictionary type belongs to the cache of the SDK
+            let desc = RfcGetTypeDesc(conn.handle(), type_name.as_ptr(), err_info.as_mut_ptr());
+            assert!(!desc.is_null(), "{}", err_info);
+            let mut count = 0;
+            RfcGetFieldCount(desc, &mut count, err_info.as_mut_ptr());
+            let fields: Vec<_> = (0..count)
+                .map(|index| {
+                    let mut field = std::mem::zeroed();
+                    RfcGetFieldDescByIndex(desc, index, &mut field, err_info.as_mut_ptr());
+                    field
+                })
+                .collect();
+            let (mut nuc_length, mut uc_length) = (0, 0);
+            RfcGetTypeLength(desc, &mut nuc_length, &mut uc_length, err_info.as_mut_ptr());
+            declare_type(name, &fields, nuc_length, uc_length)
+        }
+    }
+
+    #[cfg(sapnwrfc_int8)]
+    #[test]
+    fn int8_test() {
+        use sapnwrfc_sys::{RFC_FIELD_DESC, _RFCTYPE};
+
+        // The dictionary has no structure with an INT8 field on every release, one is declared
+        let mut field: RFC_FIELD_DESC = unsafe { std::mem::zeroed() };
+        field.name = uc::from_str_to_abap_name("VALUE").unwrap();
+        field.type_ = _RFCTYPE::RFCTYPE_INT8;
+        field.nucLength = 8;
+        field.ucLength = 8;
+        let desc = declare_type("ZINT8_TEST", &[field], 8, 8);
+        let mut row = desc.create_structure().unwrap();
+
+        row.set_i64("VALUE", 1 << 40).unwrap();
+        assert_eq!(row.get_i64("VALUE").unwrap(), 1 << 40);
+        row.set_i64("VALUE", i64::MIN).unwrap();
+        assert_eq!(row.get_i64("VALUE").unwrap(), i64::MIN);
+
+        // Values beyond i32 are refused by get_int rather than truncated
+        let err = row.get_int("VALUE").unwrap_err();
+        assert_eq!(err.code(), RfcErrorCode::ConversionFailure);
+        assert!(err.message().contains("VALUE"));
+        row.set_i64("VALUE", -7).unwrap();
+        assert_eq!(row.get_int("VALUE").unwrap(), -7);
+    }
+
+    #[test]
+    fn num_type_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let mut imp = func.get_structure("IMPORTSTRUCT").unwrap();
+
+        // Only NUMC fields take digits, other character fields are refused
+        let err = imp.set_num_from_u64("RFCCHAR4", 42).unwrap_err();
+        assert_eq!(err.code(), RfcErrorCode::ConversionFailure);
+        assert!(imp.get_num("RFCCHAR4").is_err());
+
+        // The message number of BAPIRET2 is a NUMC of 3 digits
+        let mut ret = conn
+            .get_type_desc("BAPIRET2")
+            .unwrap()
+            .create_structure()
+            .unwrap();
+        ret.set_num("NUMBER", "42").unwrap();
+        assert_eq!(ret.get_num("NUMBER").unwrap(), "042");
+        ret.set_num_from_u64("NUMBER", 999).unwrap();
+        assert_eq!(ret.get_num("NUMBER").unwrap(), "999");
+
+        let err = ret.set_num("NUMBER", "1000").unwrap_err();
+        assert_eq!(err.code(), RfcErrorCode::ConversionFailure);
+        assert!(err.message().contains("NUMBER"));
+        assert!(ret.set_num("NUMBER", "4a").is_err());
+        assert!(ret.set_num("NUMBER", "-1").is_err());
+        // The last value written is left in place by the refused ones
+        assert_eq!(ret.get_num("NUMBER").unwrap(), "999");
+    }
+
+    #[test]
+    fn parameter_active_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let mut func = conn.get_function("STFC_STRUCTURE").unwrap();
+        assert!(func.is_parameter_active("RFCTABLE").unwrap());
+        func.import_parameter("RFCTABLE")
+            .unwrap()
+            .deactivate()
+            .unwrap();
+        assert!(!func.is_parameter_active("RFCTABLE").unwrap());
+        assert!(func.set_parameter_active("NOT_A_PARAMETER", false).is_err());
+
+        {
+            let mut is = func.get_structure("IMPORTSTRUCT").unwrap();
+            is.set_int("RFCINT4", 4711).unwrap();
+        }
+        func.invoke().unwrap();
+        let echo = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(echo.get_int("RFCINT4").unwrap(), 4711);
+
+        func.set_parameter_active("RFCTABLE", true).unwrap();
+        assert!(func.is_parameter_active("RFCTABLE").unwrap());
+    }
+
+    #[test]
+    fn connection_attributes_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let attrs = conn.attributes().unwrap();
+        assert_eq!(attrs.dest, "TEST");
+        assert_eq!(attrs.rfc_role, "C");
+        assert!(!attrs.sys_id.is_empty());
+        assert!(!attrs.client.is_empty());
+        assert!(!attrs.user.is_empty());
+        assert!(format!("{:?}", attrs).contains(&format!("sys_id: {:?}", attrs.sys_id)));
+    }
+
+    #[test]
+    fn partner_sso_ticket_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .set_getsso2(1)
+            .build()
+            .unwrap();
+        let ticket = conn.partner_sso_ticket().unwrap();
+        assert!(ticket.len() > 100);
+
+        assert!(!ticket.ends_with('\0'));
+
+        // Without a ticket requested at logon the backend has none to give
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+        assert!(conn.partner_sso_ticket().is_err());
+    }
+
+    #[test]
+    fn call_test() {
+        use std::collections::HashMap;
+
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let mut imports = HashMap::new();
+        imports.insert(
+            "IMPORTSTRUCT".to_owned(),
+            Value::Structure(vec![
+                ("RFCINT4".to_owned(), Value::Int(4711)),
+                ("RFCFLOAT".to_owned(), Value::Float(1.5)),
+                ("RFCCHAR4".to_owned(), "Call".into()),
+                ("RFCHEX3".to_owned(), Value::Bytes(vec![0xCA, 0xFE, 0x01])),
+            ]),
+        );
+        imports.insert(
+            "RFCTABLE".to_owned(),
+            Value::Table(vec![vec![("RFCINT4".to_owned(), Value::Int(1))]]),
+        );
+        let exports = conn.call("STFC_STRUCTURE", imports).unwrap();
+
+        let echo = &exports["ECHOSTRUCT"];
+        assert_eq!(echo.field("RFCINT4"), Some(&Value::Int(4711)));
+        assert_eq!(echo.field("RFCFLOAT"), Some(&Value::Float(1.5)));
+        assert_eq!(echo.field("RFCCHAR4"), Some(&Value::Str("Call".to_owned())));
+        assert_eq!(
+            echo.field("RFCHEX3"),
+            Some(&Value::Bytes(vec![0xCA, 0xFE, 0x01]))
+        );
+        // Fields are read in declaration order
+        let names: Vec<_> = match echo {
+            Value::Structure(fields) => fields.iter().map(|(name, _)| name.as_str()).collect(),
+            other => panic!("Unexpected value {:?}", other),
+        };
+        assert_eq!(&names[..3], ["RFCFLOAT", "RFCCHAR1", "RFCINT2"]);
+        match &exports["RFCTABLE"] {
+            Value::Table(rows) => {
+                assert_eq!(rows.len(), 2);
+                assert_eq!(
+                    Value::Structure(rows[0].clone()).field("RFCINT4"),
+                    Some(&Value::Int(1))
+                );
+            }
+            other => panic!("Unexpected value {:?}", other),
+        }
+        assert!(!exports.contains_key("IMPORTSTRUCT"));
+
+        // Mismatches name the parameter or field they happened on
+        let mut imports = HashMap::new();
+        imports.insert("REQUTEXT".to_owned(), Value::Int(1));
+        let err = conn.call("STFC_CONNECTION", imports).unwrap_err();
+        assert!(err.message().contains("REQUTEXT"));
+
+        let mut imports = HashMap::new();
+        imports.insert(
+            "IMPORTSTRUCT".to_owned(),
+            Value::Structure(vec![("RFCINT4".to_owned(), "Four".into())]),
+        );
+        let err = conn.call("STFC_STRUCTURE", imports).unwrap_err();
+        assert!(err.message().contains("IMPORTSTRUCT.RFCINT4"));
+
+        let mut imports = HashMap::new();
+        imports.insert(
+            "IMPORTSTRUCT".to_owned(),
+            Value::Structure(vec![("NOPE".to_owned(), Value::Int(1))]),
+        );
+        let err = conn.call("STFC_STRUCTURE", imports).unwrap_err();
+        assert_eq!(err.code(), RfcErrorCode::InvalidParameter);
+        assert!(err.message().contains("IMPORTSTRUCT"), "{}", err);
+        assert!(err.message().contains("NOPE"), "{}", err);
+
+        // Rows of table parameters are checked against the line type, other values refused
+        let mut imports = HashMap::new();
+        imports.insert(
+            "RFCTABLE".to_owned(),
+            Value::Table(vec![
+                vec![("RFCINT4".to_owned(), Value::Int(1))],
+                vec![("NOPE".to_owned(), Value::Int(2))],
+            ]),
+        );
+        let err = conn.call("STFC_STRUCTURE", imports).unwrap_err();
+        assert!(err.message().contains("RFCTABLE[1]"), "{}", err);
+        assert!(err.message().contains("RFCTEST"), "{}", err);
+
+        let mut imports = HashMap::new();
+        imports.insert(
+            "RFCTABLE".to_owned(),
+            Value::Structure(vec![("RFCINT4".to_owned(), Value::Int(1))]),
+        );
+        let err = conn.call("STFC_STRUCTURE", imports).unwrap_err();
+        assert!(err.message().contains("RFCTABLE"), "{}", err);
+
+        let mut imports = HashMap::new();
+        imports.insert("NOT_A_PARAM".to_owned(), "Value".into());
+        assert!(conn.call("STFC_CONNECTION", imports).is_err());
+    }
+
+    #[test]
+    fn transaction_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let mut func = conn.get_function("STFC_CONNECTION").unwrap();
+        func.set_chars("REQUTEXT", "tRFC").unwrap();
+
+        let trans = conn.begin_transaction(None, None).unwrap();
+        let tid = trans.tid().to_owned();
+        assert_eq!(tid.len(), 24);
+        assert_eq!(trans.queue(), None);
+        trans.invoke(&func).unwrap();
+        trans.submit().unwrap();
+
+        // Submitting again with the stored id is accepted without executing twice
+        let retry = conn.begin_transaction(Some(&tid), None).unwrap();
+        assert_eq!(retry.tid(), tid);
+        retry.invoke(&func).unwrap();
+        retry.submit().unwrap();
+        retry.confirm().unwrap();
+
+        assert!(conn.begin_transaction(Some(""), None).is_err());
+        assert!(conn.begin_transaction(Some(&"X".repeat(25)), None).is_err());
+    }
+
+    #[test]
+    fn queued_transaction_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let mut func = conn.get_function("STFC_CONNECTION").unwrap();
+        func.set_chars("REQUTEXT", "qRFC").unwrap();
+
+        let trans = conn.begin_transaction(None, Some("SAPRFC_TEST")).unwrap();
+        assert_eq!(trans.queue(), Some("SAPRFC_TEST"));
+        trans.invoke(&func).unwrap();
+        trans.submit().unwrap();
+        trans.confirm().unwrap();
+    }
+
+    #[test]
+    fn stateful_session_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let session = conn.begin_context();
+        let mut func = session.get_function("BAPI_USER_GET_DETAIL").unwrap();
+        func.set_chars("USERNAME", "NOT_A_USER").unwrap();
+        let err = func.invoke_checked().unwrap_err();
+        // The message of the RETURN table is kept whole
+        assert!(matches!(err, RfcError::AbapException(_)));
+        assert_eq!(err.code(), RfcErrorCode::AbapMessage);
+        let info = err.info();
+        assert_eq!(info.abap_msg_type(), "E");
+        assert!(info.matches_key(&format!(
+            "{}{}",
+            info.abap_msg_class(),
+            info.abap_msg_number()
+        )));
+        assert_eq!(info.abap_msg_vars()[0], "NOT_A_USER");
+        session.rollback().unwrap();
+        session.commit(true).unwrap();
+        drop(func);
+        session.end().unwrap();
+
+        // A session dropped after a failed call still leaves the connection usable
+        {
+            let session = conn.begin_context();
+            let mut func = session.get_function("BAPI_USER_GET_DETAIL").unwrap();
+            func.set_chars("USERNAME", "NOT_A_USER").unwrap();
+            assert!(func.invoke_checked().is_err());
+        }
+        conn.ping().unwrap();
+        conn.commit(false).unwrap();
+    }
+
+    #[test]
+    fn throughput_test() {
+        let first = RfcConnection::for_dest("TEST").unwrap();
+        let second = RfcConnection::for_dest("TEST").unwrap();
+
+        // One throughput adds up the calls of all the connections it is attached to
+        let throughput = RfcThroughput::new().unwrap();
+        throughput.attach(&first).unwrap();
+        throughput.attach(&second).unwrap();
+        for conn in [&first, &second] {
+            let mut func = conn.get_function("STFC_CONNECTION").unwrap();
+            func.set_chars("REQUTEXT", "Counted").unwrap();
+            func.invoke().unwrap();
+        }
+        assert_eq!(throughput.number_of_calls().unwrap(), 2);
+        assert!(throughput.sent_bytes().unwrap() > 0);
+        assert!(throughput.received_bytes().unwrap() > 0);
+        assert!(throughput.total_time().unwrap() >= throughput.serialization_time().unwrap());
+
+        throughput.detach(&second).unwrap();
+        assert!(second.throughput().is_none());
+        second
+            .get_function("STFC_CONNECTION")
+            .unwrap()
+            .invoke()
+            .unwrap();
+        assert_eq!(throughput.number_of_calls().unwrap(), 2);
+
+        throughput.reset().unwrap();
+        assert_eq!(throughput.number_of_calls().unwrap(), 0);
+
+        // The connection keeps its throughput alive
+        drop(throughput);
+        first
+            .get_function("STFC_CONNECTION")
+            .unwrap()
+            .invoke()
+            .unwrap();
+        assert_eq!(first.throughput().unwrap().number_of_calls().unwrap(), 1);
+    }
+
+    #[test]
+    fn health_report_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let report = conn.health_report(std::time::Duration::from_secs(10));
+        assert!(report.is_healthy(), "{:?}", report);
+        assert!(report.latency.is_some());
+        assert_eq!(
+            report.attributes.unwrap().sys_id,
+            conn.attributes().unwrap().sys_id
+        );
+        assert!(report.sdk_version.0 >= 7);
+    }
+
+    #[test]
+    fn get_function_errors_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        // Names are looked up in uppercase
+        conn.get_function("scp_string_echo").unwrap();
+        let err = conn.get_function_exact("scp_string_echo").unwrap_err();
+        assert_eq!(err.name(), "scp_string_echo");
+
+        let err = conn.get_function("not_a_function_module").unwrap_err();
+        assert_eq!(err.kind(), GetFunctionErrorKind::NotFound);
+        assert_eq!(err.name(), "NOT_A_FUNCTION_MODULE");
+        assert!(err.to_string().contains("NOT_A_FUNCTION_MODULE"));
+
+        let err = conn.get_function("POPUP_TO_CONFIRM").unwrap_err();
+        assert_eq!(err.kind(), GetFunctionErrorKind::NotRemoteEnabled);
+    }
+
+    /// Collect the path of every elementary field, descending into structures and table rows.
+    fn collect_paths(row: &RfcStructure, prefix: &str, paths: &mut Vec<String>) {
+        for field in row.fields().unwrap() {
+            let field = field.unwrap();
+            let path = format!("{}{}", prefix, field.name());
+            match field.rfc_type() {
+                RfcType::Structure => {
+                    let nested = row.get_structure(field.name()).unwrap();
+                    collect_paths(&nested, &format!("{}.", path), paths);
+                }
+                RfcType::Table => {
+                    let table = row.get_table(field.name()).unwrap();
+                    for index in 0..table.row_count().unwrap() {
+                        let nested = table.get_row(index).unwrap();
+                        collect_paths(&nested, &format!("{}[{}].", path, index), paths);
+                    }
+                }
+                _ => paths.push(path),
+            }
+        }
+    }
+
+    #[test]
+    fn nested_access_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        // Rows of a table parameter are structures reachable through the table
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        func.get_table("RFCTABLE").unwrap().append_row().unwrap();
+        func.invoke().unwrap();
+        let table = func.get_table("RFCTABLE").unwrap();
+        let mut paths = Vec::new();
+        for index in 0..table.row_count().unwrap() {
+            let row = table.get_row(index).unwrap();
+            collect_paths(&row, &format!("RFCTABLE[{}].", index), &mut paths);
+        }
+        assert!(paths.contains(&"RFCTABLE[1].RFCINT4".to_owned()));
+
+        // The RETURN table of a BAPI holds BAPIRET2 rows, read down to their fields
+        let mut func = conn.get_function("BAPI_USER_GET_DETAIL").unwrap();
+        func.set_chars("USERNAME", "NOT_A_USER").unwrap();
+        func.invoke().unwrap();
+        let ret = func.get_table("RETURN").unwrap();
+        let row = ret.get_first_row().unwrap();
+        let mut paths = Vec::new();
+        collect_paths(&row, "RETURN[0].", &mut paths);
+        assert!(paths.contains(&"RETURN[0].MESSAGE".to_owned()));
+        assert_eq!(row.get_string("TYPE").unwrap(), "E");
+        assert!(ret.get_row(ret.row_count().unwrap()).is_err());
+        assert!(table.get_row(u32::MAX).is_err());
+
+        // Application log messages nest a structure, and a table within a structure
+        let msg = conn
+            .get_type_desc("BAL_S_MSG")
+            .unwrap()
+            .create_structure()
+            .unwrap();
+        msg.get_structure("CONTEXT")
+            .unwrap()
+            .set_chars("TABNAME", "T100")
+            .unwrap();
+        {
+            let params = msg.get_structure("PARAMS").unwrap();
+            let mut pars = params.get_table("T_PAR").unwrap();
+            for (name, value) in [("ID", "BL"), ("NUMBER", "001")] {
+                let mut par = pars.append_row().unwrap();
+                par.set_chars("PARNAME", name).unwrap();
+                par.set_chars("PARVALUE", value).unwrap();
+            }
+        }
+        let mut paths = Vec::new();
+        collect_paths(&msg, "", &mut paths);
+        assert!(paths.contains(&"CONTEXT.TABNAME".to_owned()));
+        assert!(paths.contains(&"PARAMS.T_PAR[1].PARVALUE".to_owned()));
+
+        let context = msg.get_structure("CONTEXT").unwrap();
+        assert_eq!(context.get_string("TABNAME").unwrap(), "T100");
+        let params = msg.get_structure("PARAMS").unwrap();
+        let pars = params.get_table("T_PAR").unwrap();
+        assert_eq!(
+            pars.get_row(1).unwrap().get_string("PARVALUE").unwrap(),
+            "001"
+        );
+        assert!(pars.get_row(2).is_err());
+    }
+
+    #[test]
+    fn append_rows_test() {
+        use std::time::Instant;
+
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let mut table = func.get_table("RFCTABLE").unwrap();
+
+        let start = Instant::now();
+        let appended = table
+            .append_rows_with(10_000, |index, row| {
+                row.set_int("RFCINT4", index as i32)?;
+                row.set_chars("RFCCHAR4", "Bulk")?;
+                Ok(())
+            })
+            .unwrap();
+        log::info!("Appended {} rows in {:?}", appended, start.elapsed());
+        assert_eq!(appended, 10_000);
+        assert_eq!(table.row_count().unwrap(), 10_000);
+
+        table
+            .modify_row(42, |row| row.set_chars("RFCCHAR4", "Edit"))
+            .unwrap();
+        let row = table.get_row(42).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 42);
+        assert_eq!(row.get_chars("RFCCHAR4").unwrap(), "Edit");
+
+        let mut replacement = conn
+            .get_type_desc("RFCTEST")
+            .unwrap()
+            .create_structure()
+            .unwrap();
+        replacement.set_int("RFCINT4", -1).unwrap();
+        replacement.set_chars("RFCCHAR4", "Set").unwrap();
+        table.set_row(43, &replacement).unwrap();
+        let row = table.get_row(43).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), -1);
+        assert_eq!(row.get_chars("RFCCHAR4").unwrap(), "Set");
+        assert!(table.set_row(10_000, &replacement).is_err());
+
+        // A failing row removes the rows appended by the same call
+        let err = table
+            .append_rows(["1", "2", "x"], |value, row| {
+                row.set_from_str("RFCINT4", value)
+            })
+            .unwrap_err();
+        assert_eq!(err.row(), 2);
+        assert_eq!(err.error().code(), RfcErrorCode::ConversionFailure);
+        assert!(!err.error().message().starts_with("Row"));
+        assert!(err.to_string().starts_with("Row 2:"));
+        assert_eq!(table.row_count().unwrap(), 10_000);
+
+        func.invoke().unwrap();
+        assert_eq!(table.row_count().unwrap(), 10_001);
+    }
+
+    #[test]
+    fn field_name_test() {
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+        let mut table = conn
+            .get_type_desc("RFCTEST")
+            .unwrap()
+            .create_table()
+            .unwrap();
+
+        let int4 = table.field("RFCINT4").unwrap();
+        assert_eq!(int4.as_str(), "RFCINT4");
+        assert!(table.field("NOT_A_FIELD").is_err());
+        assert!(FieldName::new(&"X".repeat(31)).is_err());
+
+        // Converted names and text are interchangeable in the accessors
+        let mut row = table.append_row().unwrap();
+        row.set_int(&int4, 42).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 42);
+        let name = String::from("RFCINT4");
+        assert_eq!(row.get_int(&name).unwrap(), 42);
+
+        // Any text type is accepted, as with a `&str` parameter
+        let cow: std::borrow::Cow<str> = name.as_str().into();
+        assert_eq!(row.get_int(&cow).unwrap(), 42);
+        let boxed: Box<str> = name.clone().into_boxed_str();
+        assert_eq!(row.get_int(&boxed).unwrap(), 42);
+        let shared: std::rc::Rc<str> = name.as_str().into();
+        assert_eq!(row.get_int(&shared).unwrap(), 42);
+        fn get_by<S: AsRef<str>>(row: &RfcStructure, name: &S) -> i32 {
+            row.get_int(name).unwrap()
+        }
+        assert_eq!(get_by(&row, &name), 42);
+    }
+
+    #[test]
+    fn append_from_maps_test() {
+        use std::collections::HashMap;
+
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let row = |values: &[(&str, &str)]| -> HashMap<String, String> {
+            values
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let rows = vec![
+            row(&[("RFCINT4", "1"), ("RFCCHAR4", "ONE")]),
+            row(&[("RFCFLOAT", "2.5"), ("RFCDATE", "20240131")]),
+            row(&[("RFCINT4", "3"), ("RFCTIME", "235959"), ("RFCCHAR1", "X")]),
+        ];
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        {
+            let mut table = func.get_table("RFCTABLE").unwrap();
+            assert_eq!(table.append_from_maps(&rows).unwrap(), 3);
+
+            // A failed load leaves the table as it was
+            let bad = vec![row(&[("RFCINT4", "4")]), row(&[("NOT_A_FIELD", "5")])];
+            let err = table.append_from_maps(&bad).unwrap_err();
+            assert!(err.message().contains("Row 1 field NOT_A_FIELD"));
+            let bad = vec![row(&[("RFCINT4", "not a number")])];
+            assert!(table.append_from_maps(&bad).is_err());
+            assert_eq!(table.row_count().unwrap(), 3);
+
+            let skipped = vec![row(&[("RFCINT4", "4"), ("NOT_A_FIELD", "5")])];
+            assert_eq!(
+                table
+                    .append_from_maps_with(&skipped, UnknownFields::Skip)
+                    .unwrap(),
+                1
+            );
+        }
+        func.invoke().unwrap();
+
+        let table = func.get_table("RFCTABLE").unwrap();
+        let row = table.get_row(0).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 1);
+        assert_eq!(row.get_chars("RFCCHAR4").unwrap(), "ONE");
+        let row = table.get_row(1).unwrap();
+        assert_eq!(row.get_string("RFCDATE").unwrap(), "20240131");
+        let row = table.get_row(2).unwrap();
+        assert_eq!(row.get_string("RFCTIME").unwrap(), "235959");
+        assert_eq!(row.get_chars("RFCCHAR1").unwrap(), "X");
+        let row = table.get_row(3).unwrap();
+        assert_eq!(row.get_int("RFCINT4").unwrap(), 4);
+    }
+
+    #[test]
+    fn get_function_with_timeout_test() {
+        use std::time::{Duration, Instant};
+
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        conn.get_function_with_timeout("STFC_CONNECTION", Duration::from_secs(30))
+            .unwrap()
+            .invoke()
+            .unwrap();
+        assert!(!conn.is_broken());
+
+        // A lookup which cannot complete in time returns promptly and breaks the connection
+        let start = Instant::now();
+        let err = conn
+            .get_function_with_timeout("BAPI_USER_GET_DETAIL", Duration::ZERO)
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(err.code(), RfcErrorCode::Timeout);
+        assert!(conn.is_broken());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invoke_returning_test() {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(rename_all = "UPPERCASE")]
+        struct Echo {
+            echostruct: EchoStruct,
+            resptext: String,
+            rfctable: Vec<EchoStruct>,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(rename_all = "UPPERCASE")]
+        struct EchoStruct {
+            rfcint4: i32,
+            rfcfloat: f64,
+            rfcchar4: String,
+            rfcchar1: Option<String>,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct BadEcho {
+            #[serde(rename = "ECHOSTRUCT")]
+            _echostruct: BadStruct,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct BadStruct {
+            #[serde(rename = "RFCCHAR4")]
+            _rfcchar4: i32,
+        }
+
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        {
+            let mut is = func.get_structure("IMPORTSTRUCT").unwrap();
+            is.set_int("RFCINT4", 42).unwrap();
+            is.set_string("RFCFLOAT", "1.5").unwrap();
+            is.set_chars("RFCCHAR4", "ECHO").unwrap();
+        }
+        let echo: Echo = func.invoke_returning().unwrap();
+        assert_eq!(echo.echostruct.rfcint4, 42);
+        assert_eq!(echo.echostruct.rfcfloat, 1.5);
+        assert_eq!(echo.echostruct.rfcchar4, "ECHO");
+        assert_eq!(echo.echostruct.rfcchar1, None);
+        assert!(!echo.resptext.is_empty());
+        assert!(!echo.rfctable.is_empty());
+
+        // Errors name the path of the field that could not be read
+        let err = func.invoke_returning::<BadEcho>().unwrap_err();
+        assert!(err.message().contains("ECHOSTRUCT.RFCCHAR4"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn structure_serde_test() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Test {
+            rfcint4: i32,
+            rfcfloat: f64,
+            #[serde(rename = "RFCCHAR4")]
+            code: String,
+            rfcchar1: Option<String>,
+            rfcdate: String,
+        }
+
+        #[derive(Debug, serde::Serialize)]
+        struct Unknown {
+            rfcint4: i32,
+            not_a_field: i32,
+        }
+
+        let conn = RfcConnection::for_dest("TEST").unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let test = Test {
+            rfcint4: 42,
+            rfcfloat: 1.5,
+            code: "ECHO".to_owned(),
+            rfcchar1: None,
+            rfcdate: "20210314".to_owned(),
+        };
+        func.get_structure("IMPORTSTRUCT")
+            .unwrap()
+            .from_serde(&test)
+            .unwrap();
+        {
+            let mut table = func.get_table("RFCTABLE").unwrap();
+            table.append_row().unwrap().from_serde(&test).unwrap();
+        }
+
+        func.invoke().unwrap();
+
+        let echo = func.get_structure("ECHOSTRUCT").unwrap();
+        assert_eq!(echo.get_int("RFCINT4").unwrap(), 42);
+        assert_eq!(echo.to_serde::<Test>().unwrap(), test);
+
+        let table = func.get_table("RFCTABLE").unwrap();
+        assert_eq!(
+            table.get_first_row().unwrap().to_serde::<Test>().unwrap(),
+            test
+        );
+
+        // Fields of the value which are not in the structure are named in the error
+        let err = func
+            .get_structure("IMPORTSTRUCT")
+            .unwrap()
+            .from_serde(&Unknown {
+                rfcint4: 1,
+                not_a_field: 2,
+            })
+            .unwrap_err();
+        assert!(err.message().contains("not_a_field"));
+    }
+
+    #[test]
+    fn index_by_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let mut table = func.get_table("RFCTABLE").unwrap();
+        for (day, (int, chars)) in [(1, "A"), (2, "B"), (1, "A"), (1, "C")].iter().enumerate() {
+            let mut row = table.append_row().unwrap();
+            row.set_int("RFCINT4", *int).unwrap();
+            row.set_chars("RFCCHAR4", chars).unwrap();
+            row.set_string("RFCDATE", &format!("2024010{}", day + 1))
+                .unwrap();
+        }
+
+        let date = |row: &OwnedStructure| match row.get("RFCDATE") {
+            Some(OwnedValue::Text(value)) => value.clone(),
+            other => panic!("Unexpected value {:?}", other),
+        };
+        let key = |int: &str, chars: &str| vec![int.to_owned(), chars.to_owned()];
+
+        let err = table.index_by(&["RFCINT4", "RFCCHAR4"]).unwrap_err();
+        assert!(err.message().contains("row 2"));
+
+        let index = table
+            .index_by_with(&["RFCINT4", "RFCCHAR4"], DuplicateKeys::FirstWins)
+            .unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(date(&index[&key("1", "A")]), "20240101");
+        assert!(index.contains_key(&key("1", "C")));
+
+        let index = table
+            .index_by_with(&["RFCINT4", "RFCCHAR4"], DuplicateKeys::LastWins)
+            .unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(date(&index[&key("1", "A")]), "20240103");
+
+        let err = table.index_by(&["RFCINT4", "MATNR"]).unwrap_err();
+        assert!(err.message().contains("MATNR"));
+
+        let err = table.index_by_field("RFCCHAR4").unwrap_err();
+        assert!(err.message().contains("Duplicate key"));
+        table.delete_row(2).unwrap();
+        let index = table.index_by_field("RFCCHAR4").unwrap();
+        assert_eq!(date(&index["B"]), "20240102");
+    }
+
+    #[test]
+    fn read_columns_test() {
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+
+        let func = conn.get_function("STFC_STRUCTURE").unwrap();
+        let mut table = func.get_table("RFCTABLE").unwrap();
+        for i in 0..3000 {
+            let mut row = table.append_row().unwrap();
+            row.set_int("RFCINT4", i).unwrap();
+            row.set_chars("RFCCHAR4", &format!("{:04}", i % 7919))
+                .unwrap();
+            row.set_float("RFCFLOAT", f64::from(i) / 4.0).unwrap();
+        }
+
+        let fields = ["RFCFLOAT", "RFCINT4", "RFCCHAR4"];
+        let columns = table.read_columns(&fields).unwrap();
+        assert_eq!(columns.len(), 3000);
+        for (index, projected) in columns.iter().enumerate() {
+            let row =
+                OwnedStructure::from_structure(&table.get_row(index as u32).unwrap()).unwrap();
+            let expected: Vec<_> = fields
+                .iter()
+                .map(|field| row.get(field).unwrap().clone())
+                .collect();
+            assert_eq!(projected, &expected);
+        }
+        assert_eq!(columns[42][1], OwnedValue::Text("42".to_owned()));
+
+        // Unknown fields fail before reading any row
+        let err = table.read_columns(&["RFCINT4", "MATNR"]).unwrap_err();
+        assert!(err.message().contains("MATNR"));
+        assert!(err.message().contains("RFCCHAR4"));
+    }
+
+    #[test]
+    fn connection_events_test() {
+        use events::{ConnectionEvent, ConnectionEventKind};
+        use std::sync::{Arc, Mutex};
+
+        let collected = Arc::new(Mutex::new(Vec::<ConnectionEvent>::new()));
+        let collector = Arc::clone(&collected);
+        let subscription = events::subscribe(move |event| collector.lock().unwrap().push(event));
+        // A failing listener must not get in the way of the others
+        let panicking = events::subscribe(|_| panic!("Listener failure"));
+
+        let mut conn = RfcConnection::for_dest("TEST").unwrap();
+        let id = conn.id();
+        conn.ping().unwrap();
+        conn.track_error(RfcErrorInfo::custom_with_code(
+            sapnwrfc_sys::_RFC_RC::RFC_COMMUNICATION_FAILURE,
+            "Simulated failure",
+        ));
+        // Only the transition to broken is reported
+        conn.track_error(RfcErrorInfo::custom_with_code(
+            sapnwrfc_sys::_RFC_RC::RFC_CLOSED,
+            "Simulated failure",
+        ));
+        // Reopening clears the flag and keeps the id
+        conn.reopen().unwrap();
+        assert!(!conn.is_broken());
+        assert_eq!(conn.id(), id);
+        conn.ping().unwrap();
+        drop(conn);
+        events::unsubscribe(subscription);
+        events::unsubscribe(panicking);
+
+        let events: Vec<_> = collected
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| event.connection_id == id)
+            .cloned()
+            .collect();
+        let kinds: Vec<_> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ConnectionEventKind::Opened,
+                ConnectionEventKind::Broken,
+                ConnectionEventKind::Reconnected,
+                ConnectionEventKind::Closed,
+            ]
+        );
+        assert!(events.iter().all(|event| event.dest == "TEST"));
+        assert!(!events[0].sys_id.is_empty());
+        assert_eq!(
+            events[1].error.as_ref().unwrap().message(),
+            "Simulated failure"
+        );
+        assert!(events[2].error.is_none());
+    }
+
+    #[test]
+    fn default_call_timeout_test() {
+        use std::time::Duration;
+
+        let builder = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .default_call_timeout(Duration::from_secs(1));
+
+        // The default applies to every call
+        let conn = builder.clone().build().unwrap();
+        let mut func = conn.get_function("RFC_PING_AND_WAIT").unwrap();
+        func.set_int("SECONDS", 3).unwrap();
+        let err = func.invoke().unwrap_err();
+        assert_eq!(err.code(), RfcErrorCode::Timeout);
+        assert!(err.message().contains("RFC_PING_AND_WAIT"));
+        assert!(err.message().contains("1s"));
+        assert!(conn.is_broken());
+
+        // A call can extend or disable the default
+        let conn = builder.build().unwrap();
+        let mut func = conn.get_function("RFC_PING_AND_WAIT").unwrap();
+        func.set_int("SECONDS", 2).unwrap();
+        func.invoke_with_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        func.invoke_with_timeout(None).unwrap();
+        assert!(!conn.is_broken());
+    }
+
+    #[test]
+    fn shared_watchdog_test() {
+        use std::{
+            thread,
+            time::{Duration, Instant},
+        };
+
+        // Calls of several connections are cancelled at their own deadlines
+        let start = Instant::now();
+        let calls: Vec<_> = [1, 2]
+            .iter()
+            .map(|&secs| {
+                thread::spawn(move || {
+                    let conn = RfcConnection::builder()
+                        .set_param("dest", "TEST")
+                        .default_call_timeout(Duration::from_secs(secs))
+                        .build()
+                        .unwrap();
+                    let mut func = conn.get_function("RFC_PING_AND_WAIT").unwrap();
+                    func.set_int("SECONDS", 5).unwrap();
+                    let err = func.invoke().unwrap_err();
+                    (err.code(), start.elapsed())
+                })
+            })
+            .collect();
+        let elapsed: Vec<_> = calls
+            .into_iter()
+            .map(|call| {
+                let (code, elapsed) = call.join().unwrap();
+                assert_eq!(code, RfcErrorCode::Timeout);
+                elapsed
+            })
+            .collect();
+        assert!(elapsed[0] < Duration::from_secs(2));
+        assert!(elapsed[1] >= Duration::from_secs(2) && elapsed[1] < Duration::from_secs(4));
+
+        // The deadline of a call which returned in time does not cancel the next ones
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .default_call_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+        let mut func = conn.get_function("RFC_PING_AND_WAIT").unwrap();
+        func.set_int("SECONDS", 0).unwrap();
+        func.invoke().unwrap();
+        func.set_int("SECONDS", 2).unwrap();
+        func.invoke_with_timeout(None).unwrap();
+        assert!(!conn.is_broken());
+    }
+
+    #[test]
+    fn cancel_handle_test() {
+        use std::{thread, time::Duration};
+
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let handle = conn.cancel_handle();
+        let watchdog = thread::spawn(move || {
+            thread::sleep(Duration::from_secs(1));
+            handle.cancel()
+        });
+
+        let mut func = conn.get_function("RFC_PING_AND_WAIT").unwrap();
+        func.set_int("SECONDS", 10).unwrap();
+        let err = func.invoke_with_timeout(None).unwrap_err();
+        watchdog.join().unwrap().unwrap();
+        assert_eq!(err.code(), RfcErrorCode::Canceled);
+        assert!(matches!(err, RfcError::CommunicationFailure(_)));
+        assert!(conn.is_broken());
+        assert!(conn.is_poisoned());
+
+        // Cancelling is refused once the connection is closed
+        let handle = conn.cancel_handle();
+        drop(func);
+        drop(conn);
+        assert!(handle.cancel().is_err());
+    }
+
+    #[test]
+    fn owned_function_test() {
+        use std::thread;
+
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let handle = conn.cancel_handle();
+        let mut func = conn.get_function_owned("stfc_connection").unwrap();
+        func.set_chars("REQUTEXT", "owned").unwrap();
+
+        let echo = thread::spawn(move || {
+            func.invoke().unwrap();
+            func.get_chars("ECHOTEXT").unwrap()
+        });
+        assert_eq!(echo.join().unwrap().trim_end(), "owned");
+
+        // The connection is closed with the function owning it
+        assert!(handle.cancel().is_err());
+    }
+
+    #[test]
+    fn server_workers_test() {
+        use std::{
+            collections::HashSet,
+            sync::{Arc, Mutex},
+            thread,
+            time::{Duration, Instant},
+        };
+
+        let repository = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let mut server = RfcServer::builder()
+            .set_param("dest", "TEST_SERVER")
+            .set_workers(3)
+            .build()
+            .unwrap();
+
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+        let seen = Arc::clone(&threads);
+        server
+            .install_function(&repository, "STFC_CONNECTION", move |_, func| {
+                seen.lock().unwrap().insert(thread::current().id());
+                thread::sleep(Duration::from_secs(1));
+                let text = func.get_string("REQUTEXT")?;
+                func.set_string("ECHOTEXT", &text)
+            })
+            .unwrap();
+        server.start().unwrap();
+        thread::sleep(Duration::from_secs(2));
+
+        // Three calls taking a second each are handled at the same time
+        let start = Instant::now();
+        let clients: Vec<_> = (0..3)
+            .map(|i| {
+                thread::spawn(move || {
+                    let conn = RfcConnection::builder()
+                        .set_param("dest", "TEST_SERVER_CLIENT")
+                        .build()
+                        .unwrap();
+                    let mut func = conn.get_function("STFC_CONNECTION").unwrap();
+                    func.set_string("REQUTEXT", &format!("Call {}", i)).unwrap();
+                    func.invoke().unwrap();
+                    func.get_string("ECHOTEXT").unwrap()
+                })
+            })
+            .collect();
+        for (i, client) in clients.into_iter().enumerate() {
+            assert_eq!(client.join().unwrap().trim_end(), format!("Call {}", i));
+        }
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(threads.lock().unwrap().len(), 3);
+
+        server.shutdown();
+        assert!(!server.is_running());
+    }
+
+    #[test]
+    fn server_handlers_test() {
+        let repository = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let mut server = RfcServer::builder()
+            .set_param("dest", "TEST_SERVER")
+            .build()
+            .unwrap();
+        server
+            .install_function(&repository, "STFC_CONNECTION", |_, func| {
+                func.set_string("ECHOTEXT", "first")
+            })
+            .unwrap();
+
+        // Another server installing the same function does not replace the handler
+        let other = RfcServer::builder()
+            .set_param("dest", "TEST_SERVER")
+            .build()
+            .unwrap();
+        other
+            .install_function(&repository, "STFC_CONNECTION", |_, func| {
+                func.set_string("ECHOTEXT", "second")
+            })
+            .unwrap();
+
+        server.start().unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(2));
+
+        let client = RfcConnection::builder()
+            .set_param("dest", "TEST_SERVER_CLIENT")
+            .build()
+            .unwrap();
+        let func = client.get_function("STFC_CONNECTION").unwrap();
+        func.invoke().unwrap();
+        assert_eq!(func.get_string("ECHOTEXT").unwrap(), "first");
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn server_serve_test() {
+        use std::thread;
+
+        let repository = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let mut server = RfcServer::builder()
+            .set_param("dest", "TEST_SERVER")
+            .build()
+            .unwrap();
+        server
+            .install_function(&repository, "STFC_CONNECTION", |_, func| {
+                let text = func.get_string("REQUTEXT")?;
+                if text == "PANIC" {
+                    panic!("Asked to panic");
+                }
+                if text == "LONG PANIC" {
+                    panic!("Asked to panic at length {}", "!".repeat(4000));
+                }
+                func.set_string("ECHOTEXT", &text)
+            })
+            .unwrap();
+
+        let stop = server.stop_handle();
+        let client = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_secs(2));
+            let conn = RfcConnection::builder()
+                .set_param("dest", "TEST_SERVER_CLIENT")
+                .build()
+                .unwrap();
+            let mut func = conn.get_function("STFC_CONNECTION").unwrap();
+            func.set_string("REQUTEXT", "Served").unwrap();
+            func.invoke().unwrap();
+            let echo = func.get_string("ECHOTEXT").unwrap();
+
+            // A panic of the handler is reported to the caller instead of unwinding
+            func.set_string("REQUTEXT", "PANIC").unwrap();
+            let err = func.invoke().unwrap_err();
+
+            // A long panic message is cut short, the server keeps running
+            func.set_string("REQUTEXT", "LONG PANIC").unwrap();
+            let long_err = func.invoke().unwrap_err();
+            func.set_string("REQUTEXT", "Still served").unwrap();
+            func.invoke().unwrap();
+            stop.stop();
+            (echo, err, long_err)
+        });
+        server.serve().unwrap();
+        assert!(!server.is_running());
+
+        let (echo, err, long_err) = client.join().unwrap();
+        assert_eq!(echo.trim_end(), "Served");
+        assert!(err.message().contains("Asked to panic"));
+        assert!(long_err.message().contains("Asked to panic at length"));
+        assert!(long_err.message().len() < 4000);
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn rfc_handler_test() {
+        let repository = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .build()
+            .unwrap();
+        let mut server = RfcServer::builder()
+            .set_param("dest", "TEST_SERVER")
+            .build()
+            .unwrap();
+        shouting_echo::register(&server, &repository).unwrap();
+        server.start().unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(2));
+
+        let client = RfcConnection::builder()
+            .set_param("dest", "TEST_SERVER_CLIENT")
+            .build()
+            .unwrap();
+        let mut func = client.get_function("SCP_STRING_ECHO").unwrap();
+        func.set_string("IMP", "hello").unwrap();
+        func.invoke().unwrap();
+        assert_eq!(func.get_string("EXP").unwrap(), "HELLO");
+
+        // Errors with a key are raised as ABAP exceptions
+        func.set_string("IMP", "FAIL").unwrap();
+        let err = func.invoke().unwrap_err();
+        assert!(matches!(err, RfcError::AbapException(_)));
+        assert_eq!(err.code(), RfcErrorCode::AbapException);
+        assert_eq!(err.key(), "FAILED");
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn language_conversion_test() {
+        assert_eq!(sdk::lang_iso_to_sap("EN").unwrap(), "E");
+        assert_eq!(sdk::lang_iso_to_sap("de").unwrap(), "D");
+        assert_eq!(sdk::lang_sap_to_iso("E").unwrap(), "EN");
+        assert_eq!(sdk::lang_sap_to_iso("1").unwrap(), "ZH");
+
+        // Unknown codes are reported by the SDK, wrong lengths before reaching it
+        assert!(sdk::lang_iso_to_sap("QQ").is_err());
+        assert!(sdk::lang_iso_to_sap("ENG").is_err());
+        assert!(sdk::lang_sap_to_iso("").is_err());
+        let err = sdk::lang_iso_to_sap(&"E".repeat(5000)).unwrap_err();
+        assert_eq!(err.code(), RfcErrorCode::InvalidParameter);
+        assert!(err.message().contains("5000 characters"));
+
+        // Both forms log on in the same language
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .set_lang("D")
+            .build()
+            .unwrap();
+        assert_eq!(conn.attributes().unwrap().iso_language, "DE");
+    }
+
+    #[test]
+    fn trace_settings_test() {
+        use sdk::{TraceEncoding, TraceLevel};
+
+        // A directory below a regular file cannot be created
+        let err = sdk::set_trace_dir("/dev/null/trace").unwrap_err();
+        assert!(!err.message().is_empty());
+        sdk::set_trace_encoding(TraceEncoding::Utf8).unwrap();
+        sdk::set_destination_trace_level("TEST", TraceLevel::Brief).unwrap();
+        sdk::set_trace_level(TraceLevel::Off).unwrap();
+
+        let conn = RfcConnection::builder()
+            .set_param("dest", "TEST")
+            .set_trace(TraceLevel::Off)
+            .build()
+            .unwrap();
+        conn.set_trace_level(TraceLevel::Off).unwrap();
+        assert_eq!(TraceLevel::Verbose.to_string(), "2");
+    }
+
+    #[cfg(feature = "doctor")]
+    #[test]
+    fn doctor_test() {
+        let opts = doctor::DoctorOptions::new()
+            .set_ping_samples(3)
+            .set_functions(vec!["STFC_STRUCTURE", "Z_DOES_NOT_EXIST"]);
+        let report = doctor::run(RfcConnection::builder().set_param("dest", "TEST"), &opts);
+
+        // The missing function module is only a warning, every other step succeeds
+        assert_eq!(report.severity, doctor::Severity::Warning);
+        assert_eq!(report.exit_code(), 1);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["severity"], "warning");
+        let steps = json["steps"].as_array().unwrap();
+        let names: Vec<_> = steps
+            .iter()
+            .map(|step| step["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "SDK version",
+                "Open connection",
+                "Connection attributes",
+                "Ping latency",
+                "String echo",
+                "Structure echo",
+                "Metadata fetch",
+            ]
+        );
+        for step in steps {
+            let keys: Vec<_> = step.as_object().unwrap().keys().collect();
+            assert_eq!(
+                keys,
+                ["details", "duration_ms", "error", "name", "severity"]
+            );
+        }
+        assert_eq!(steps[3]["details"]["samples"], "3");
+        assert_eq!(steps[6]["severity"], "warning");
+        assert_eq!(steps[6]["details"]["Z_DOES_NOT_EXIST"], "failed");
+
+        // Steps needing a connection are skipped as errors when it cannot be opened
+        let report = doctor::run(
+            RfcConnection::builder().set_param("dest", "DOES_NOT_EXIST"),
+            &opts,
+        );
+        assert_eq!(report.exit_code(), 2);
+        assert_eq!(report.steps[0].severity, doctor::Severity::Ok);
+        assert!(report.steps[2..].iter().all(|step| step.error.is_some()));
     }
 }
@@ -0,0 +1,203 @@
+use crate::error::{Result, RfcError, RfcErrorInfo, THROTTLED_KEY};
+use sapnwrfc_sys::_RFC_RC;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time,
+};
+
+/// The default time a call may wait for the limiter before being refused.
+pub const DEFAULT_MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Waits shorter than this are not counted as throttled.
+const WAIT_THRESHOLD: Duration = Duration::from_millis(1);
+
+/// A client-side limit on the rate and concurrency of RFC calls.
+///
+/// Clones share the same limits, so one limiter can be given to several pools or handles to
+/// throttle them together.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    interval: Option<Duration>,
+    semaphore: Option<Arc<Semaphore>>,
+    max_wait: Duration,
+    state: Arc<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_slot: Mutex<Option<Instant>>,
+    waits: AtomicU64,
+    wait_nanos: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// The counters of a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThrottleMetrics {
+    /// The number of calls which had to wait before proceeding.
+    pub waits: u64,
+    /// The total time spent waiting by those calls.
+    pub wait_time: Duration,
+    /// The number of calls refused because they would have waited too long.
+    pub rejected: u64,
+}
+
+/// The right to make a call, held until the call is done.
+#[derive(Debug)]
+pub struct RatePermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter {
+    /// Create a limiter without any limit.
+    pub fn new() -> Self {
+        Self {
+            interval: None,
+            semaphore: None,
+            max_wait: DEFAULT_MAX_WAIT,
+            state: Default::default(),
+        }
+    }
+
+    /// Limit the number of calls started each second, calls are evenly spaced.
+    pub fn with_max_calls_per_second(mut self, calls: u32) -> Self {
+        self.interval = Some(Duration::from_secs(1) / calls.max(1));
+        self
+    }
+
+    /// Limit the number of calls running at the same time.
+    pub fn with_max_concurrent_calls(mut self, calls: usize) -> Self {
+        self.semaphore = Some(Arc::new(Semaphore::new(calls.max(1))));
+        self
+    }
+
+    /// Set how long a call may wait before being refused, 30 seconds by default.
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Wait for the right to make a call.
+    ///
+    /// Fails with a [`THROTTLED_KEY`] error without waiting when the call could not start within
+    /// the maximum wait.
+    pub async fn acquire(&self) -> Result<RatePermit> {
+        let start = Instant::now();
+        let deadline = start + self.max_wait;
+        let permit = match &self.semaphore {
+            Some(semaphore) => {
+                let acquire = Arc::clone(semaphore).acquire_owned();
+                match time::timeout_at(deadline.into(), acquire).await {
+                    Ok(Ok(permit)) => Some(permit),
+                    _ => return Err(self.throttled()),
+                }
+            }
+            None => None,
+        };
+        if let Some(interval) = self.interval {
+            let slot = {
+                let mut next_slot = self.state.next_slot.lock().unwrap();
+                let now = Instant::now();
+                let slot = next_slot.map_or(now, |next| next.max(now));
+                if slot > deadline {
+                    drop(next_slot);
+                    return Err(self.throttled());
+                }
+                *next_slot = Some(slot + interval);
+                slot
+            };
+            time::sleep_until(slot.into()).await;
+        }
+        let waited = start.elapsed();
+        if waited >= WAIT_THRESHOLD {
+            self.state.waits.fetch_add(1, Ordering::Relaxed);
+            self.state
+                .wait_nanos
+                .fetch_add(waited.as_nanos() as u64, Ordering::Relaxed);
+        }
+        Ok(RatePermit { _permit: permit })
+    }
+
+    /// Get the counters of the limiter.
+    pub fn metrics(&self) -> ThrottleMetrics {
+        ThrottleMetrics {
+            waits: self.state.waits.load(Ordering::Relaxed),
+            wait_time: Duration::from_nanos(self.state.wait_nanos.load(Ordering::Relaxed)),
+            rejected: self.state.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    fn throttled(&self) -> RfcError {
+        self.state.rejected.fetch_add(1, Ordering::Relaxed);
+        RfcErrorInfo::custom_with_code(
+            _RFC_RC::RFC_TIMEOUT,
+            &format!("Call throttled, could not start within {:?}", self.max_wait),
+        )
+        .with_key(THROTTLED_KEY)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rate_limit_test() {
+        let limiter = RateLimiter::new().with_max_calls_per_second(2);
+        let order = Mutex::new(Vec::new());
+        let call = |i| {
+            let limiter = &limiter;
+            let order = &order;
+            async move {
+                let _permit = limiter.acquire().await.unwrap();
+                order.lock().unwrap().push(i);
+            }
+        };
+
+        let start = Instant::now();
+        tokio::join!(call(0), call(1), call(2), call(3), call(4), call(5));
+        let elapsed = start.elapsed();
+
+        // The first call is immediate, the next ones are spaced by half a second
+        assert!(elapsed >= Duration::from_millis(2500), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(3500), "{:?}", elapsed);
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(limiter.metrics().waits, 5);
+        assert_eq!(limiter.metrics().rejected, 0);
+    }
+
+    #[tokio::test]
+    async fn max_wait_test() {
+        let limiter = RateLimiter::new()
+            .with_max_concurrent_calls(1)
+            .with_max_wait(Duration::from_millis(100));
+
+        let permit = limiter.acquire().await.unwrap();
+        let err = limiter.acquire().await.unwrap_err();
+        assert!(err.is_throttled());
+        assert_eq!(limiter.metrics().rejected, 1);
+
+        drop(permit);
+        limiter.acquire().await.unwrap();
+
+        let limiter = RateLimiter::new()
+            .with_max_calls_per_second(1)
+            .with_max_wait(Duration::from_millis(100));
+        limiter.acquire().await.unwrap();
+        assert!(limiter.acquire().await.unwrap_err().is_throttled());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    error::{Result, RfcError},
+    structure::RfcStructure,
+    table::RfcTable,
+};
+use sapnwrfc_sys::{RFCTYPE, _RFCTYPE, _RFC_RC};
+
+/// The version of the serialized format, bumped on any incompatible change.
+#[cfg(feature = "serde")]
+const FORMAT_VERSION: u32 = 1;
+
+/// A marker written at the start of every serialized value to detect format changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct FormatVersion;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FormatVersion {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(FORMAT_VERSION)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FormatVersion {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let version = <u32 as serde::Deserialize>::deserialize(deserializer)?;
+        if version == FORMAT_VERSION {
+            Ok(FormatVersion)
+        } else {
+            Err(serde::de::Error::custom(format!(
+                "unsupported snapshot format version {}, expected {}",
+                version, FORMAT_VERSION
+            )))
+        }
+    }
+}
+
+/// The value of a field detached from its container.
+///
+/// Elementary values are kept in their string form, as read and written by the SDK.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedValue {
+    Text(String),
+    Structure(OwnedStructure),
+    Table(OwnedTable),
+}
+
+/// A field of an owned structure.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedField {
+    name: String,
+    rfc_type: RFCTYPE,
+    value: OwnedValue,
+}
+
+impl OwnedField {
+    /// Get the name of the field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the RFC type of the field.
+    pub fn rfc_type(&self) -> RFCTYPE {
+        self.rfc_type
+    }
+
+    /// Get the value of the field.
+    pub fn value(&self) -> &OwnedValue {
+        &self.value
+    }
+}
+
+/// A copy of an RFC structure which does not depend on its function or connection.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedStructure {
+    version: FormatVersion,
+    type_name: String,
+    fields: Vec<OwnedField>,
+}
+
+impl OwnedStructure {
+    /// Copy the fields of a structure.
+    pub fn from_structure(structure: &RfcStructure) -> Result<Self> {
+        let fields = structure
+            .field_layout()?
+            .into_iter()
+            .map(|(name, desc)| {
+                let value = match desc.rfc_type {
+                    _RFCTYPE::RFCTYPE_STRUCTURE => OwnedValue::Structure(Self::from_structure(
+                        &structure.get_structure(&name)?,
+                    )?),
+                    _RFCTYPE::RFCTYPE_TABLE => {
+                        OwnedValue::Table(OwnedTable::from_table(&structure.get_table(&name)?)?)
+                    }
+                    _ => OwnedValue::Text(structure.get_string(&name)?),
+                };
+                Ok(OwnedField {
+                    name,
+                    rfc_type: desc.rfc_type,
+                    value,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            version: FormatVersion,
+            type_name: structure.name(),
+            fields,
+        })
+    }
+
+    /// Get the name of the structure type.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Get the fields of the structure.
+    pub fn fields(&self) -> &[OwnedField] {
+        &self.fields
+    }
+
+    /// Get the value of a field by name.
+    pub fn get(&self, name: &str) -> Option<&OwnedValue> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| &field.value)
+    }
+
+    /// Write the fields into a structure of the same type.
+    pub fn apply_to(&self, target: &mut RfcStructure) -> Result<()> {
+        check_type_name(&self.type_name, &target.name())?;
+        for field in &self.fields {
+            match &field.value {
+                OwnedValue::Text(value) => target.set_string(&field.name, value)?,
+                OwnedValue::Structure(value) => {
+                    value.apply_to(&mut target.get_structure(&field.name)?)?
+                }
+                OwnedValue::Table(value) => value.apply_to(&mut target.get_table(&field.name)?)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+impl OwnedStructure {
+    pub(crate) fn from_values(type_name: &str, fields: Vec<(&str, RFCTYPE, OwnedValue)>) -> Self {
+        Self {
+            version: FormatVersion,
+            type_name: type_name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(name, rfc_type, value)| OwnedField {
+                    name: name.to_owned(),
+                    rfc_type,
+                    value,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A copy of an RFC table which does not depend on its function or connection.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedTable {
+    version: FormatVersion,
+    type_name: String,
+    rows: Vec<OwnedStructure>,
+}
+
+impl OwnedTable {
+    /// Copy the rows of a table.
+    pub fn from_table(table: &RfcTable) -> Result<Self> {
+        let rows = (0..table.row_count()?)
+            .map(|index| OwnedStructure::from_structure(&table.get_row(index)?))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            version: FormatVersion,
+            type_name: table.name(),
+            rows,
+        })
+    }
+
+    /// Get the name of the row type.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Get the rows of the table.
+    pub fn rows(&self) -> &[OwnedStructure] {
+        &self.rows
+    }
+
+    /// Replace the rows of a table of the same type.
+    pub fn apply_to(&self, target: &mut RfcTable) -> Result<()> {
+        check_type_name(&self.type_name, &target.name())?;
+        target.clear_rows()?;
+        for row in &self.rows {
+            row.apply_to(&mut target.append_row()?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+impl OwnedTable {
+    pub(crate) fn from_rows(type_name: &str, rows: Vec<OwnedStructure>) -> Self {
+        Self {
+            version: FormatVersion,
+            type_name: type_name.to_owned(),
+            rows,
+        }
+    }
+}
+
+fn check_type_name(expected: &str, actual: &str) -> Result<()> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(RfcError::custom_with_code(
+            _RFC_RC::RFC_INVALID_PARAMETER,
+            &format!("Cannot apply a value of type {} to {}", expected, actual),
+        ))
+    }
+}
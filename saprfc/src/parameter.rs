@@ -0,0 +1,422 @@
+use crate::{
+    data_container::{DataDesc, SetOutcome},
+    error::{Result, RfcError},
+    function::RfcFunction,
+    owned::{OwnedStructure, OwnedTable},
+    structure::RfcStructure,
+    table::RfcTable,
+    types::{RfcDirection, RfcType},
+    uc,
+};
+use sapnwrfc_sys::{
+    RFC_ABAP_NAME, RFC_DIRECTION, RFC_PARAMETER_DESC, _RFCTYPE, _RFC_DIRECTION, _RFC_RC,
+};
+
+/// A function parameter which can be provided before invoking the function.
+///
+/// Only import, changing and table parameters can be obtained as writable.
+pub struct RfcParamMut<'func, 'conn> {
+    func: &'func mut RfcFunction<'conn>,
+    name: RFC_ABAP_NAME,
+    desc: DataDesc,
+}
+
+impl<'func, 'conn> RfcParamMut<'func, 'conn> {
+    pub(crate) fn new(
+        func: &'func mut RfcFunction<'conn>,
+        desc: &RFC_PARAMETER_DESC,
+    ) -> Result<Self> {
+        check_direction(desc, _RFC_DIRECTION::RFC_EXPORT, "written")?;
+        Ok(Self {
+            func,
+            name: desc.name,
+            desc: desc.into(),
+        })
+    }
+
+    /// Get the name of the parameter.
+    pub fn name(&self) -> String {
+        uc::to_string_truncate(&self.name).expect("Unexpected string decode failure with name")
+    }
+
+    pub fn set_int(&mut self, value: i32) -> Result<()> {
+        self.func.data_mut().set_int(&self.name, value)
+    }
+
+    /// Get the value as an integer, an `INT8` value which does not fit is an error.
+    pub fn get_int(&self) -> Result<i32> {
+        self.func.data().get_int_checked(&self.name, &self.desc)
+    }
+
+    /// Set an `INT8` value, or the value of a smaller integer parameter when it fits.
+    pub fn set_i64(&mut self, value: i64) -> Result<()> {
+        self.func.data_mut().set_i64(&self.name, value, &self.desc)
+    }
+
+    /// Get the value of an `INT8` parameter or of any smaller integer parameter.
+    pub fn get_i64(&self) -> Result<i64> {
+        self.func.data().get_i64(&self.name, &self.desc)
+    }
+
+    /// Check if the parameter is a binary floating point number.
+    pub fn is_float(&self) -> bool {
+        self.desc.rfc_type == _RFCTYPE::RFCTYPE_FLOAT
+    }
+
+    /// Check if the parameter holds binary data, of a fixed length or an `XSTRING`.
+    pub fn is_bytes(&self) -> bool {
+        is_bytes(&self.desc)
+    }
+
+    /// Set binary data, fixed-length parameters are padded with zeros.
+    pub fn set_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.func
+            .data_mut()
+            .set_bytes(&self.name, value, &self.desc)
+    }
+
+    /// Get the whole binary value of the parameter.
+    pub fn get_bytes(&self) -> Result<Vec<u8>> {
+        self.func.data().get_bytes(&self.name, &self.desc)
+    }
+
+    /// Check if the parameter is a packed or decimal float number.
+    pub fn is_decimal(&self) -> bool {
+        is_decimal(&self.desc)
+    }
+
+    /// Set a packed or decimal float number given as text, the sign can trail the digits.
+    pub fn set_decimal_str(&mut self, value: &str) -> Result<()> {
+        self.func
+            .data_mut()
+            .set_decimal_text(&self.name, value, &self.desc)
+    }
+
+    /// Get a packed or decimal float number as text with a leading sign.
+    pub fn get_decimal_str(&self) -> Result<String> {
+        self.func.data().get_decimal_text(&self.name, &self.desc)
+    }
+
+    #[cfg(feature = "decimal")]
+    pub fn set_decimal(&mut self, value: rust_decimal::Decimal) -> Result<()> {
+        self.set_decimal_str(&value.to_string())
+    }
+
+    #[cfg(feature = "decimal")]
+    pub fn get_decimal(&self) -> Result<rust_decimal::Decimal> {
+        crate::data_container::to_decimal(&self.get_decimal_str()?)
+    }
+
+    pub fn set_float(&mut self, value: f64) -> Result<()> {
+        self.func.data_mut().set_float(&self.name, value)
+    }
+
+    pub fn get_float(&self) -> Result<f64> {
+        self.func.data().get_float(&self.name)
+    }
+
+    pub fn set_chars(&mut self, value: &str) -> Result<SetOutcome> {
+        self.func
+            .data_mut()
+            .set_chars(&self.name, value, self.desc.uc_length / 2)
+    }
+
+    pub fn get_chars(&self) -> Result<String> {
+        self.func
+            .data()
+            .get_chars(&self.name, self.desc.uc_length / 2)
+    }
+
+    /// Set the digits of a `NUMC` parameter, padded with leading zeros.
+    pub fn set_num(&mut self, value: &str) -> Result<()> {
+        self.func.data_mut().set_num(&self.name, value, &self.desc)
+    }
+
+    /// Set a number in a `NUMC` parameter, padded with leading zeros.
+    pub fn set_num_from_u64(&mut self, value: u64) -> Result<()> {
+        self.set_num(&value.to_string())
+    }
+
+    /// Get the digits of a `NUMC` parameter, with their leading zeros.
+    pub fn get_num(&self) -> Result<String> {
+        self.func.data().get_num(&self.name, &self.desc)
+    }
+
+    /// Set the value as text, fixed-length character parameters are padded with blanks.
+    pub fn set_string(&mut self, value: &str) -> Result<()> {
+        self.func.data_mut().set_text(&self.name, value, &self.desc)
+    }
+
+    /// Get the value as text, trailing blanks of fixed-length character parameters are removed.
+    pub fn get_string(&self) -> Result<String> {
+        self.func.data().get_text(&self.name, &self.desc)
+    }
+
+    /// Reset the parameter to the initial value of its type.
+    pub fn clear(&mut self) -> Result<()> {
+        self.func.data_mut().clear(&self.name, &self.desc)
+    }
+
+    /// Exclude the parameter from the call.
+    pub fn deactivate(&mut self) -> Result<()> {
+        let name = self.name();
+        self.func.set_parameter_active(&name, false)
+    }
+
+    pub fn get_structure(&self) -> Result<RfcStructure<'_>> {
+        self.func.data().get_structure(&self.name)
+    }
+
+    pub fn get_table(&self) -> Result<RfcTable<'_>> {
+        self.func.data().get_table(&self.name)
+    }
+
+    /// Copy a structure of the same type into the parameter.
+    pub fn set_structure(&mut self, value: &RfcStructure) -> Result<()> {
+        self.func.data_mut().set_structure(&self.name, value)
+    }
+
+    /// Copy a table with rows of the same type into the parameter.
+    pub fn set_table(&mut self, value: &RfcTable) -> Result<()> {
+        self.func.data_mut().set_table(&self.name, value)
+    }
+}
+
+/// A function parameter holding a result of the function.
+///
+/// Only export, changing and table parameters can be obtained as read-only. Structures and tables
+/// are read as owned copies so they cannot be modified through this parameter.
+pub struct RfcParamRef<'func, 'conn> {
+    func: &'func RfcFunction<'conn>,
+    name: RFC_ABAP_NAME,
+    desc: DataDesc,
+}
+
+impl<'func, 'conn> RfcParamRef<'func, 'conn> {
+    pub(crate) fn new(func: &'func RfcFunction<'conn>, desc: &RFC_PARAMETER_DESC) -> Result<Self> {
+        check_direction(desc, _RFC_DIRECTION::RFC_IMPORT, "read")?;
+        Ok(Self {
+            func,
+            name: desc.name,
+            desc: desc.into(),
+        })
+    }
+
+    /// Get the name of the parameter.
+    pub fn name(&self) -> String {
+        uc::to_string_truncate(&self.name).expect("Unexpected string decode failure with name")
+    }
+
+    /// Get the value as an integer, an `INT8` value which does not fit is an error.
+    pub fn get_int(&self) -> Result<i32> {
+        self.func.data().get_int_checked(&self.name, &self.desc)
+    }
+
+    /// Get the value of an `INT8` parameter or of any smaller integer parameter.
+    pub fn get_i64(&self) -> Result<i64> {
+        self.func.data().get_i64(&self.name, &self.desc)
+    }
+
+    /// Check if the parameter holds binary data, of a fixed length or an `XSTRING`.
+    pub fn is_bytes(&self) -> bool {
+        is_bytes(&self.desc)
+    }
+
+    /// Get the whole binary value of the parameter.
+    pub fn get_bytes(&self) -> Result<Vec<u8>> {
+        self.func.data().get_bytes(&self.name, &self.desc)
+    }
+
+    /// Check if the parameter is a binary floating point number.
+    pub fn is_float(&self) -> bool {
+        self.desc.rfc_type == _RFCTYPE::RFCTYPE_FLOAT
+    }
+
+    /// Check if the parameter is a packed or decimal float number.
+    pub fn is_decimal(&self) -> bool {
+        is_decimal(&self.desc)
+    }
+
+    /// Get a packed or decimal float number as text with a leading sign.
+    pub fn get_decimal_str(&self) -> Result<String> {
+        self.func.data().get_decimal_text(&self.name, &self.desc)
+    }
+
+    #[cfg(feature = "decimal")]
+    pub fn get_decimal(&self) -> Result<rust_decimal::Decimal> {
+        crate::data_container::to_decimal(&self.get_decimal_str()?)
+    }
+
+    pub fn get_float(&self) -> Result<f64> {
+        self.func.data().get_float(&self.name)
+    }
+
+    pub fn get_chars(&self) -> Result<String> {
+        self.func
+            .data()
+            .get_chars(&self.name, self.desc.uc_length / 2)
+    }
+
+    /// Get the digits of a `NUMC` parameter, with their leading zeros.
+    pub fn get_num(&self) -> Result<String> {
+        self.func.data().get_num(&self.name, &self.desc)
+    }
+
+    /// Get the value as text, trailing blanks of fixed-length character parameters are removed.
+    pub fn get_string(&self) -> Result<String> {
+        self.func.data().get_text(&self.name, &self.desc)
+    }
+
+    /// Get a copy of the structure held by the parameter.
+    pub fn get_structure(&self) -> Result<OwnedStructure> {
+        OwnedStructure::from_structure(&self.func.data().get_structure(&self.name)?)
+    }
+
+    /// Get a copy of the table held by the parameter.
+    pub fn get_table(&self) -> Result<OwnedTable> {
+        OwnedTable::from_table(&self.func.data().get_table(&self.name)?)
+    }
+}
+
+/// The description of a function parameter, as found when enumerating the parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RfcParameterDesc {
+    name: String,
+    rfc_type: RfcType,
+    direction: RfcDirection,
+    nuc_length: u32,
+    uc_length: u32,
+    decimals: u32,
+    optional: bool,
+    default_value: String,
+    text: String,
+}
+
+impl RfcParameterDesc {
+    pub(crate) fn from_desc(desc: &RFC_PARAMETER_DESC) -> Result<Self> {
+        Ok(Self {
+            name: uc::to_string_truncate(&desc.name)?,
+            rfc_type: desc.type_.into(),
+            direction: desc.direction.into(),
+            nuc_length: desc.nucLength,
+            uc_length: desc.ucLength,
+            decimals: desc.decimals,
+            optional: desc.optional != 0,
+            default_value: uc::to_string_truncate(&desc.defaultValue)?
+                .trim()
+                .to_owned(),
+            text: uc::to_string_truncate(&desc.parameterText)?
+                .trim_end()
+                .to_owned(),
+        })
+    }
+
+    /// Get the name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the type of the parameter.
+    pub fn rfc_type(&self) -> RfcType {
+        self.rfc_type
+    }
+
+    /// Get the direction in which the parameter is passed.
+    pub fn direction(&self) -> RfcDirection {
+        self.direction
+    }
+
+    /// Check if the parameter is provided by the caller only.
+    pub fn is_import(&self) -> bool {
+        self.direction == RfcDirection::Import
+    }
+
+    /// Check if the parameter is returned by the function only.
+    pub fn is_export(&self) -> bool {
+        self.direction == RfcDirection::Export
+    }
+
+    /// Check if the parameter is both provided by the caller and returned by the function.
+    pub fn is_changing(&self) -> bool {
+        self.direction == RfcDirection::Changing
+    }
+
+    /// Check if the parameter is declared under the `TABLES` of the function.
+    pub fn is_table(&self) -> bool {
+        self.direction == RfcDirection::Tables
+    }
+
+    /// Check if the parameter can be left out of a call.
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Get the length of the value in the non-Unicode layout, in bytes.
+    pub fn nuc_length(&self) -> u32 {
+        self.nuc_length
+    }
+
+    /// Get the length of the value in the Unicode layout, in bytes.
+    pub fn uc_length(&self) -> u32 {
+        self.uc_length
+    }
+
+    /// Get the number of decimals of a packed number.
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    /// Get the length declared in the ABAP dictionary, in characters for character-like types
+    /// and in bytes otherwise.
+    ///
+    /// Strings, structures and tables have no declared length and return zero.
+    pub fn length(&self) -> u32 {
+        match self.rfc_type {
+            RfcType::String | RfcType::XString | RfcType::Structure | RfcType::Table => 0,
+            _ => self.nuc_length,
+        }
+    }
+
+    /// Get the declared default value, as written in ABAP.
+    pub fn default_value(&self) -> Option<&str> {
+        Some(self.default_value.as_str()).filter(|value| !value.is_empty())
+    }
+
+    /// Get the short text describing the parameter.
+    pub fn description(&self) -> Option<&str> {
+        Some(self.text.as_str()).filter(|text| !text.is_empty())
+    }
+}
+
+fn check_direction(desc: &RFC_PARAMETER_DESC, excluded: RFC_DIRECTION, access: &str) -> Result<()> {
+    if desc.direction != excluded {
+        return Ok(());
+    }
+    Err(RfcError::custom_with_code(
+        _RFC_RC::RFC_INVALID_PARAMETER,
+        &format!(
+            "Parameter {} is an {} parameter and cannot be {}",
+            uc::to_string_truncate(&desc.name)?,
+            if excluded == _RFC_DIRECTION::RFC_IMPORT {
+                "import"
+            } else {
+                "export"
+            },
+            access
+        ),
+    ))
+}
+
+fn is_bytes(desc: &DataDesc) -> bool {
+    matches!(
+        desc.rfc_type,
+        _RFCTYPE::RFCTYPE_BYTE | _RFCTYPE::RFCTYPE_XSTRING
+    )
+}
+
+fn is_decimal(desc: &DataDesc) -> bool {
+    matches!(
+        desc.rfc_type,
+        _RFCTYPE::RFCTYPE_BCD | _RFCTYPE::RFCTYPE_DECF16 | _RFCTYPE::RFCTYPE_DECF34
+    )
+}
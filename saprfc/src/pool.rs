@@ -1,33 +1,523 @@
+#[cfg(feature = "rate-limit")]
+use crate::limit::RateLimiter;
 use crate::{
     connection::{RfcConnection, RfcConnectionBuilder},
-    error::RfcErrorInfo,
+    error::RfcError,
+    events::ConnectionEventKind,
+    function::RfcFunction,
 };
 use async_trait::async_trait;
 use deadpool::{
-    managed::{self, RecycleError},
+    managed::{self, Hook, HookError, HookErrorCause, PoolError, RecycleError, TimeoutType},
     Runtime,
 };
-use deadpool_sync::SyncWrapper;
+use deadpool_sync::{InteractError, SyncWrapper};
+use sapnwrfc_sys::_RFC_RC;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+/// The default time allowed to fetch each prefetched function description.
+const DEFAULT_PREFETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A pool of RFC connections.
+pub type Pool = managed::Pool<Manager>;
+
+/// A source of connection builders, asked for a new one every time the pool opens a connection.
+///
+/// Implementations can fetch short-lived credentials from a secret store on each call. A plain
+/// [`RfcConnectionBuilder`] is a factory always giving the same parameters.
+#[async_trait]
+pub trait ConnectionFactory: Send + Sync {
+    /// Get the builder of the next connection.
+    async fn make_builder(&self) -> Result<RfcConnectionBuilder, RfcError>;
+
+    /// Called when a connection was refused at logon, to invalidate cached credentials before
+    /// the next attempt.
+    async fn on_auth_failure(&self, _err: &RfcError) {}
+}
+
+#[async_trait]
+impl ConnectionFactory for RfcConnectionBuilder {
+    async fn make_builder(&self) -> Result<RfcConnectionBuilder, RfcError> {
+        Ok(self.clone())
+    }
+}
+
+#[async_trait]
+impl<F: ConnectionFactory + ?Sized> ConnectionFactory for Arc<F> {
+    async fn make_builder(&self) -> Result<RfcConnectionBuilder, RfcError> {
+        (**self).make_builder().await
+    }
+
+    async fn on_auth_failure(&self, err: &RfcError) {
+        (**self).on_auth_failure(err).await
+    }
+}
+
+/// How a connection is checked before it is handed out again.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecycleMethod {
+    /// Only check the connection handle, without a round trip to the backend.
+    Fast,
+    /// Ping the backend, the default.
+    #[default]
+    Ping,
+    /// Check the connection handle and ping the backend every given number of recycles.
+    Verified { ping_every: u32 },
+}
 
 pub struct Manager {
-    builder: RfcConnectionBuilder,
+    factory: Box<dyn ConnectionFactory>,
     runtime: Runtime,
+    prefetch_functions: RwLock<Vec<String>>,
+    prefetch_required: bool,
+    prefetch_timeout: Duration,
+    prefetch_failures: AtomicU64,
+    hook_failures: Arc<AtomicU64>,
+    recycle_method: RecycleMethod,
+    recycles: AtomicU64,
+    #[cfg(feature = "rate-limit")]
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl Manager {
     pub fn new(builder: RfcConnectionBuilder, runtime: Runtime) -> Manager {
-        Self { builder, runtime }
+        Self::from_factory(builder, runtime)
+    }
+
+    /// Create a manager getting the parameters of every new connection from a factory.
+    pub fn from_factory<F>(factory: F, runtime: Runtime) -> Manager
+    where
+        F: ConnectionFactory + 'static,
+    {
+        Self {
+            factory: Box::new(factory),
+            runtime,
+            prefetch_functions: RwLock::new(Vec::new()),
+            prefetch_required: false,
+            prefetch_timeout: DEFAULT_PREFETCH_TIMEOUT,
+            prefetch_failures: AtomicU64::new(0),
+            hook_failures: Arc::new(AtomicU64::new(0)),
+            recycle_method: RecycleMethod::default(),
+            recycles: AtomicU64::new(0),
+            #[cfg(feature = "rate-limit")]
+            rate_limiter: None,
+        }
+    }
+
+    /// Set the function modules whose description is fetched on every new connection.
+    pub fn with_prefetch_functions<I, S>(self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        *self.prefetch_functions.write().unwrap() = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set whether a failure to prefetch a function description fails the connection creation.
+    ///
+    /// By default failures are only logged and counted.
+    pub fn with_prefetch_required(mut self, required: bool) -> Self {
+        self.prefetch_required = required;
+        self
+    }
+
+    /// Set the time allowed to fetch each function description, 30 seconds by default.
+    ///
+    /// A connection on which a fetch times out is broken and its creation fails.
+    pub fn with_prefetch_timeout(mut self, timeout: Duration) -> Self {
+        self.prefetch_timeout = timeout;
+        self
+    }
+
+    /// Set how connections are checked before they are reused, a ping by default.
+    pub fn with_recycle_method(mut self, method: RecycleMethod) -> Self {
+        self.recycle_method = method;
+        self
+    }
+
+    /// Get how connections are checked before they are reused.
+    pub fn recycle_method(&self) -> RecycleMethod {
+        self.recycle_method
+    }
+
+    /// Throttle the function calls made with [`call`] and the closures run with [`interact`] on
+    /// the connections of the pool.
+    #[cfg(feature = "rate-limit")]
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Get the rate limiter of the pool.
+    #[cfg(feature = "rate-limit")]
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Add a function module to prefetch on the connections created from now on.
+    pub fn add_prefetch_function(&self, name: &str) {
+        let mut names = self.prefetch_functions.write().unwrap();
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_owned());
+        }
+    }
+
+    /// Get the function modules prefetched on new connections.
+    pub fn prefetch_functions(&self) -> Vec<String> {
+        self.prefetch_functions.read().unwrap().clone()
+    }
+
+    /// Get the number of function descriptions that could not be prefetched so far.
+    pub fn prefetch_failures(&self) -> u64 {
+        self.prefetch_failures.load(Ordering::Relaxed)
+    }
+
+    /// Create a hook running a check on every new connection before it is first handed out.
+    ///
+    /// The check runs after the connection is opened and its functions prefetched. When it fails
+    /// the connection is closed and the error is returned from [`Pool::get`](managed::Pool::get).
+    ///
+    /// ```no_run
+    /// # use saprfc::{pool::{Manager, Pool}, RfcConnection};
+    /// # use deadpool::Runtime;
+    /// let manager = Manager::new(RfcConnection::builder().set_param("dest", "TEST"), Runtime::Tokio1);
+    /// let hook = manager.post_create_hook(|conn| conn.get_function("STFC_CONNECTION")?.invoke());
+    /// let pool = Pool::builder(manager).post_create(hook).build().unwrap();
+    /// ```
+    pub fn post_create_hook<F>(&self, check: F) -> Hook<Self>
+    where
+        F: Fn(&RfcConnection) -> Result<(), RfcError> + Send + Sync + 'static,
+    {
+        self.hook(check, |err| HookError::Abort(HookErrorCause::Backend(err)))
+    }
+
+    /// Create a hook running a check on a connection before it is recycled.
+    ///
+    /// The check runs before the broken state and ping checks of the manager. When it fails the
+    /// connection is closed and the pool moves on to the next idle connection or opens a new one.
+    pub fn pre_recycle_hook<F>(&self, check: F) -> Hook<Self>
+    where
+        F: Fn(&RfcConnection) -> Result<(), RfcError> + Send + Sync + 'static,
+    {
+        self.hook(check, |err| {
+            HookError::Continue(Some(HookErrorCause::Backend(err)))
+        })
+    }
+
+    /// Get the number of connections discarded by failed hook checks so far.
+    pub fn hook_failures(&self) -> u64 {
+        self.hook_failures.load(Ordering::Relaxed)
+    }
+
+    fn hook<F>(&self, check: F, on_failure: fn(RfcError) -> HookError<RfcError>) -> Hook<Self>
+    where
+        F: Fn(&RfcConnection) -> Result<(), RfcError> + Send + Sync + 'static,
+    {
+        let check = Arc::new(check);
+        let failures = Arc::clone(&self.hook_failures);
+        Hook::async_fn(move |conn: &mut SyncWrapper<RfcConnection>, _| {
+            let check = Arc::clone(&check);
+            let failures = Arc::clone(&failures);
+            Box::pin(async move {
+                conn.interact(move |conn| check(conn))
+                    .await
+                    .map_err(|err| RfcError::custom(&err.to_string()))
+                    .and_then(|res| res)
+                    .map_err(|err| {
+                        failures.fetch_add(1, Ordering::Relaxed);
+                        log::warn!("Connection check failed: {}", err);
+                        on_failure(err)
+                    })
+            })
+        })
+    }
+
+    async fn prefetch(&self, conn: &SyncWrapper<RfcConnection>) -> Result<(), RfcError> {
+        let names = self.prefetch_functions();
+        if names.is_empty() {
+            return Ok(());
+        }
+        let timeout = self.prefetch_timeout;
+        let (failures, broken) = conn
+            .interact(move |conn| {
+                let mut failures = Vec::new();
+                for name in names {
+                    if let Err(err) = conn.get_function_with_timeout(&name, timeout) {
+                        failures.push((name, err));
+                        if conn.is_broken() {
+                            break;
+                        }
+                    }
+                }
+                (failures, conn.is_broken())
+            })
+            .await
+            .map_err(|err| RfcError::custom(&err.to_string()))?;
+        for (name, err) in failures {
+            self.prefetch_failures.fetch_add(1, Ordering::Relaxed);
+            log::warn!("Prefetch of function {} failed: {}", name, err);
+            if self.prefetch_required || broken {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Call a function module with a pooled connection on a blocking thread.
+///
+/// The `setup` closure provides the parameters before the invocation and the `extract` closure
+/// reads the results after. The call first waits for the rate limiter of the pool, if any.
+pub async fn call<S, E, T>(
+    conn: &managed::Object<Manager>,
+    name: &str,
+    setup: S,
+    extract: E,
+) -> Result<T, RfcError>
+where
+    S: FnOnce(&mut RfcFunction) -> Result<(), RfcError> + Send + 'static,
+    E: FnOnce(&RfcFunction) -> Result<T, RfcError> + Send + 'static,
+    T: Send + 'static,
+{
+    let name = name.to_owned();
+    interact(conn, move |conn| {
+        let mut func = conn.get_function(&name)?;
+        setup(&mut func)?;
+        func.invoke()?;
+        extract(&func)
+    })
+    .await
+}
+
+/// Run a closure with a pooled connection on a blocking thread.
+///
+/// The closure first waits for the rate limiter of the pool, if any, and counts as a single call
+/// however many calls it makes.
+pub async fn interact<F, T>(conn: &managed::Object<Manager>, f: F) -> Result<T, RfcError>
+where
+    F: FnOnce(&RfcConnection) -> Result<T, RfcError> + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(feature = "rate-limit")]
+    let _permit =
+        match managed::Object::pool(conn).and_then(|pool| pool.manager().rate_limiter.clone()) {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+    conn.interact(move |conn| f(conn)).await?
+}
+
+/// Open connections ahead of the first calls, until the pool holds at least `count` of them.
+///
+/// The count is capped to the maximum size of the pool. Stops at the first connection which
+/// fails to open and returns its error.
+pub async fn warm_up(pool: &Pool, count: usize) -> Result<(), RfcError> {
+    let count = count.min(pool.status().max_size);
+    let mut held = Vec::with_capacity(count);
+    for _ in 0..count {
+        held.push(pool.get().await?);
+    }
+    // Handing the connections back leaves them idle in the pool
+    drop(held);
+    Ok(())
+}
+
+/// A pool of RFC connections, running the calls on blocking threads.
+///
+/// The pool can be cloned cheaply to share it between tasks.
+#[derive(Clone)]
+pub struct RfcPool {
+    pool: Pool,
+}
+
+impl RfcPool {
+    /// Create a builder of a pool opening its connections with the given parameters.
+    pub fn builder(conn_builder: RfcConnectionBuilder) -> RfcPoolBuilder {
+        RfcPoolBuilder::new(conn_builder)
+    }
+
+    /// Get the underlying deadpool pool.
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
+    /// Get a connection from the pool, waiting for one to be available.
+    pub async fn get(&self) -> Result<managed::Object<Manager>, RfcError> {
+        Ok(self.pool.get().await?)
+    }
+
+    /// Open connections ahead of the first calls, see [`warm_up`].
+    pub async fn warm_up(&self, count: usize) -> Result<(), RfcError> {
+        warm_up(&self.pool, count).await
+    }
+
+    /// Run a closure with a pooled connection on a blocking thread, see [`interact`].
+    pub async fn with_connection<F, T>(&self, f: F) -> Result<T, RfcError>
+    where
+        F: FnOnce(&RfcConnection) -> Result<T, RfcError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = self.get().await?;
+        interact(&conn, f).await
+    }
+
+    /// Call a function module with a pooled connection on a blocking thread.
+    ///
+    /// See [`call`] for the `setup` and `extract` closures.
+    pub async fn call<S, E, T>(&self, name: &str, setup: S, extract: E) -> Result<T, RfcError>
+    where
+        S: FnOnce(&mut RfcFunction) -> Result<(), RfcError> + Send + 'static,
+        E: FnOnce(&RfcFunction) -> Result<T, RfcError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = self.get().await?;
+        call(&conn, name, setup, extract).await
+    }
+}
+
+/// A builder of [`RfcPool`].
+pub struct RfcPoolBuilder {
+    conn_builder: RfcConnectionBuilder,
+    runtime: Option<Runtime>,
+    max_size: Option<usize>,
+    timeouts: managed::Timeouts,
+    recycle_method: RecycleMethod,
+}
+
+impl RfcPoolBuilder {
+    fn new(conn_builder: RfcConnectionBuilder) -> Self {
+        Self {
+            conn_builder,
+            runtime: None,
+            max_size: None,
+            timeouts: managed::Timeouts::new(),
+            recycle_method: RecycleMethod::default(),
+        }
+    }
+
+    /// Set the async runtime running the blocking calls, it is required.
+    pub fn set_runtime(mut self, runtime: Runtime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Set the maximum number of connections, four per physical CPU core by default.
+    pub fn set_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Set the time allowed to open a new connection.
+    pub fn set_create_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.create = Some(timeout);
+        self
+    }
+
+    /// Set the time allowed to check a connection before it is reused.
+    pub fn set_recycle_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.recycle = Some(timeout);
+        self
+    }
+
+    /// Set the time allowed to wait for a connection when all of them are in use.
+    pub fn set_wait_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.wait = Some(timeout);
+        self
+    }
+
+    /// Set how connections are checked before they are reused, a ping by default.
+    pub fn set_recycle_method(mut self, method: RecycleMethod) -> Self {
+        self.recycle_method = method;
+        self
+    }
+
+    pub fn build(self) -> Result<RfcPool, RfcError> {
+        let runtime = self.runtime.ok_or_else(|| {
+            RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_PARAMETER,
+                "A runtime is required to build a connection pool",
+            )
+        })?;
+        let manager =
+            Manager::new(self.conn_builder, runtime).with_recycle_method(self.recycle_method);
+        let mut builder = Pool::builder(manager)
+            .runtime(runtime)
+            .timeouts(self.timeouts);
+        if let Some(max_size) = self.max_size {
+            builder = builder.max_size(max_size);
+        }
+        let pool = builder
+            .build()
+            .map_err(|err| RfcError::custom(&err.to_string()))?;
+        Ok(RfcPool { pool })
+    }
+}
+
+impl From<PoolError<RfcError>> for RfcError {
+    fn from(err: PoolError<RfcError>) -> Self {
+        match err {
+            PoolError::Backend(err) => err,
+            PoolError::Timeout(kind) => {
+                let what = match kind {
+                    TimeoutType::Wait => "waiting for",
+                    TimeoutType::Create => "opening",
+                    TimeoutType::Recycle => "checking",
+                };
+                RfcError::custom_with_code(
+                    _RFC_RC::RFC_TIMEOUT,
+                    &format!("Timed out {} a pooled connection", what),
+                )
+            }
+            PoolError::Closed => {
+                RfcError::custom_with_code(_RFC_RC::RFC_CLOSED, "Connection pool is closed")
+            }
+            PoolError::PostCreateHook(HookError::Abort(HookErrorCause::Backend(err))) => err,
+            other => RfcError::custom(&other.to_string()),
+        }
+    }
+}
+
+impl From<InteractError> for RfcError {
+    fn from(err: InteractError) -> Self {
+        match err {
+            InteractError::Panic(_) => RfcError::custom_with_code(
+                _RFC_RC::RFC_UNKNOWN_ERROR,
+                "Call on a pooled connection panicked",
+            ),
+            InteractError::Aborted => RfcError::custom_with_code(
+                _RFC_RC::RFC_CANCELED,
+                "Call on a pooled connection was aborted",
+            ),
+        }
     }
 }
 
 #[async_trait]
 impl managed::Manager for Manager {
     type Type = SyncWrapper<RfcConnection>;
-    type Error = RfcErrorInfo;
+    type Error = RfcError;
 
     async fn create(&self) -> Result<Self::Type, Self::Error> {
-        let builder = self.builder.clone();
-        SyncWrapper::new(self.runtime, move || builder.build()).await
+        let builder = self.factory.make_builder().await?;
+        let conn = match SyncWrapper::new(self.runtime, move || builder.build()).await {
+            Ok(conn) => conn,
+            Err(err) => {
+                if err.is_logon_failure() {
+                    log::warn!("Connection logon failed: {}", err);
+                    self.factory.on_auth_failure(&err).await;
+                }
+                return Err(err);
+            }
+        };
+        self.prefetch(&conn).await?;
+        Ok(conn)
     }
 
     async fn recycle(&self, conn: &mut Self::Type) -> managed::RecycleResult<Self::Error> {
@@ -36,9 +526,299 @@ impl managed::Manager for Manager {
                 "Mutex is poisoned. Connection is considered unusable.",
             ));
         }
-        conn.interact(|conn| conn.ping())
-            .await
-            .map_err(|err| RecycleError::Message(err.to_string()))??;
+        if let Ok(guard) = conn.try_lock() {
+            if guard.is_broken() {
+                return Err(RecycleError::StaticMessage(
+                    "Connection is broken by a previous failure.",
+                ));
+            }
+        }
+        let ping = match self.recycle_method {
+            RecycleMethod::Fast => false,
+            RecycleMethod::Ping => true,
+            RecycleMethod::Verified { ping_every } => {
+                let count = self.recycles.fetch_add(1, Ordering::Relaxed);
+                count % u64::from(ping_every.max(1)) == 0
+            }
+        };
+        conn.interact(move |conn| -> Result<(), RfcError> {
+            if ping {
+                conn.ping()?;
+            } else if !conn.is_valid() {
+                return Err(RfcError::custom("Connection handle is no longer valid"));
+            }
+            conn.emit_event(ConnectionEventKind::Recycled, None);
+            Ok(())
+        })
+        .await
+        .map_err(|err| RecycleError::Message(err.to_string()))??;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{RfcErrorCode, RfcErrorInfo};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn broken_test() {
+        let manager = Manager::new(
+            RfcConnection::builder().set_param("dest", "TEST"),
+            Runtime::Tokio1,
+        );
+        let pool = Pool::builder(manager).max_size(1).build().unwrap();
+
+        let conn = pool.get().await.unwrap();
+        let broken = conn
+            .interact(|conn| {
+                conn.track_error(RfcErrorInfo::custom_with_code(
+                    _RFC_RC::RFC_COMMUNICATION_FAILURE,
+                    "Simulated failure",
+                ));
+                conn.is_broken()
+            })
+            .await
+            .unwrap();
+        assert!(broken);
+        drop(conn);
+
+        // The broken connection is replaced instead of recycled
+        let conn = pool.get().await.unwrap();
+        assert_eq!(managed::Object::metrics(&conn).recycle_count, 0);
+        assert!(!conn.interact(|conn| conn.is_broken()).await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn recycle_method_test() {
+        for method in [
+            RecycleMethod::Fast,
+            RecycleMethod::Ping,
+            RecycleMethod::Verified { ping_every: 2 },
+        ] {
+            let manager = Manager::new(
+                RfcConnection::builder().set_param("dest", "TEST"),
+                Runtime::Tokio1,
+            )
+            .with_recycle_method(method);
+            assert_eq!(manager.recycle_method(), method);
+            let pool = Pool::builder(manager).max_size(1).build().unwrap();
+
+            drop(pool.get().await.unwrap());
+            drop(pool.get().await.unwrap());
+            let conn = pool.get().await.unwrap();
+            assert_eq!(managed::Object::metrics(&conn).recycle_count, 2);
+            assert!(conn.interact(|conn| conn.is_valid()).await.unwrap());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn warm_up_test() {
+        let pool = RfcPool::builder(RfcConnection::builder().set_param("dest", "TEST"))
+            .set_runtime(Runtime::Tokio1)
+            .set_max_size(3)
+            .build()
+            .unwrap();
+        assert_eq!(pool.pool().status().size, 0);
+
+        // Asking for more than the maximum opens up to the maximum
+        pool.warm_up(5).await.unwrap();
+        let status = pool.pool().status();
+        assert_eq!(status.size, 3);
+        assert_eq!(status.available, 3);
+
+        let pool = RfcPool::builder(RfcConnection::builder().set_param("dest", "NOT_A_DEST"))
+            .set_runtime(Runtime::Tokio1)
+            .build()
+            .unwrap();
+        assert!(pool.warm_up(2).await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn rfc_pool_test() {
+        let pool = RfcPool::builder(RfcConnection::builder().set_param("dest", "TEST"))
+            .set_runtime(Runtime::Tokio1)
+            .set_max_size(1)
+            .set_wait_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        let echo = pool
+            .call(
+                "SCP_STRING_ECHO",
+                |func| func.set_string("IMP", "Pooled"),
+                |func| func.get_string("EXP"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(echo, "Pooled");
+
+        // Failures of the closure come back unchanged
+        let err = pool
+            .with_connection(|conn| conn.get_function("INVALID_TEST_FUNCTION_NAME")?.invoke())
+            .await
+            .unwrap_err();
+        assert_eq!(err.key(), "FU_NOT_FOUND");
+
+        // Waiting for a busy connection times out
+        let conn = pool.get().await.unwrap();
+        let err = pool.with_connection(|conn| conn.ping()).await.unwrap_err();
+        assert_eq!(err.code(), RfcErrorCode::Timeout);
+        drop(conn);
+        pool.with_connection(|conn| conn.ping()).await.unwrap();
+
+        // A runtime is required
+        let res = RfcPool::builder(RfcConnection::builder().set_param("dest", "TEST")).build();
+        assert!(matches!(res, Err(err) if err.code() == RfcErrorCode::InvalidParameter));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn prefetch_test() {
+        let manager = Manager::new(
+            RfcConnection::builder().set_param("dest", "TEST"),
+            Runtime::Tokio1,
+        )
+        .with_prefetch_functions(vec!["STFC_STRUCTURE", "INVALID_TEST_FUNCTION_NAME"]);
+        manager.add_prefetch_function("SCP_STRING_ECHO");
+        let pool = Pool::builder(manager).max_size(1).build().unwrap();
+
+        // The unknown function is only counted, the connection is still handed out
+        let conn = pool.get().await.unwrap();
+        assert_eq!(pool.manager().prefetch_failures(), 1);
+        conn.interact(|conn| {
+            conn.get_function("STFC_STRUCTURE")?.invoke()?;
+            conn.get_function("SCP_STRING_ECHO")?.invoke()
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn post_create_hook_test() {
+        let manager = Manager::new(
+            RfcConnection::builder().set_param("dest", "TEST"),
+            Runtime::Tokio1,
+        );
+        let checked = Arc::new(AtomicU64::new(0));
+        let marker = Arc::clone(&checked);
+        let hook = manager.post_create_hook(move |conn| {
+            conn.get_function("STFC_CONNECTION")?.invoke()?;
+            marker.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        });
+        let pool = Pool::builder(manager)
+            .max_size(1)
+            .post_create(hook)
+            .build()
+            .unwrap();
+
+        // The hook only runs on creation, not when the connection is reused
+        drop(pool.get().await.unwrap());
+        drop(pool.get().await.unwrap());
+        assert_eq!(checked.load(Ordering::Relaxed), 1);
+        assert_eq!(pool.manager().hook_failures(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn post_create_hook_failure_test() {
+        let manager = Manager::new(
+            RfcConnection::builder().set_param("dest", "TEST"),
+            Runtime::Tokio1,
+        );
+        let hook = manager.post_create_hook(|_| Err(RfcError::custom("Not licensed")));
+        let pool = Pool::builder(manager)
+            .max_size(1)
+            .post_create(hook)
+            .build()
+            .unwrap();
+
+        // The connection is discarded and the failure surfaced to the caller
+        let res = pool.get().await;
+        assert!(matches!(res, Err(managed::PoolError::PostCreateHook(_))));
+        assert_eq!(pool.manager().hook_failures(), 1);
+        assert_eq!(pool.status().size, 0);
+    }
+
+    /// Gives a wrong password until told about the failed logon, counting the calls.
+    #[derive(Default)]
+    struct CountingFactory {
+        builders: AtomicU64,
+        auth_failures: AtomicU64,
+    }
+
+    #[async_trait]
+    impl ConnectionFactory for CountingFactory {
+        async fn make_builder(&self) -> Result<RfcConnectionBuilder, RfcError> {
+            self.builders.fetch_add(1, Ordering::Relaxed);
+            let builder = RfcConnection::builder().set_param("dest", "TEST");
+            if self.auth_failures.load(Ordering::Relaxed) == 0 {
+                Ok(builder.set_param("passwd", "expired"))
+            } else {
+                Ok(builder)
+            }
+        }
+
+        async fn on_auth_failure(&self, err: &RfcError) {
+            assert!(err.is_logon_failure());
+            self.auth_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connection_factory_test() {
+        let factory = Arc::new(CountingFactory::default());
+        let manager = Manager::from_factory(Arc::clone(&factory), Runtime::Tokio1);
+        let pool = Pool::builder(manager).max_size(2).build().unwrap();
+
+        // The first logon fails and the factory is told before the next attempt
+        let res = pool.get().await;
+        assert!(matches!(res, Err(managed::PoolError::Backend(ref err)) if err.is_logon_failure()));
+        assert_eq!(factory.builders.load(Ordering::Relaxed), 1);
+        assert_eq!(factory.auth_failures.load(Ordering::Relaxed), 1);
+
+        // Every new connection asks for a new builder, reused ones do not
+        let first = pool.get().await.unwrap();
+        let second = pool.get().await.unwrap();
+        assert_eq!(factory.builders.load(Ordering::Relaxed), 3);
+        drop(first);
+        drop(second);
+        drop(pool.get().await.unwrap());
+        assert_eq!(factory.builders.load(Ordering::Relaxed), 3);
+        assert_eq!(factory.auth_failures.load(Ordering::Relaxed), 1);
+    }
+
+    #[cfg(feature = "rate-limit")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn rate_limit_test() {
+        let limiter = RateLimiter::new().with_max_calls_per_second(2);
+        let manager = Manager::new(
+            RfcConnection::builder().set_param("dest", "TEST"),
+            Runtime::Tokio1,
+        )
+        .with_rate_limiter(limiter.clone());
+        let pool = Pool::builder(manager).max_size(1).build().unwrap();
+
+        let conn = pool.get().await.unwrap();
+        let start = std::time::Instant::now();
+        for i in 0..3 {
+            let value = format!("Call {}", i);
+            let echo = call(
+                &conn,
+                "SCP_STRING_ECHO",
+                move |func| func.set_string("IMP", &value),
+                |func| func.get_string("EXP"),
+            )
+            .await
+            .unwrap();
+            assert_eq!(echo, format!("Call {}", i));
+        }
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(limiter.metrics().waits, 2);
+
+        // Closures run with the connection are throttled the same way
+        interact(&conn, |conn| conn.ping()).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(1500));
+        assert_eq!(limiter.metrics().waits, 3);
+    }
+}
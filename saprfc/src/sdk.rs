@@ -0,0 +1,218 @@
+use crate::{
+    error::{Result, RfcError, RfcErrorInfo},
+    ini,
+    macros::{check_rc_ok, is_rc_err},
+    uc,
+};
+use sapnwrfc_sys::{
+    RfcGetVersion, RfcLanguageIsoToSap, RfcLanguageSapToIso, RfcReloadIniFile, RfcSetIniPath,
+    RfcSetTraceDir, RfcSetTraceEncoding, RfcSetTraceLevel, SAP_UC, _RFC_RC,
+};
+use std::{fmt, path::Path, ptr};
+
+/// Get the version of the loaded SDK library as major, minor and patch level.
+pub fn version() -> (u32, u32, u32) {
+    let (mut major, mut minor, mut patch) = (0, 0, 0);
+    unsafe {
+        RfcGetVersion(&mut major, &mut minor, &mut patch);
+    }
+    (major, minor, patch)
+}
+
+/// The version of the 7.50 release, the first one handling `INT8` and `UTCLONG` values.
+#[cfg(sapnwrfc_int8)]
+const VERSION_750: (u32, u32, u32) = (7500, 0, 0);
+
+/// Fail when the loaded SDK is older than 7.50, naming what needs it.
+#[cfg(sapnwrfc_int8)]
+pub(crate) fn require_750(what: &str) -> Result<()> {
+    if version() < VERSION_750 {
+        return Err(unsupported_before_750(what));
+    }
+    Ok(())
+}
+
+/// The error of a feature of the 7.50 release used with an older SDK.
+pub(crate) fn unsupported_before_750(what: &str) -> RfcError {
+    RfcError::custom_with_code(
+        _RFC_RC::RFC_NOT_SUPPORTED,
+        &format!("{} requires SDK >= 7.50", what),
+    )
+}
+
+/// The detail of the trace files written by the SDK.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TraceLevel {
+    #[default]
+    Off,
+    Brief,
+    Verbose,
+    Full,
+}
+
+impl TraceLevel {
+    pub(crate) fn as_u32(self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::Brief => 1,
+            Self::Verbose => 2,
+            Self::Full => 3,
+        }
+    }
+}
+
+/// The level as the SDK expects it in the `trace` connection parameter.
+impl fmt::Display for TraceLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_u32())
+    }
+}
+
+/// The encoding of the trace files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TraceEncoding {
+    Utf8,
+    Utf16,
+    /// The encoding of the operating system.
+    System,
+}
+
+impl TraceEncoding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16 => "UTF-16",
+            Self::System => "DEFAULT",
+        }
+    }
+}
+
+/// Set the trace level of all the connections, existing and future.
+///
+/// Like the other SDK settings, this is global to the process and can be set before any
+/// connection exists.
+pub fn set_trace_level(level: TraceLevel) -> Result<()> {
+    unsafe {
+        check_rc_ok!(RfcSetTraceLevel(
+            ptr::null_mut(),
+            ptr::null(),
+            level.as_u32()
+        ));
+    }
+    Ok(())
+}
+
+/// Set the trace level of the connections to a destination of the `sapnwrfc.ini` file.
+pub fn set_destination_trace_level(dest: &str, level: TraceLevel) -> Result<()> {
+    let dest = uc::from_str(dest)?;
+    unsafe {
+        check_rc_ok!(RfcSetTraceLevel(
+            ptr::null_mut(),
+            dest.as_ptr(),
+            level.as_u32()
+        ));
+    }
+    Ok(())
+}
+
+/// Set the directory the trace files of the process are written to, the working directory by
+/// default.
+pub fn set_trace_dir<P: AsRef<Path>>(dir: P) -> Result<()> {
+    let dir = uc::from_str(path_str(dir.as_ref())?)?;
+    unsafe {
+        check_rc_ok!(RfcSetTraceDir(dir.as_ptr()));
+    }
+    Ok(())
+}
+
+/// Set the encoding of the trace files of the process, the one of the operating system by
+/// default.
+pub fn set_trace_encoding(encoding: TraceEncoding) -> Result<()> {
+    let encoding = uc::from_str(encoding.as_str())?;
+    unsafe {
+        check_rc_ok!(RfcSetTraceEncoding(encoding.as_ptr()));
+    }
+    Ok(())
+}
+
+/// Set the directory of the `sapnwrfc.ini` file of the process, instead of the working
+/// directory.
+///
+/// The file is read again from the new directory. The `RFC_INI` variable still takes precedence.
+pub fn set_ini_path<P: AsRef<Path>>(dir: P) -> Result<()> {
+    let dir = dir.as_ref();
+    let uc_dir = uc::from_str(path_str(dir)?)?;
+    unsafe {
+        check_rc_ok!(RfcSetIniPath(uc_dir.as_ptr()));
+    }
+    ini::set_ini_dir(dir);
+    Ok(())
+}
+
+/// Read the `sapnwrfc.ini` file again, to pick up changed destinations.
+pub fn reload_ini_file() -> Result<()> {
+    let mut err_info = RfcErrorInfo::new();
+    if is_rc_err!(unsafe { RfcReloadIniFile(err_info.as_mut_ptr()) }) {
+        return Err(err_info.into());
+    }
+    Ok(())
+}
+
+/// Convert a two letter ISO language code, like `EN`, to the one character language key of
+/// ABAP, like `E`.
+pub fn lang_iso_to_sap(iso: &str) -> Result<String> {
+    check_lang_len(iso, 2)?;
+    let uc_iso = uc::from_str(&iso.to_uppercase())?;
+    let mut lang: [SAP_UC; 2] = [0; 2];
+    let mut err_info = RfcErrorInfo::new();
+    if is_rc_err!(unsafe {
+        RfcLanguageIsoToSap(uc_iso.as_ptr(), lang.as_mut_ptr(), err_info.as_mut_ptr())
+    }) {
+        return Err(err_info.into());
+    }
+    uc::to_string(&lang, 1)
+}
+
+/// Convert a one character ABAP language key, like `E`, to the two letter ISO language code,
+/// like `EN`.
+///
+/// Keys are case sensitive, a few lowercase ones exist besides the uppercase ones.
+pub fn lang_sap_to_iso(lang: &str) -> Result<String> {
+    check_lang_len(lang, 1)?;
+    let uc_lang = uc::from_str(lang)?;
+    let mut iso: [SAP_UC; 3] = [0; 3];
+    let mut err_info = RfcErrorInfo::new();
+    if is_rc_err!(unsafe {
+        RfcLanguageSapToIso(uc_lang.as_ptr(), iso.as_mut_ptr(), err_info.as_mut_ptr())
+    }) {
+        return Err(err_info.into());
+    }
+    uc::to_string(&iso, 2)
+}
+
+fn check_lang_len(value: &str, len: usize) -> Result<()> {
+    let count = value.chars().count();
+    if count != len {
+        // Only the length of the value is shown, it could be anything
+        return Err(RfcError::custom_with_code(
+            _RFC_RC::RFC_INVALID_PARAMETER,
+            &format!(
+                "Language of {} character{} must have {} character{}",
+                count,
+                if count == 1 { "" } else { "s" },
+                len,
+                if len == 1 { "" } else { "s" }
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn path_str(path: &Path) -> Result<&str> {
+    path.to_str().ok_or_else(|| {
+        RfcError::custom_with_code(
+            _RFC_RC::RFC_INVALID_PARAMETER,
+            &format!("Path {} is not valid Unicode", path.display()),
+        )
+    })
+}
@@ -0,0 +1,467 @@
+use crate::{data_container::DataDesc, error::RfcError, structure::RfcStructure, table::RfcTable};
+use sapnwrfc_sys::{RFCTYPE, _RFCTYPE, _RFC_RC};
+use serde::ser::{self, Error as _, Impossible, Serialize};
+use std::{borrow::BorrowMut, convert::TryFrom, error, fmt, marker::PhantomData};
+
+/// Serialize a value into the fields of a structure.
+pub(crate) fn to_structure<T: Serialize + ?Sized>(
+    value: &T,
+    target: &mut RfcStructure,
+) -> Result<(), Error> {
+    value.serialize(StructureTarget(target))
+}
+
+/// A serialization failure with the path of the field it occurred in.
+#[derive(Debug)]
+pub(crate) struct Error {
+    path: String,
+    message: String,
+}
+
+impl Error {
+    fn in_field(mut self, name: &str) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("{}{}", name, self.path)
+        } else {
+            format!("{}.{}", name, self.path)
+        };
+        self
+    }
+
+    fn in_row(mut self, index: usize) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("[{}]{}", index, self.path)
+        } else {
+            format!("[{}].{}", index, self.path)
+        };
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            path: String::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl From<RfcError> for Error {
+    fn from(err: RfcError) -> Self {
+        Error::custom(err.message())
+    }
+}
+
+impl From<Error> for RfcError {
+    fn from(err: Error) -> Self {
+        RfcError::custom_with_code(
+            _RFC_RC::RFC_CONVERSION_FAILURE,
+            &format!("Cannot serialize {}", err),
+        )
+    }
+}
+
+macro_rules! unsupported {
+    ($what:expr; $($method:ident($($arg:ty),*) -> $ok:ty,)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<$ok, Error> {
+                Err(Error::custom(format!("{} cannot be written {}", $what, stringify!($method))))
+            }
+        )*
+    };
+}
+
+/// A structure receiving the fields of a struct, rows of tables are written through it too.
+struct StructureTarget<'s, 'data>(&'s mut RfcStructure<'data>);
+
+impl<'s, 'data> ser::Serializer for StructureTarget<'s, 'data> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = StructSerializer<'data, &'s mut RfcStructure<'data>>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        StructSerializer::new(self.0)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error> {
+        Err(Error::custom("a structure cannot be written from an enum"))
+    }
+
+    unsupported! {
+        "a structure";
+        serialize_bool(bool) -> (),
+        serialize_i8(i8) -> (),
+        serialize_i16(i16) -> (),
+        serialize_i32(i32) -> (),
+        serialize_i64(i64) -> (),
+        serialize_u8(u8) -> (),
+        serialize_u16(u16) -> (),
+        serialize_u32(u32) -> (),
+        serialize_u64(u64) -> (),
+        serialize_f32(f32) -> (),
+        serialize_f64(f64) -> (),
+        serialize_char(char) -> (),
+        serialize_str(&str) -> (),
+        serialize_bytes(&[u8]) -> (),
+        serialize_none() -> (),
+        serialize_unit() -> (),
+        serialize_unit_struct(&'static str) -> (),
+        serialize_unit_variant(&'static str, u32, &'static str) -> (),
+        serialize_seq(Option<usize>) -> Self::SerializeSeq,
+        serialize_tuple(usize) -> Self::SerializeTuple,
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct,
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant,
+        serialize_map(Option<usize>) -> Self::SerializeMap,
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant,
+    }
+}
+
+/// The fields of a struct written to the fields of a structure with a matching name.
+///
+/// Names are matched without regard to case, as ABAP names are uppercase.
+struct StructSerializer<'data, S> {
+    target: S,
+    layout: Vec<(String, DataDesc)>,
+    _data: PhantomData<RfcStructure<'data>>,
+}
+
+impl<'data, S: BorrowMut<RfcStructure<'data>>> StructSerializer<'data, S> {
+    fn new(target: S) -> Result<Self, Error> {
+        let layout = target.borrow().field_layout()?;
+        Ok(Self {
+            target,
+            layout,
+            _data: PhantomData,
+        })
+    }
+}
+
+impl<'data, S: BorrowMut<RfcStructure<'data>>> ser::SerializeStruct for StructSerializer<'data, S> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let (name, desc) = self
+            .layout
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .ok_or_else(|| {
+                Error::custom(format!(
+                    "structure {} has no field matching {}",
+                    self.target.borrow().name(),
+                    key
+                ))
+            })?;
+        value
+            .serialize(FieldSerializer {
+                target: self.target.borrow_mut(),
+                name,
+                rfc_type: desc.rfc_type,
+            })
+            .map_err(|err| err.in_field(name))
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A value written to a field according to the type of the field.
+struct FieldSerializer<'s, 'data> {
+    target: &'s mut RfcStructure<'data>,
+    name: &'s str,
+    rfc_type: RFCTYPE,
+}
+
+impl FieldSerializer<'_, '_> {
+    fn write_i64(self, value: i64) -> Result<(), Error> {
+        match self.rfc_type {
+            _RFCTYPE::RFCTYPE_BCD | _RFCTYPE::RFCTYPE_DECF16 | _RFCTYPE::RFCTYPE_DECF34 => {
+                Ok(self.target.set_decimal_str(self.name, &value.to_string())?)
+            }
+            _RFCTYPE::RFCTYPE_FLOAT => Ok(self.target.set_float(self.name, value as f64)?),
+            _RFCTYPE::RFCTYPE_CHAR
+            | _RFCTYPE::RFCTYPE_NUM
+            | _RFCTYPE::RFCTYPE_STRING
+            | _RFCTYPE::RFCTYPE_DATE
+            | _RFCTYPE::RFCTYPE_TIME => {
+                Ok(self.target.set_string(self.name, &value.to_string())?)
+            }
+            _ => Ok(self.target.set_i64(self.name, value)?),
+        }
+    }
+
+    fn write_f64(self, value: f64) -> Result<(), Error> {
+        match self.rfc_type {
+            _RFCTYPE::RFCTYPE_BCD | _RFCTYPE::RFCTYPE_DECF16 | _RFCTYPE::RFCTYPE_DECF34 => {
+                Ok(self.target.set_decimal_str(self.name, &value.to_string())?)
+            }
+            _RFCTYPE::RFCTYPE_CHAR | _RFCTYPE::RFCTYPE_STRING => {
+                Ok(self.target.set_string(self.name, &value.to_string())?)
+            }
+            _ => Ok(self.target.set_float(self.name, value)?),
+        }
+    }
+}
+
+impl<'s, 'data> ser::Serializer for FieldSerializer<'s, 'data> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = RowsSerializer<'s>;
+    type SerializeTuple = RowsSerializer<'s>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = StructSerializer<'s, RfcStructure<'s>>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    /// ABAP flags are `X` when set and blank otherwise.
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        Ok(self
+            .target
+            .set_string(self.name, if value { "X" } else { "" })?)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        self.write_i64(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        self.write_i64(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        self.write_i64(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        self.write_i64(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        self.write_i64(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        self.write_i64(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        self.write_i64(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        let value = i64::try_from(value)
+            .map_err(|_| Error::custom(format!("value {} is too large", value)))?;
+        self.write_i64(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        self.write_f64(value.into())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        self.write_f64(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        Ok(self
+            .target
+            .set_string(self.name, value.encode_utf8(&mut [0; 4]))?)
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        match self.rfc_type {
+            _RFCTYPE::RFCTYPE_BCD | _RFCTYPE::RFCTYPE_DECF16 | _RFCTYPE::RFCTYPE_DECF34 => {
+                Ok(self.target.set_decimal_str(self.name, value)?)
+            }
+            _ => Ok(self.target.set_string(self.name, value)?),
+        }
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Error> {
+        Ok(self.target.set_bytes(self.name, value)?)
+    }
+
+    /// Missing values leave the field with the initial value of its type.
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(self.target.clear_field(self.name)?)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error> {
+        Err(Error::custom(
+            "a field cannot be written from an enum with data",
+        ))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<RowsSerializer<'s>, Error> {
+        if self.rfc_type != _RFCTYPE::RFCTYPE_TABLE {
+            return Err(Error::custom("only a table can be written from a sequence"));
+        }
+        RowsSerializer::new(self.target.get_table(self.name)?)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<RowsSerializer<'s>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        if self.rfc_type != _RFCTYPE::RFCTYPE_STRUCTURE {
+            return Err(Error::custom(
+                "only a structure can be written from a struct",
+            ));
+        }
+        StructSerializer::new(self.target.get_structure(self.name)?)
+    }
+
+    unsupported! {
+        "a field";
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct,
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant,
+        serialize_map(Option<usize>) -> Self::SerializeMap,
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant,
+    }
+}
+
+/// The elements of a sequence written as the rows of a table, replacing its rows.
+///
+/// Elements of tables with an elementary line type are written to the unnamed field of the rows,
+/// structures with a single field still take structured elements.
+struct RowsSerializer<'s> {
+    table: RfcTable<'s>,
+    scalar: Option<RFCTYPE>,
+    index: usize,
+}
+
+impl<'s> RowsSerializer<'s> {
+    fn new(mut table: RfcTable<'s>) -> Result<Self, Error> {
+        table.clear_rows()?;
+        let scalar = table.elementary_type()?;
+        Ok(Self {
+            table,
+            scalar,
+            index: 0,
+        })
+    }
+}
+
+impl ser::SerializeSeq for RowsSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let index = self.index;
+        self.index += 1;
+        let mut row = self.table.append_row()?;
+        let result = match &self.scalar {
+            Some(rfc_type) => value.serialize(FieldSerializer {
+                target: &mut row,
+                name: "",
+                rfc_type: *rfc_type,
+            }),
+            None => value.serialize(StructureTarget(&mut row)),
+        };
+        result.map_err(|err| err.in_row(index))
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for RowsSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{
+    connection::RfcConnection,
+    error::{Result, RfcError, RfcErrorInfo},
+    function::RfcFunction,
+    macros::{check_rc_ok, is_rc_err},
+    uc::{self, UcString},
+};
+use sapnwrfc_sys::{
+    self, RfcCloseConnection, RfcDescribeFunction, RfcGetFunctionDesc, RfcGetFunctionName,
+    RfcInstallServerFunction, RfcListenAndDispatch, RfcRegisterServer, RFC_ABAP_NAME,
+    RFC_CONNECTION_HANDLE, RFC_CONNECTION_PARAMETER, RFC_ERROR_INFO, RFC_FUNCTION_HANDLE, RFC_RC,
+    _RFC_ERROR_GROUP, _RFC_RC,
+};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashMap,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// How long a worker waits for a call before checking if the server is shutting down.
+const LISTEN_TIMEOUT_SECS: i32 = 1;
+
+/// How long a worker waits before registering again after losing its connection.
+const REGISTER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+type Handler = dyn Fn(&RfcConnection, &mut RfcFunction) -> Result<()> + Send + Sync;
+
+/// The installed handlers of a server by function name.
+type Handlers = RwLock<Vec<(String, Arc<Handler>)>>;
+
+thread_local! {
+    /// The handlers of the server the worker listens for, the SDK callback does not carry any
+    /// context but is called on the thread dispatching the call.
+    static HANDLERS: RefCell<Option<Arc<Handlers>>> = RefCell::new(None);
+}
+
+/// A value read from the parameters of a function called on the server.
+pub trait FromFunction: Sized {
+    fn from_function(func: &RfcFunction) -> Result<Self>;
+}
+
+/// A value written to the parameters of a function called on the server.
+pub trait IntoFunction {
+    fn into_function(self, func: &mut RfcFunction) -> Result<()>;
+}
+
+impl FromFunction for () {
+    fn from_function(_func: &RfcFunction) -> Result<Self> {
+        Ok(())
+    }
+}
+
+impl IntoFunction for () {
+    fn into_function(self, _func: &mut RfcFunction) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A builder for an RFC server registered at a gateway.
+#[derive(Clone, Debug)]
+pub struct RfcServerBuilder {
+    params: HashMap<String, String>,
+    workers: usize,
+    system_id: Option<String>,
+}
+
+impl RfcServerBuilder {
+    /// Get a new, empty, builder with a single worker.
+    pub fn new() -> Self {
+        Self {
+            params: HashMap::new(),
+            workers: 1,
+            system_id: None,
+        }
+    }
+
+    /// Set a registration parameter, like `gwhost`, `gwserv` and `program_id`, to a given value.
+    pub fn set_param<T>(mut self, key: &str, value: T) -> Self
+    where
+        T: ToString,
+    {
+        self.params.insert(key.to_owned(), value.to_string());
+        self
+    }
+
+    /// Set the number of connections registered at the gateway.
+    ///
+    /// Each registration is served by its own thread, so up to this many calls are handled in
+    /// parallel.
+    pub fn set_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Set the ID of the backend system the functions are installed for.
+    ///
+    /// The descriptions of the installed functions are used by the SDK for calls from any system
+    /// when it is not set.
+    pub fn set_system_id(mut self, system_id: &str) -> Self {
+        self.system_id = Some(system_id.to_owned());
+        self
+    }
+
+    /// Consume the builder and get a server which is not listening yet.
+    pub fn build(self) -> Result<RfcServer> {
+        let params = self
+            .params
+            .into_iter()
+            .map(|(k, v)| Ok((uc::from_str(&k)?, uc::from_str(&v)?)))
+            .collect::<Result<_>>()?;
+        Ok(RfcServer {
+            params: Arc::new(params),
+            system_id: self.system_id.as_deref().map(uc::from_str).transpose()?,
+            handlers: Arc::new(RwLock::new(Vec::new())),
+            workers: self.workers,
+            running: Arc::new(AtomicBool::new(false)),
+            threads: Vec::new(),
+        })
+    }
+}
+
+impl Default for RfcServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An RFC server receiving calls from the gateway it is registered at.
+pub struct RfcServer {
+    params: Arc<Vec<(UcString, UcString)>>,
+    system_id: Option<UcString>,
+    handlers: Arc<Handlers>,
+    workers: usize,
+    running: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl RfcServer {
+    /// Get an empty server builder to provide registration parameters.
+    pub fn builder() -> RfcServerBuilder {
+        RfcServerBuilder::default()
+    }
+
+    /// Install a handler for a function module, looking up its description with a connection.
+    ///
+    /// Handlers belong to the server, other servers of the process keep their own. They are
+    /// shared by all the workers and are called from several threads at once. A failure of the
+    /// handler is reported to the caller, errors created with [`RfcError::abap_exception`]
+    /// raise the exception of their key. A panic is reported as a system failure.
+    pub fn install_function<F>(
+        &self,
+        repository: &RfcConnection,
+        name: &str,
+        handler: F,
+    ) -> Result<()>
+    where
+        F: Fn(&RfcConnection, &mut RfcFunction) -> Result<()> + Send + Sync + 'static,
+    {
+        let name = name.to_uppercase();
+        let uc_name = uc::from_str(&name)?;
+        let mut err_info = RfcErrorInfo::new();
+        let desc = unsafe {
+            RfcGetFunctionDesc(repository.handle(), uc_name.as_ptr(), err_info.as_mut_ptr())
+        };
+        if desc.is_null() {
+            return Err(err_info.into());
+        }
+        {
+            let mut handlers = self.handlers.write().unwrap();
+            handlers.retain(|(installed, _)| *installed != name);
+            handlers.push((name, Arc::new(handler)));
+        }
+        let system_id = self
+            .system_id
+            .as_ref()
+            .map_or(ptr::null(), |system_id| system_id.as_ptr());
+        unsafe {
+            check_rc_ok!(RfcInstallServerFunction(system_id, desc, Some(dispatch)));
+        }
+        Ok(())
+    }
+
+    /// Register the workers at the gateway and start listening for calls in the background.
+    pub fn start(&mut self) -> Result<()> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        for index in 0..self.workers {
+            let params = Arc::clone(&self.params);
+            let handlers = Arc::clone(&self.handlers);
+            let running = Arc::clone(&self.running);
+            let thread = thread::Builder::new()
+                .name(format!("rfc-server-{}", index))
+                .spawn(move || {
+                    HANDLERS.with(|current| *current.borrow_mut() = Some(handlers));
+                    listen(&params, &running)
+                })
+                .map_err(|err| RfcError::custom(&err.to_string()))?;
+            self.threads.push(thread);
+        }
+        Ok(())
+    }
+
+    /// Start listening and block until the server is stopped with a [`StopHandle`].
+    pub fn serve(&mut self) -> Result<()> {
+        self.start()?;
+        self.join();
+        Ok(())
+    }
+
+    /// Get a handle to stop the server from another thread, like a signal handler.
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle {
+            running: Arc::clone(&self.running),
+        }
+    }
+
+    /// Check if the server is listening for calls.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Stop listening and wait for the calls in progress to complete.
+    pub fn shutdown(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.join();
+    }
+
+    fn join(&mut self) {
+        for thread in self.threads.drain(..) {
+            if thread.join().is_err() {
+                log::error!("Server worker panicked");
+            }
+        }
+    }
+}
+
+/// A handle stopping a server when the calls in progress are complete.
+#[derive(Clone, Debug)]
+pub struct StopHandle {
+    running: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Drop for RfcServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Register at the gateway and dispatch calls until the server shuts down.
+fn listen(params: &[(UcString, UcString)], running: &AtomicBool) {
+    let conn_params: Vec<_> = params
+        .iter()
+        .map(|(k, v)| RFC_CONNECTION_PARAMETER {
+            name: k.as_ptr(),
+            value: v.as_ptr(),
+        })
+        .collect();
+    let mut handle: RFC_CONNECTION_HANDLE = ptr::null_mut();
+    while running.load(Ordering::SeqCst) {
+        let mut err_info = RfcErrorInfo::new();
+        if handle.is_null() {
+            handle = unsafe {
+                RfcRegisterServer(
+                    conn_params.as_ptr(),
+                    conn_params.len() as u32,
+                    err_info.as_mut_ptr(),
+                )
+            };
+            if handle.is_null() {
+                log::error!("Server registration failed: {}", err_info);
+                thread::sleep(REGISTER_RETRY_DELAY);
+            }
+            continue;
+        }
+        let rc =
+            unsafe { RfcListenAndDispatch(handle, LISTEN_TIMEOUT_SECS, err_info.as_mut_ptr()) };
+        match rc {
+            _RFC_RC::RFC_OK | _RFC_RC::RFC_RETRY | _RFC_RC::RFC_ABAP_EXCEPTION => {}
+            _RFC_RC::RFC_CLOSED | _RFC_RC::RFC_COMMUNICATION_FAILURE => {
+                log::warn!("Server connection lost, registering again: {}", err_info);
+                close(handle);
+                handle = ptr::null_mut();
+            }
+            _ => log::warn!("Server call failed: {}", err_info),
+        }
+    }
+    if !handle.is_null() {
+        close(handle);
+    }
+}
+
+fn close(handle: RFC_CONNECTION_HANDLE) {
+    let mut err_info = RfcErrorInfo::new();
+    unsafe {
+        if is_rc_err!(RfcCloseConnection(handle, err_info.as_mut_ptr())) {
+            log::warn!("Server connection close failed: {}", err_info);
+        }
+    }
+}
+
+/// The callback of every installed function, calling the handler registered for its name.
+unsafe extern "C" fn dispatch(
+    conn_handle: RFC_CONNECTION_HANDLE,
+    func_handle: RFC_FUNCTION_HANDLE,
+    error_info: *mut RFC_ERROR_INFO,
+) -> RFC_RC {
+    // Reporting a panic stays inside the guard too, no unwinding may cross into the SDK
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| call_handler(conn_handle, func_handle)))
+            .unwrap_or_else(|panic| Err(panic_error(&*panic)))
+            .err()
+            .map(|err| err.into_info().into_inner())
+    }));
+    match outcome {
+        Ok(None) => _RFC_RC::RFC_OK,
+        Ok(Some(err)) => {
+            let code = err.code;
+            ptr::write(error_info, err);
+            code
+        }
+        Err(panic) => {
+            // Dropping the payload could panic again, it is leaked instead
+            mem::forget(panic);
+            let mut err = RfcErrorInfo::new().into_inner();
+            err.code = _RFC_RC::RFC_EXTERNAL_FAILURE;
+            err.group = _RFC_ERROR_GROUP::EXTERNAL_APPLICATION_FAILURE;
+            ptr::write(error_info, err);
+            _RFC_RC::RFC_EXTERNAL_FAILURE
+        }
+    }
+}
+
+/// Report the panic of a handler as a system failure, with the start of its reason.
+fn panic_error(panic: &(dyn Any + Send)) -> RfcError {
+    const MAX_REASON_CHARS: usize = 200;
+    let reason = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown reason");
+    let mut message = String::from("Server function panicked: ");
+    message.extend(reason.chars().take(MAX_REASON_CHARS));
+    RfcError::custom_with_code(_RFC_RC::RFC_EXTERNAL_FAILURE, &message)
+}
+
+unsafe fn call_handler(
+    conn_handle: RFC_CONNECTION_HANDLE,
+    func_handle: RFC_FUNCTION_HANDLE,
+) -> Result<()> {
+    let mut err_info = RfcErrorInfo::new();
+    let desc = RfcDescribeFunction(func_handle, err_info.as_mut_ptr());
+    if desc.is_null() {
+        return Err(err_info.into());
+    }
+    let mut uc_name: RFC_ABAP_NAME = Default::default();
+    check_rc_ok!(RfcGetFunctionName(desc, uc_name.as_mut_ptr()));
+    let name = uc::to_string_truncate(&uc_name)?;
+    let handler = HANDLERS
+        .with(|current| {
+            current.borrow().as_ref().and_then(|handlers| {
+                handlers
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .find(|(installed, _)| *installed == name)
+                    .map(|(_, handler)| Arc::clone(handler))
+            })
+        })
+        .ok_or_else(|| {
+            RfcError::custom_with_code(
+                _RFC_RC::RFC_NOT_FOUND,
+                &format!("No handler installed for function {}", name),
+            )
+        })?;
+
+    // The connection and function belong to the SDK, they must not be closed or destroyed here
+    let conn = RfcConnection::borrowed(conn_handle);
+    let mut func = RfcFunction::borrowed(&conn, func_handle, desc);
+    handler(&conn, &mut func)
+}
@@ -0,0 +1,50 @@
+use crate::{connection::RfcConnection, error::Result};
+use std::ops::Deref;
+
+/// A stateful session on a connection, running all its calls in the same ABAP user context.
+///
+/// BAPIs which write data only take effect once committed by a call in the same context, which
+/// the session keeps until it ends. Ending the session, explicitly or when dropped, resets the
+/// context on the backend and discards the work not committed, also after a failed call.
+#[derive(Debug)]
+pub struct StatefulSession<'conn> {
+    conn: &'conn RfcConnection,
+    ended: bool,
+}
+
+impl<'conn> StatefulSession<'conn> {
+    pub(crate) fn new(conn: &'conn RfcConnection) -> Self {
+        Self { conn, ended: false }
+    }
+
+    /// Get the connection the session runs on.
+    pub fn connection(&self) -> &'conn RfcConnection {
+        self.conn
+    }
+
+    /// End the session, resetting its context on the backend.
+    pub fn end(mut self) -> Result<()> {
+        self.ended = true;
+        self.conn.end_context()
+    }
+}
+
+/// Functions are called through the session like through its connection.
+impl Deref for StatefulSession<'_> {
+    type Target = RfcConnection;
+
+    fn deref(&self) -> &RfcConnection {
+        self.conn
+    }
+}
+
+impl Drop for StatefulSession<'_> {
+    fn drop(&mut self) {
+        if self.ended {
+            return;
+        }
+        if let Err(err) = self.conn.end_context() {
+            log::warn!("Session context reset failed: {}", err);
+        }
+    }
+}
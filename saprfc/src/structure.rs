@@ -1,31 +1,41 @@
 use crate::{
-    data_container::{macros::rfc_data_delegates, RfcDataContainer},
-    error::RfcErrorInfo,
-    macros::{assert_rc_ok, check_rc_ok},
-    uc,
+    data_container::{field_descs, macros::rfc_data_delegates, DataDesc, RfcDataContainer},
+    display::DisplayOptions,
+    error::{Result, RfcError, RfcErrorInfo},
+    macros::{assert_rc_ok, check_rc_ok, is_rc_err},
+    owned::{OwnedStructure, OwnedTable, OwnedValue},
+    types::{self, FieldName, Fields, RfcFieldDesc},
+    uc, value,
 };
 use sapnwrfc_sys::{
-    self, RfcGetFieldCount, RfcGetFieldDescByName, RfcGetTypeName, DATA_CONTAINER_HANDLE,
-    RFC_ABAP_NAME, RFC_STRUCTURE_HANDLE, RFC_TYPE_DESC_HANDLE,
+    self, RfcDestroyStructure, RfcGetFieldCount, RfcGetFieldDescByName, RfcGetTypeName,
+    DATA_CONTAINER_HANDLE, RFC_ABAP_NAME, RFC_STRUCTURE_HANDLE, RFC_TYPE_DESC_HANDLE, _RFCTYPE,
+    _RFC_RC,
 };
+use std::marker::PhantomData;
 
 /// An RFC structure.
+///
+/// Structures are borrowed from the function, structure or table holding them, except the ones
+/// created from a [`RfcTypeDesc`](crate::types::RfcTypeDesc) which own their data.
 pub struct RfcStructure<'data> {
-    _container: &'data DATA_CONTAINER_HANDLE,
+    _container: PhantomData<&'data DATA_CONTAINER_HANDLE>,
     desc: RFC_TYPE_DESC_HANDLE,
     data: RfcDataContainer,
+    _owner: Option<StructureOwner>,
 }
 
 impl<'data> RfcStructure<'data> {
     pub(crate) fn new(
-        container: &'data DATA_CONTAINER_HANDLE,
-        handle: RFC_STRUCTURE_HANDLE,
+        _container: &'data DATA_CONTAINER_HANDLE,
+        data: RfcDataContainer,
         desc: RFC_TYPE_DESC_HANDLE,
     ) -> Self {
         Self {
-            _container: container,
+            _container: PhantomData,
             desc,
-            data: RfcDataContainer::new(handle),
+            data,
+            _owner: None,
         }
     }
 
@@ -53,6 +63,135 @@ impl<'data> RfcStructure<'data> {
         count
     }
 
+    /// Get the description of a field by its position, an index out of range is an error.
+    pub fn field_desc_by_index(&self, index: u32) -> Result<RfcFieldDesc> {
+        types::field_desc_by_index(self.desc, index)
+    }
+
+    /// Get the description of a field by its name.
+    pub fn field_desc_by_name(&self, name: &str) -> Result<RfcFieldDesc> {
+        types::field_desc_by_name(self.desc, name)
+    }
+
+    /// Iterate over the descriptions of all the fields, in declaration order.
+    pub fn fields(&self) -> Result<Fields<'_>> {
+        Fields::new(self.desc)
+    }
+
+    /// Wrap a structure created on its own, destroyed when dropped.
+    pub(crate) fn new_owned(
+        handle: RFC_STRUCTURE_HANDLE,
+        desc: RFC_TYPE_DESC_HANDLE,
+    ) -> RfcStructure<'static> {
+        RfcStructure {
+            _container: PhantomData,
+            desc,
+            data: RfcDataContainer::new(handle),
+            _owner: Some(StructureOwner(handle)),
+        }
+    }
+
+    pub(crate) fn handle(&self) -> RFC_STRUCTURE_HANDLE {
+        self.data.handle()
+    }
+
+    pub(crate) fn type_desc(&self) -> RFC_TYPE_DESC_HANDLE {
+        self.desc
+    }
+
+    pub(crate) fn field_layout(&self) -> Result<Vec<(String, DataDesc)>> {
+        field_descs(self.desc)
+    }
+
+    pub(crate) fn display_value(
+        &self,
+        name: &str,
+        desc: &DataDesc,
+        opts: &DisplayOptions,
+    ) -> Result<String> {
+        self.data
+            .get_display_value(&uc::from_str_to_abap_name(name)?, desc, opts)
+    }
+
+    /// Get the value of a field by its position in the type, copying nested structures and tables.
+    pub(crate) fn owned_value_by_index(
+        &self,
+        index: u32,
+        name: &str,
+        desc: &DataDesc,
+    ) -> Result<OwnedValue> {
+        Ok(match desc.rfc_type {
+            _RFCTYPE::RFCTYPE_STRUCTURE => {
+                OwnedValue::Structure(OwnedStructure::from_structure(&self.get_structure(name)?)?)
+            }
+            _RFCTYPE::RFCTYPE_TABLE => {
+                OwnedValue::Table(OwnedTable::from_table(&self.get_table(name)?)?)
+            }
+            _RFCTYPE::RFCTYPE_CHAR | _RFCTYPE::RFCTYPE_NUM => {
+                OwnedValue::Text(self.data.get_string_by_index(index)?.trim_end().to_owned())
+            }
+            _ => OwnedValue::Text(self.data.get_string_by_index(index)?),
+        })
+    }
+
+    /// Read the fields into a value, matching their names without regard to case.
+    ///
+    /// Tables are read as sequences of rows and nested structures as nested values.
+    #[cfg(feature = "serde")]
+    pub fn to_serde<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(crate::de::from_structure(&OwnedStructure::from_structure(
+            self,
+        )?)?)
+    }
+
+    /// Write the fields of a value, matching their names without regard to case.
+    ///
+    /// A field of the value missing from the structure is an error. Sequences replace the rows
+    /// of tables and nested values fill nested structures.
+    #[cfg(feature = "serde")]
+    pub fn from_serde<T: serde::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        Ok(crate::ser::to_structure(value, self)?)
+    }
+
+    /// Reset a field to the initial value of its type.
+    pub fn clear_field(&mut self, name: &str) -> Result<()> {
+        let name = &uc::from_str_to_abap_name(name)?;
+        let desc = self.data_desc(name)?;
+        self.data.clear(name, &desc)
+    }
+
+    /// Reset every field to the initial value of its type, blanks and zeros, nested tables are
+    /// emptied.
+    pub fn clear(&mut self) -> Result<()> {
+        self.data.clear_all(self.desc)
+    }
+
+    /// Copy the fields of another structure of the same type, nested structures and tables
+    /// included.
+    ///
+    /// Reuses a prepared structure as the template of the next one, like for posting similar
+    /// documents.
+    pub fn copy_from(&mut self, other: &RfcStructure) -> Result<()> {
+        let (name, other_name) = (self.name(), other.name());
+        if name != other_name {
+            return Err(RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_PARAMETER,
+                &format!(
+                    "Cannot copy a structure of type {} into one of type {}",
+                    other_name, name
+                ),
+            ));
+        }
+        value::copy_fields(other, self)
+    }
+
+    /// Get the name of a field of the structure, converted once to be reused across accesses.
+    pub fn field(&self, name: &str) -> Result<FieldName> {
+        let name = FieldName::new(name)?;
+        self.data_desc(name.abap_name())?;
+        Ok(name)
+    }
+
     rfc_data_delegates!(self.data, |name, desc| {
         unsafe {
             check_rc_ok!(RfcGetFieldDescByName(self.desc, name.as_ptr(), &mut desc));
@@ -61,3 +200,19 @@ impl<'data> RfcStructure<'data> {
 }
 
 unsafe impl Send for RfcStructure<'_> {}
+
+/// Destroys a structure created on its own.
+///
+/// Kept apart so that borrowed structures have no drop code extending the borrow of their holder.
+struct StructureOwner(RFC_STRUCTURE_HANDLE);
+
+impl Drop for StructureOwner {
+    fn drop(&mut self) {
+        let mut err_info = RfcErrorInfo::new();
+        unsafe {
+            if is_rc_err!(RfcDestroyStructure(self.0, err_info.as_mut_ptr())) {
+                log::warn!("Structure destroy failed: {}", err_info);
+            }
+        }
+    }
+}
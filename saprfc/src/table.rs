@@ -1,39 +1,139 @@
 use crate::{
-    data_container::{macros::rfc_data_delegates, RfcDataContainer},
-    error::{Result, RfcErrorInfo},
-    macros::{assert_rc_ok, check_rc_ok},
+    data_container::{field_descs, macros::rfc_data_delegates, DataDesc, RfcDataContainer},
+    display::DisplayOptions,
+    error::{join_names, Result, RfcError, RfcErrorInfo, RowError},
+    macros::{assert_rc_ok, check_rc_ok, is_rc_err},
+    owned::{OwnedStructure, OwnedValue},
     structure::RfcStructure,
-    uc,
+    types::{self, FieldName, Fields, RfcFieldDesc, RfcType},
+    uc, value,
 };
 use sapnwrfc_sys::{
-    self, RfcAppendNewRow, RfcDeleteAllRows, RfcDeleteCurrentRow, RfcGetCurrentRow,
-    RfcGetFieldCount, RfcGetFieldDescByName, RfcGetRowCount, RfcGetRowType, RfcGetTypeName,
+    self, RfcAppendNewRow, RfcAppendRow, RfcDeleteAllRows, RfcDeleteCurrentRow, RfcDestroyTable,
+    RfcGetCurrentRow, RfcGetFieldCount, RfcGetFieldDescByName, RfcGetRowCount, RfcGetTypeName,
     RfcInsertNewRow, RfcMoveTo, RfcMoveToFirstRow, RfcMoveToLastRow, DATA_CONTAINER_HANDLE,
-    RFC_ABAP_NAME, RFC_TABLE_HANDLE, RFC_TYPE_DESC_HANDLE,
+    RFC_ABAP_NAME, RFC_STRUCTURE_HANDLE, RFC_TABLE_HANDLE, RFC_TYPE_DESC_HANDLE, _RFCTYPE, _RFC_RC,
 };
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    marker::PhantomData,
+};
+
+/// How to handle values for fields which do not exist in the row type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownFields {
+    Error,
+    Skip,
+}
+
+/// How to handle rows with the same key when indexing a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    Error,
+    FirstWins,
+    LastWins,
+}
+
+/// Options to render a table as text with [`RfcTable::to_pretty_string`].
+#[derive(Clone, Debug)]
+pub struct PrettyOptions {
+    max_width: usize,
+    max_rows: Option<usize>,
+    columns: Option<Vec<String>>,
+    display: DisplayOptions,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            max_width: 30,
+            max_rows: Some(20),
+            columns: None,
+            display: DisplayOptions::default(),
+        }
+    }
+}
+
+impl PrettyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum width of a column, longer values are cut with an ellipsis.
+    pub fn set_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width.max(1);
+        self
+    }
+
+    /// Set the maximum number of rows to render, or `None` to render all of them.
+    pub fn set_max_rows(mut self, max_rows: Option<usize>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Only render the given columns, in the given order.
+    pub fn set_columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set how the values are formatted.
+    pub fn set_display_options(mut self, display: DisplayOptions) -> Self {
+        self.display = display;
+        self
+    }
+}
 
 /// An RFC table.
+///
+/// Tables are borrowed from the function or structure holding them, except the ones created from
+/// a [`RfcTypeDesc`](crate::types::RfcTypeDesc) which own their rows.
 pub struct RfcTable<'data> {
-    _container: &'data DATA_CONTAINER_HANDLE,
+    _container: PhantomData<&'data DATA_CONTAINER_HANDLE>,
     handle: RFC_TABLE_HANDLE,
     desc: RFC_TYPE_DESC_HANDLE,
     data: RfcDataContainer,
+    _owner: Option<TableOwner>,
 }
 
 impl<'data> RfcTable<'data> {
     pub(crate) fn new(
-        container: &'data DATA_CONTAINER_HANDLE,
-        handle: RFC_TABLE_HANDLE,
+        _container: &'data DATA_CONTAINER_HANDLE,
+        data: RfcDataContainer,
         desc: RFC_TYPE_DESC_HANDLE,
     ) -> Self {
         Self {
-            _container: container,
+            _container: PhantomData,
+            handle: data.handle(),
+            desc,
+            data,
+            _owner: None,
+        }
+    }
+
+    /// Wrap a table created on its own, destroyed when dropped.
+    pub(crate) fn new_owned(
+        handle: RFC_TABLE_HANDLE,
+        desc: RFC_TYPE_DESC_HANDLE,
+    ) -> RfcTable<'static> {
+        RfcTable {
+            _container: PhantomData,
             handle,
             desc,
             data: RfcDataContainer::new(handle),
+            _owner: Some(TableOwner(handle)),
         }
     }
 
+    pub(crate) fn handle(&self) -> RFC_TABLE_HANDLE {
+        self.handle
+    }
+
     pub fn name(&self) -> String {
         let mut err_info = RfcErrorInfo::new();
         let mut uc_name: RFC_ABAP_NAME = Default::default();
@@ -58,17 +158,38 @@ impl<'data> RfcTable<'data> {
         count
     }
 
-    fn current_row<'row: 'data>(&'row self) -> Result<RfcStructure<'row>> {
+    /// Get the description of a field by its position, an index out of range is an error.
+    pub fn field_desc_by_index(&self, index: u32) -> Result<RfcFieldDesc> {
+        types::field_desc_by_index(self.desc, index)
+    }
+
+    /// Get the description of a field by its name.
+    pub fn field_desc_by_name(&self, name: &str) -> Result<RfcFieldDesc> {
+        types::field_desc_by_name(self.desc, name)
+    }
+
+    /// Iterate over the descriptions of all the fields, in declaration order.
+    pub fn fields(&self) -> Result<Fields<'_>> {
+        Fields::new(self.desc)
+    }
+
+    /// Wrap the current row, which is at the given index.
+    fn current_row(&self, index: u32) -> Result<RfcStructure<'_>> {
         let mut err_info = RfcErrorInfo::new();
         let handle = unsafe { RfcGetCurrentRow(self.handle, err_info.as_mut_ptr()) };
         if handle.is_null() {
-            return Err(err_info);
-        }
-        let desc = unsafe { RfcGetRowType(self.handle, err_info.as_mut_ptr()) };
-        if desc.is_null() {
-            return Err(err_info);
+            return Err(err_info.into());
         }
-        Ok(RfcStructure::new(&self.handle, handle, desc))
+        Ok(self.row(handle, index))
+    }
+
+    fn row(&self, handle: RFC_STRUCTURE_HANDLE, index: u32) -> RfcStructure<'_> {
+        let data = self.data.row(handle, index);
+        RfcStructure::new(&self.handle, data, self.desc)
+    }
+
+    pub(crate) fn field_layout(&self) -> Result<Vec<(String, DataDesc)>> {
+        field_descs(self.desc)
     }
 
     /// Get the number of rows in the table.
@@ -81,41 +202,485 @@ impl<'data> RfcTable<'data> {
     }
 
     /// Get the row at the given index.
-    pub fn get_row<'row: 'data>(&'row self, index: u32) -> Result<RfcStructure<'row>> {
+    pub fn get_row(&self, index: u32) -> Result<RfcStructure<'_>> {
         unsafe {
-            check_rc_ok!(RfcMoveTo(self.handle, index as u32));
+            check_rc_ok!(RfcMoveTo(self.handle, index));
         }
-        self.current_row()
+        self.current_row(index)
     }
 
     /// Get the first row.
-    pub fn get_first_row<'row: 'data>(&'row self) -> Result<RfcStructure<'row>> {
+    pub fn get_first_row(&self) -> Result<RfcStructure<'_>> {
         unsafe {
             check_rc_ok!(RfcMoveToFirstRow(self.handle));
         }
-        self.current_row()
+        self.current_row(0)
     }
 
     /// Get the last row.
-    pub fn get_last_row<'row: 'data>(&'row self) -> Result<RfcStructure<'row>> {
+    pub fn get_last_row(&self) -> Result<RfcStructure<'_>> {
         unsafe {
             check_rc_ok!(RfcMoveToLastRow(self.handle));
         }
-        self.current_row()
+        self.current_row(self.row_count()?.saturating_sub(1))
     }
 
     /// Append a new row and return it.
-    pub fn append_row<'row: 'data>(&'row mut self) -> Result<RfcStructure<'row>> {
+    pub fn append_row(&mut self) -> Result<RfcStructure<'_>> {
+        let index = self.row_count()?;
+        self.append_row_at(index)
+    }
+
+    /// Append a new row, which is known to land at the given index.
+    fn append_row_at(&mut self, index: u32) -> Result<RfcStructure<'_>> {
         let mut err_info = RfcErrorInfo::new();
         let handle = unsafe { RfcAppendNewRow(self.handle, err_info.as_mut_ptr()) };
         if handle.is_null() {
-            return Err(err_info);
+            return Err(err_info.into());
         }
-        Ok(RfcStructure::new(&self.handle, handle, self.desc))
+        Ok(self.row(handle, index))
+    }
+
+    /// Append the given number of rows, each one filled by a closure given its index.
+    ///
+    /// On failure the rows appended so far are removed and the error names the faulty row.
+    pub fn append_rows_with<F>(
+        &mut self,
+        count: usize,
+        mut fill: F,
+    ) -> std::result::Result<usize, RowError>
+    where
+        F: FnMut(usize, &mut RfcStructure) -> Result<()>,
+    {
+        self.append_rows(0..count, |index, row| fill(index, row))
+    }
+
+    /// Append one row per item, each one filled from its item by a closure, returning the number
+    /// of rows appended.
+    ///
+    /// On failure the rows appended so far are removed and the error names the faulty row.
+    pub fn append_rows<I, F>(
+        &mut self,
+        items: I,
+        mut fill: F,
+    ) -> std::result::Result<usize, RowError>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item, &mut RfcStructure) -> Result<()>,
+    {
+        let initial_count = self.row_count().map_err(|err| RowError::new(0, err))?;
+        let mut appended = 0;
+        for item in items {
+            let index = initial_count + appended as u32;
+            if let Err(err) = self
+                .append_row_at(index)
+                .and_then(|mut row| fill(item, &mut row))
+            {
+                if let Err(rollback) = self.truncate_rows(initial_count) {
+                    log::warn!("Removing the appended rows failed: {}", rollback);
+                }
+                return Err(RowError::new(appended, err));
+            }
+            appended += 1;
+        }
+        Ok(appended)
+    }
+
+    /// Replace the row at the given index with a copy of a structure of the row type.
+    pub fn set_row(&mut self, index: u32, row: &RfcStructure) -> Result<()> {
+        self.modify_row(index, |current| current.copy_from(row))
+    }
+
+    /// Update the row at the given index with a closure.
+    pub fn modify_row<F, T>(&mut self, index: u32, modify: F) -> Result<T>
+    where
+        F: FnOnce(&mut RfcStructure) -> Result<T>,
+    {
+        let mut row = self.get_row(index)?;
+        modify(&mut row)
+    }
+
+    /// Append one row per map of field names to values, returning the number of rows appended.
+    ///
+    /// Values are converted according to the type of their field. Unknown fields are an error,
+    /// see [`append_from_maps_with`](Self::append_from_maps_with) to skip them instead.
+    pub fn append_from_maps(&mut self, rows: &[HashMap<String, String>]) -> Result<usize> {
+        self.append_from_maps_with(rows, UnknownFields::Error)
+    }
+
+    /// Append one row per map of field names to values, returning the number of rows appended.
+    ///
+    /// On failure the rows appended so far are removed and the error names the faulty row and
+    /// field.
+    pub fn append_from_maps_with(
+        &mut self,
+        rows: &[HashMap<String, String>],
+        unknown: UnknownFields,
+    ) -> Result<usize> {
+        let fields = self.field_layout()?;
+        let initial_count = self.row_count()?;
+        for (index, values) in rows.iter().enumerate() {
+            if let Err((field, err)) = self.append_from_map(values, &fields, unknown) {
+                self.truncate_rows(initial_count)?;
+                return Err(RfcError::custom_with_code(
+                    err.info().code(),
+                    &format!("Row {} field {}: {}", index, field, err.message()),
+                ));
+            }
+        }
+        Ok(rows.len())
+    }
+
+    fn append_from_map(
+        &mut self,
+        values: &HashMap<String, String>,
+        fields: &[(String, DataDesc)],
+        unknown: UnknownFields,
+    ) -> std::result::Result<(), (String, RfcError)> {
+        let mut row = self.append_row().map_err(|err| (String::new(), err))?;
+        for (name, value) in values {
+            if unknown == UnknownFields::Skip && !fields.iter().any(|(field, _)| field == name) {
+                continue;
+            }
+            row.set_from_str(name, value)
+                .map_err(|err| (name.clone(), err))?;
+        }
+        Ok(())
+    }
+
+    /// Copy the rows into a map keyed by the trimmed values of the given fields.
+    ///
+    /// Rows with the same key are an error, see [`index_by_with`](Self::index_by_with) to keep
+    /// one of them instead.
+    pub fn index_by(&self, fields: &[&str]) -> Result<HashMap<Vec<String>, OwnedStructure>> {
+        self.index_by_with(fields, DuplicateKeys::Error)
+    }
+
+    /// Copy the rows into a map keyed by the trimmed value of a single field.
+    pub fn index_by_field(&self, field: &str) -> Result<HashMap<String, OwnedStructure>> {
+        Ok(self
+            .index_by(&[field])?
+            .into_iter()
+            .map(|(mut key, row)| (key.remove(0), row))
+            .collect())
+    }
+
+    /// Copy the rows into a map keyed by the trimmed values of the given fields.
+    pub fn index_by_with(
+        &self,
+        fields: &[&str],
+        duplicates: DuplicateKeys,
+    ) -> Result<HashMap<Vec<String>, OwnedStructure>> {
+        let descs = self.field_layout()?;
+        let missing: Vec<_> = fields
+            .iter()
+            .filter(|field| !descs.iter().any(|(name, _)| name == *field))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            return Err(RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_PARAMETER,
+                &format!(
+                    "Key fields {} are not in the row type {}",
+                    join_names(&missing),
+                    self.name()
+                ),
+            ));
+        }
+        let key_descs: Vec<_> = fields
+            .iter()
+            .map(|field| {
+                let (_, desc) = descs.iter().find(|(name, _)| name == field).unwrap();
+                *desc
+            })
+            .collect();
+
+        let mut index = HashMap::new();
+        for row_index in 0..self.row_count()? {
+            let row = self.get_row(row_index)?;
+            let key = row_key(&row, fields, &key_descs)?;
+            if index.contains_key(&key) {
+                match duplicates {
+                    DuplicateKeys::Error => {
+                        return Err(RfcError::custom_with_code(
+                            _RFC_RC::RFC_INVALID_PARAMETER,
+                            &format!("Duplicate key {:?} at row {}", key, row_index),
+                        ))
+                    }
+                    DuplicateKeys::FirstWins => continue,
+                    DuplicateKeys::LastWins => {}
+                }
+            }
+            index.insert(key, OwnedStructure::from_structure(&row)?);
+        }
+        Ok(index)
+    }
+
+    /// Read only the given fields of every row, as one vector of values per row.
+    ///
+    /// The fields are resolved once up front and the other fields of the rows are never read,
+    /// which is much faster than copying whole rows of a wide table.
+    pub fn read_columns(&self, fields: &[&str]) -> Result<Vec<Vec<OwnedValue>>> {
+        let descs = self.field_layout()?;
+        let columns = fields
+            .iter()
+            .map(|field| {
+                descs
+                    .iter()
+                    .position(|(name, _)| name == field)
+                    .map(|index| (index as u32, *field, descs[index].1))
+            })
+            .collect::<Option<Vec<_>>>();
+        let columns = match columns {
+            Some(columns) => columns,
+            None => {
+                let missing: Vec<_> = fields
+                    .iter()
+                    .filter(|field| !descs.iter().any(|(name, _)| name == *field))
+                    .copied()
+                    .collect();
+                let available: Vec<_> = descs.iter().map(|(name, _)| name.as_str()).collect();
+                return Err(RfcError::custom_with_code(
+                    _RFC_RC::RFC_INVALID_PARAMETER,
+                    &format!(
+                        "Fields {} are not in the row type {}, available fields are {}",
+                        join_names(&missing),
+                        self.name(),
+                        join_names(&available)
+                    ),
+                ));
+            }
+        };
+
+        let count = self.row_count()?;
+        let mut rows = Vec::with_capacity(count as usize);
+        for row_index in 0..count {
+            let row = self.get_row(row_index)?;
+            rows.push(
+                columns
+                    .iter()
+                    .map(|(index, name, desc)| row.owned_value_by_index(*index, name, desc))
+                    .collect::<Result<_>>()?,
+            );
+        }
+        Ok(rows)
+    }
+
+    /// Delete the rows past the given count.
+    fn truncate_rows(&mut self, count: u32) -> Result<()> {
+        while self.row_count()? > count {
+            unsafe {
+                check_rc_ok!(RfcMoveToLastRow(self.handle));
+                check_rc_ok!(RfcDeleteCurrentRow(self.handle));
+            }
+        }
+        Ok(())
+    }
+
+    /// Append a copy of a structure as a new row.
+    ///
+    /// The structure must have the same type as the rows of the table. Structures described by
+    /// another connection are copied field by field.
+    pub fn append_row_copy(&mut self, row: &RfcStructure) -> Result<()> {
+        if row.type_desc() == self.desc {
+            unsafe {
+                check_rc_ok!(RfcAppendRow(self.handle, row.handle()));
+            }
+            return Ok(());
+        }
+        if layout_mismatch(&self.field_layout()?, &row.field_layout()?).is_some()
+            || row.name() != self.name()
+        {
+            return Err(RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_PARAMETER,
+                &format!(
+                    "Cannot append a row of type {} to a table of {}",
+                    row.name(),
+                    self.name()
+                ),
+            ));
+        }
+        value::copy_fields(row, &mut self.append_row()?)
+    }
+
+    /// Append a copy of every row of another table.
+    ///
+    /// On failure the rows appended so far are removed.
+    pub fn extend_from_table(&mut self, other: &RfcTable) -> Result<()> {
+        let start = self.row_count()?;
+        let result = (|| {
+            for index in 0..other.row_count()? {
+                self.append_row_copy(&other.get_row(index)?)?;
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            self.truncate_rows(start)?;
+        }
+        result
+    }
+
+    /// Append a copy of every row of another table with a compatible row type, returning the
+    /// number of rows added.
+    ///
+    /// The row types must have the same fields with the same types and lengths, the first
+    /// difference is reported otherwise. Rows of another type description, like the one of a
+    /// table of another connection, are copied field by field.
+    pub fn merge_from(&mut self, other: &RfcTable) -> Result<u32> {
+        self.merge_rows(other, None)
+    }
+
+    /// Append a copy of the rows of another table whose key is not in the table yet, returning
+    /// the number of rows added.
+    ///
+    /// Keys are compared on their display values, like [`index_by`](Self::index_by). Only the
+    /// first of the rows of the other table with the same key is added.
+    pub fn merge_from_dedup(&mut self, other: &RfcTable, keys: &[&str]) -> Result<u32> {
+        self.merge_rows(other, Some(keys))
+    }
+
+    fn merge_rows(&mut self, other: &RfcTable, keys: Option<&[&str]>) -> Result<u32> {
+        let same_type = other.desc == self.desc;
+        let descs = self.field_layout()?;
+        if !same_type {
+            if let Some(mismatch) = layout_mismatch(&descs, &other.field_layout()?) {
+                return Err(RfcError::custom_with_code(
+                    _RFC_RC::RFC_INVALID_PARAMETER,
+                    &format!(
+                        "Cannot merge rows of {} into a table of {}, {}",
+                        other.name(),
+                        self.name(),
+                        mismatch
+                    ),
+                ));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let key_descs = match keys {
+            Some(keys) => {
+                let key_descs = keys
+                    .iter()
+                    .map(|key| {
+                        descs
+                            .iter()
+                            .find(|(name, _)| name == key)
+                            .map(|(_, desc)| *desc)
+                            .ok_or_else(|| {
+                                RfcError::custom_with_code(
+                                    _RFC_RC::RFC_INVALID_PARAMETER,
+                                    &format!(
+                                        "Key field {} is not in the row type {}",
+                                        key,
+                                        self.name()
+                                    ),
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                for index in 0..self.row_count()? {
+                    seen.insert(row_key(&self.get_row(index)?, keys, &key_descs)?);
+                }
+                Some((keys, key_descs))
+            }
+            None => None,
+        };
+
+        let start = self.row_count()?;
+        let result = (|| {
+            let mut added = 0;
+            for index in 0..other.row_count()? {
+                let row = other.get_row(index)?;
+                if let Some((keys, key_descs)) = &key_descs {
+                    if !seen.insert(row_key(&row, keys, key_descs)?) {
+                        continue;
+                    }
+                }
+                if same_type {
+                    unsafe {
+                        check_rc_ok!(RfcAppendRow(self.handle, row.handle()));
+                    }
+                } else {
+                    // The layouts match but the type names may not
+                    value::copy_fields(&row, &mut self.append_row()?)?;
+                }
+                added += 1;
+            }
+            Ok(added)
+        })();
+        if result.is_err() {
+            self.truncate_rows(start)?;
+        }
+        result
+    }
+
+    /// Check if the rows of the table hold a single value rather than named fields.
+    ///
+    /// Tables with an elementary line type and tables of single field structures are scalar.
+    pub fn is_scalar(&self) -> Result<bool> {
+        Ok(self.scalar_field_name()?.is_some())
+    }
+
+    /// Get the value of a row of a scalar table.
+    pub fn get_scalar_row(&self, index: u32) -> Result<String> {
+        let field = self.scalar_field()?;
+        self.get_row(index)?.get_string(&field)
+    }
+
+    /// Append a row with the given value to a scalar table.
+    pub fn append_scalar_row(&mut self, value: &str) -> Result<()> {
+        let field = self.scalar_field()?;
+        self.append_row()?.set_string(&field, value)
+    }
+
+    /// Iterate over the values of the rows of a scalar table.
+    pub fn scalar_rows(&self) -> Result<ScalarRows<'_, 'data>> {
+        Ok(ScalarRows {
+            field: self.scalar_field()?,
+            index: 0,
+            count: self.row_count()?,
+            table: self,
+        })
+    }
+
+    /// Get the type of the rows when the line type is elementary, a single field without a name.
+    ///
+    /// Unlike [`is_scalar`](Self::is_scalar), a structure with a single named field is not
+    /// elementary.
+    #[cfg(feature = "serde")]
+    pub(crate) fn elementary_type(&self) -> Result<Option<sapnwrfc_sys::RFCTYPE>> {
+        Ok(match self.field_layout()?.as_slice() {
+            [(name, desc)] if name.is_empty() => Some(desc.rfc_type),
+            _ => None,
+        })
+    }
+
+    /// Get the name of the only field of the line type, which is empty for elementary types.
+    pub(crate) fn scalar_field(&self) -> Result<String> {
+        self.scalar_field_name()?.ok_or_else(|| {
+            RfcError::custom_with_code(
+                _RFC_RC::RFC_INVALID_PARAMETER,
+                &format!("Table of {} does not have a scalar line type", self.name()),
+            )
+        })
+    }
+
+    /// Get the name of the only field of a scalar line type, none for other line types.
+    fn scalar_field_name(&self) -> Result<Option<String>> {
+        let mut fields = self.field_layout()?;
+        if fields.len() != 1
+            || matches!(
+                fields[0].1.rfc_type,
+                _RFCTYPE::RFCTYPE_STRUCTURE | _RFCTYPE::RFCTYPE_TABLE
+            )
+        {
+            return Ok(None);
+        }
+        Ok(Some(fields.remove(0).0))
     }
 
     /// Insert a new row at the given position and return it.
-    pub fn insert_row<'row: 'data>(&'row mut self, index: u32) -> Result<RfcStructure<'row>> {
+    pub fn insert_row(&mut self, index: u32) -> Result<RfcStructure<'_>> {
         let mut err_info = RfcErrorInfo::new();
         unsafe {
             check_rc_ok!(
@@ -125,9 +690,9 @@ impl<'data> RfcTable<'data> {
         }
         let handle = unsafe { RfcInsertNewRow(self.handle, err_info.as_mut_ptr()) };
         if handle.is_null() {
-            return Err(err_info);
+            return Err(err_info.into());
         }
-        Ok(RfcStructure::new(&self.handle, handle, self.desc))
+        Ok(self.row(handle, index))
     }
 
     /// Delete the row at the given index.
@@ -154,6 +719,58 @@ impl<'data> RfcTable<'data> {
         Ok(())
     }
 
+    /// Reset a field of the current row to the initial value of its type.
+    pub fn clear_field(&mut self, name: &str) -> Result<()> {
+        let name = &uc::from_str_to_abap_name(name)?;
+        let desc = self.data_desc(name)?;
+        self.data.clear(name, &desc)
+    }
+
+    /// Render the table as text in the style of a database command line client.
+    ///
+    /// The rows are read through the cursor, which is left on the last row shown. Move it again
+    /// before using the accessors of the current row.
+    pub fn to_pretty_string(&self, opts: &PrettyOptions) -> Result<String> {
+        let columns = self.pretty_columns(opts)?;
+        let count = self.row_count()?;
+        let shown = opts
+            .max_rows
+            .map_or(count, |max_rows| count.min(max_rows as u32));
+
+        let rows = (0..shown)
+            .map(|index| {
+                let row = self.get_row(index)?;
+                columns
+                    .iter()
+                    .map(|(name, desc)| row.display_value(name, desc, &opts.display))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let headers = columns.into_iter().map(|(name, _)| name).collect();
+        Ok(render_table(headers, rows, count - shown, opts.max_width))
+    }
+
+    fn pretty_columns(&self, opts: &PrettyOptions) -> Result<Vec<(String, DataDesc)>> {
+        match &opts.columns {
+            Some(columns) => columns
+                .iter()
+                .map(|name| {
+                    let desc = self.data_desc(&uc::from_str_to_abap_name(name)?)?;
+                    Ok((name.clone(), desc))
+                })
+                .collect(),
+            None => self.field_layout(),
+        }
+    }
+
+    /// Get the name of a field of the rows, converted once to be reused across accesses.
+    pub fn field(&self, name: &str) -> Result<FieldName> {
+        let name = FieldName::new(name)?;
+        self.data_desc(name.abap_name())?;
+        Ok(name)
+    }
+
     rfc_data_delegates!(self.data, |name, desc| {
         unsafe {
             check_rc_ok!(RfcGetFieldDescByName(self.desc, name.as_ptr(), &mut desc));
@@ -162,3 +779,156 @@ impl<'data> RfcTable<'data> {
 }
 
 unsafe impl Send for RfcTable<'_> {}
+
+/// Destroys a table created on its own.
+///
+/// Kept apart so that borrowed tables have no drop code extending the borrow of their holder.
+struct TableOwner(RFC_TABLE_HANDLE);
+
+impl Drop for TableOwner {
+    fn drop(&mut self) {
+        let mut err_info = RfcErrorInfo::new();
+        unsafe {
+            if is_rc_err!(RfcDestroyTable(self.0, err_info.as_mut_ptr())) {
+                log::warn!("Table destroy failed: {}", err_info);
+            }
+        }
+    }
+}
+
+/// An iterator over the values of a scalar table.
+pub struct ScalarRows<'table, 'data> {
+    table: &'table RfcTable<'data>,
+    field: String,
+    index: u32,
+    count: u32,
+}
+
+impl Iterator for ScalarRows<'_, '_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+        let value = self
+            .table
+            .get_row(self.index)
+            .and_then(|row| row.get_string(&self.field));
+        self.index += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Describe the first difference between two row layouts, if any.
+fn layout_mismatch(a: &[(String, DataDesc)], b: &[(String, DataDesc)]) -> Option<String> {
+    for (index, ((a_name, a_desc), (b_name, b_desc))) in a.iter().zip(b).enumerate() {
+        if a_name != b_name {
+            return Some(format!(
+                "field {} is {} instead of {}",
+                index + 1,
+                b_name,
+                a_name
+            ));
+        }
+        if a_desc.rfc_type != b_desc.rfc_type {
+            return Some(format!(
+                "field {} is {} instead of {}",
+                a_name,
+                RfcType::from(b_desc.rfc_type),
+                RfcType::from(a_desc.rfc_type)
+            ));
+        }
+        if a_desc.uc_length != b_desc.uc_length {
+            return Some(format!(
+                "field {} is {} bytes long instead of {}",
+                a_name, b_desc.uc_length, a_desc.uc_length
+            ));
+        }
+    }
+    if a.len() != b.len() {
+        return Some(format!("{} fields instead of {}", b.len(), a.len()));
+    }
+    None
+}
+
+/// Get the key of a row from the display values of its key fields.
+fn row_key(row: &RfcStructure, keys: &[&str], key_descs: &[DataDesc]) -> Result<Vec<String>> {
+    let display = DisplayOptions::default();
+    keys.iter()
+        .zip(key_descs)
+        .map(|(key, desc)| {
+            row.display_value(key, desc, &display)
+                .map(|value| value.trim().to_owned())
+        })
+        .collect()
+}
+
+fn fit(value: &str, max_width: usize) -> String {
+    if value.chars().count() > max_width {
+        let mut fitted: String = value.chars().take(max_width - 1).collect();
+        fitted.push('…');
+        fitted
+    } else {
+        value.to_owned()
+    }
+}
+
+fn render_table(
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    hidden: u32,
+    max_width: usize,
+) -> String {
+    let headers: Vec<_> = headers.iter().map(|h| fit(h, max_width)).collect();
+    let rows: Vec<Vec<_>> = rows
+        .iter()
+        .map(|row| row.iter().map(|v| fit(v, max_width)).collect())
+        .collect();
+
+    let mut widths: Vec<_> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let separator = |out: &mut String| {
+        for width in &widths {
+            out.push('+');
+            out.push_str(&"-".repeat(width + 2));
+        }
+        out.push_str("+\n");
+    };
+    let line = |out: &mut String, values: &[String]| {
+        for (width, value) in widths.iter().zip(values) {
+            let _ = write!(
+                out,
+                "| {}{} ",
+                value,
+                " ".repeat(width - value.chars().count())
+            );
+        }
+        out.push_str("|\n");
+    };
+
+    separator(&mut out);
+    line(&mut out, &headers);
+    separator(&mut out);
+    if !rows.is_empty() {
+        for row in &rows {
+            line(&mut out, row);
+        }
+        separator(&mut out);
+    }
+    if hidden > 0 {
+        let _ = writeln!(out, "… and {} more rows", hidden);
+    }
+    out
+}
@@ -0,0 +1,153 @@
+use crate::{
+    connection::RfcConnection,
+    error::{Result, RfcErrorInfo},
+    macros::{check_rc_ok, is_rc_err},
+};
+use sapnwrfc_sys::{
+    RfcCreateThroughput, RfcDestroyThroughput, RfcGetApplicationTime, RfcGetDeserializationTime,
+    RfcGetNumberOfCalls, RfcGetReceivedBytes, RfcGetSentBytes, RfcGetSerializationTime,
+    RfcGetTotalTime, RfcRemoveThroughputFromConnection, RfcResetThroughput,
+    RfcSetThroughputOnConnection, RFC_THROUGHPUT_HANDLE,
+};
+use std::{sync::Arc, time::Duration};
+
+/// Statistics on the calls made on the connections it is attached to.
+///
+/// A throughput attached to several connections adds up their calls. Clones share the same
+/// statistics, and an attached connection keeps its throughput alive until it is closed.
+#[derive(Clone, Debug)]
+pub struct RfcThroughput {
+    inner: Arc<ThroughputHandle>,
+}
+
+impl RfcThroughput {
+    /// Create a throughput with all its statistics at zero.
+    pub fn new() -> Result<Self> {
+        let mut err_info = RfcErrorInfo::new();
+        let handle = unsafe { RfcCreateThroughput(err_info.as_mut_ptr()) };
+        if handle.is_null() {
+            return Err(err_info.into());
+        }
+        Ok(Self {
+            inner: Arc::new(ThroughputHandle(handle)),
+        })
+    }
+
+    fn handle(&self) -> RFC_THROUGHPUT_HANDLE {
+        self.inner.0
+    }
+
+    /// Count the calls made on a connection from now on, replacing its previous throughput.
+    pub fn attach(&self, conn: &RfcConnection) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe {
+            RfcSetThroughputOnConnection(conn.handle(), self.handle(), err_info.as_mut_ptr())
+        }) {
+            return Err(conn.track_error(err_info).into());
+        }
+        conn.set_throughput(Some(self.clone()));
+        Ok(())
+    }
+
+    /// Stop counting the calls made on a connection.
+    pub fn detach(&self, conn: &RfcConnection) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe {
+            RfcRemoveThroughputFromConnection(conn.handle(), err_info.as_mut_ptr())
+        }) {
+            return Err(conn.track_error(err_info).into());
+        }
+        conn.set_throughput(None);
+        Ok(())
+    }
+
+    /// Set all the statistics back to zero.
+    pub fn reset(&self) -> Result<()> {
+        unsafe {
+            check_rc_ok!(RfcResetThroughput(self.handle()));
+        }
+        Ok(())
+    }
+
+    /// Get the number of calls made.
+    pub fn number_of_calls(&self) -> Result<u64> {
+        let mut value = 0;
+        unsafe {
+            check_rc_ok!(RfcGetNumberOfCalls(self.handle(), &mut value));
+        }
+        Ok(value)
+    }
+
+    /// Get the time spent in the calls, from start to end.
+    pub fn total_time(&self) -> Result<Duration> {
+        let mut value = 0;
+        unsafe {
+            check_rc_ok!(RfcGetTotalTime(self.handle(), &mut value));
+        }
+        Ok(Duration::from_millis(value))
+    }
+
+    /// Get the time spent serializing the parameters sent.
+    pub fn serialization_time(&self) -> Result<Duration> {
+        let mut value = 0;
+        unsafe {
+            check_rc_ok!(RfcGetSerializationTime(self.handle(), &mut value));
+        }
+        Ok(Duration::from_millis(value))
+    }
+
+    /// Get the time spent deserializing the parameters received.
+    pub fn deserialization_time(&self) -> Result<Duration> {
+        let mut value = 0;
+        unsafe {
+            check_rc_ok!(RfcGetDeserializationTime(self.handle(), &mut value));
+        }
+        Ok(Duration::from_millis(value))
+    }
+
+    /// Get the time spent by the backend running the functions.
+    pub fn application_time(&self) -> Result<Duration> {
+        let mut value = 0;
+        unsafe {
+            check_rc_ok!(RfcGetApplicationTime(self.handle(), &mut value));
+        }
+        Ok(Duration::from_millis(value))
+    }
+
+    /// Get the number of bytes sent.
+    pub fn sent_bytes(&self) -> Result<u64> {
+        let mut value = 0;
+        unsafe {
+            check_rc_ok!(RfcGetSentBytes(self.handle(), &mut value));
+        }
+        Ok(value)
+    }
+
+    /// Get the number of bytes received.
+    pub fn received_bytes(&self) -> Result<u64> {
+        let mut value = 0;
+        unsafe {
+            check_rc_ok!(RfcGetReceivedBytes(self.handle(), &mut value));
+        }
+        Ok(value)
+    }
+}
+
+/// The throughput handle shared by the clones, destroyed with the last one.
+#[derive(Debug)]
+struct ThroughputHandle(RFC_THROUGHPUT_HANDLE);
+
+impl Drop for ThroughputHandle {
+    fn drop(&mut self) {
+        let mut err_info = RfcErrorInfo::new();
+        unsafe {
+            if is_rc_err!(RfcDestroyThroughput(self.0, err_info.as_mut_ptr())) {
+                log::warn!("Throughput destroy failed: {}", err_info);
+            }
+        }
+    }
+}
+
+// The SDK updates the statistics of a throughput shared by connections on several threads
+unsafe impl Send for ThroughputHandle {}
+unsafe impl Sync for ThroughputHandle {}
@@ -0,0 +1,140 @@
+use crate::{
+    connection::RfcConnection,
+    error::{Result, RfcError, RfcErrorInfo},
+    function::RfcFunction,
+    macros::is_rc_err,
+    uc::{self, UcString},
+};
+use sapnwrfc_sys::{
+    RfcConfirmTransaction, RfcCreateTransaction, RfcDestroyTransaction, RfcGetTransactionID,
+    RfcInvokeInTransaction, RfcSubmitTransaction, RFC_TID, RFC_TID_LN, RFC_TRANSACTION_HANDLE,
+    _RFC_RC,
+};
+use std::ptr;
+
+/// A transactional RFC unit, executed exactly once by the backend.
+///
+/// Functions invoked in the transaction are only recorded, they are all sent when the
+/// transaction is submitted. The id of the transaction should be stored before submitting, so
+/// that a transaction interrupted by a failure can be submitted again with the same id. A
+/// transaction with a queue name is a queued RFC unit, executed in order within its queue.
+#[derive(Debug)]
+pub struct RfcTransaction<'conn> {
+    conn: &'conn RfcConnection,
+    handle: RFC_TRANSACTION_HANDLE,
+    tid: String,
+    queue: Option<String>,
+}
+
+impl<'conn> RfcTransaction<'conn> {
+    pub(crate) fn new(
+        conn: &'conn RfcConnection,
+        tid: Option<&str>,
+        queue: Option<&str>,
+    ) -> Result<Self> {
+        let mut uc_tid: RFC_TID = Default::default();
+        match tid {
+            Some(tid) => {
+                if tid.is_empty() || tid.chars().count() > RFC_TID_LN as usize {
+                    return Err(RfcError::custom_with_code(
+                        _RFC_RC::RFC_INVALID_PARAMETER,
+                        &format!(
+                            "Transaction id {:?} must have between 1 and {} characters",
+                            tid, RFC_TID_LN
+                        ),
+                    ));
+                }
+                uc::from_str_to_slice(tid, &mut uc_tid)?;
+            }
+            None => {
+                let mut err_info = RfcErrorInfo::new();
+                let rc = unsafe {
+                    RfcGetTransactionID(conn.handle(), uc_tid.as_mut_ptr(), err_info.as_mut_ptr())
+                };
+                if is_rc_err!(rc) {
+                    return Err(conn.track_error(err_info).into());
+                }
+            }
+        }
+        let uc_queue = queue.map(uc::from_str).transpose()?;
+
+        let mut err_info = RfcErrorInfo::new();
+        let handle = unsafe {
+            RfcCreateTransaction(
+                conn.handle(),
+                uc_tid.as_ptr(),
+                uc_queue.as_ref().map_or(ptr::null(), UcString::as_ptr),
+                err_info.as_mut_ptr(),
+            )
+        };
+        if handle.is_null() {
+            return Err(conn.track_error(err_info).into());
+        }
+        Ok(Self {
+            conn,
+            handle,
+            tid: uc::to_string_truncate(&uc_tid)?,
+            queue: queue.map(str::to_owned),
+        })
+    }
+
+    /// Get the id of the transaction, to store for submitting it again after a failure.
+    pub fn tid(&self) -> &str {
+        &self.tid
+    }
+
+    /// Get the name of the queue of a queued RFC unit.
+    pub fn queue(&self) -> Option<&str> {
+        self.queue.as_deref()
+    }
+
+    /// Record a call of the function in the transaction.
+    ///
+    /// The parameters are copied when recorded, the function can be reused for the next call.
+    /// Export parameters are never filled in a transaction.
+    pub fn invoke(&self, func: &RfcFunction) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe {
+            RfcInvokeInTransaction(self.handle, func.handle(), err_info.as_mut_ptr())
+        }) {
+            return Err(self.conn.track_error(err_info).into());
+        }
+        Ok(())
+    }
+
+    /// Send the recorded calls to the backend for execution.
+    ///
+    /// Submitting again a transaction with an id already executed by the backend succeeds
+    /// without executing the calls a second time.
+    pub fn submit(&self) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe { RfcSubmitTransaction(self.handle, err_info.as_mut_ptr()) }) {
+            return Err(self.conn.track_error(err_info).into());
+        }
+        Ok(())
+    }
+
+    /// Tell the backend the transaction was submitted, so it can forget its id.
+    ///
+    /// The id must not be used again afterwards.
+    pub fn confirm(&self) -> Result<()> {
+        let mut err_info = RfcErrorInfo::new();
+        if is_rc_err!(unsafe { RfcConfirmTransaction(self.handle, err_info.as_mut_ptr()) }) {
+            return Err(self.conn.track_error(err_info).into());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RfcTransaction<'_> {
+    fn drop(&mut self) {
+        let mut err_info = RfcErrorInfo::new();
+        unsafe {
+            if is_rc_err!(RfcDestroyTransaction(self.handle, err_info.as_mut_ptr())) {
+                log::warn!("Transaction destroy failed: {}", err_info);
+            }
+        }
+    }
+}
+
+unsafe impl Send for RfcTransaction<'_> {}
@@ -0,0 +1,419 @@
+use crate::{
+    error::{Result, RfcErrorInfo},
+    macros::{check_rc_ok, is_rc_err},
+    structure::RfcStructure,
+    table::RfcTable,
+    uc,
+};
+use sapnwrfc_sys::{
+    RfcCreateStructure, RfcCreateTable, RfcDestroyTypeDesc, RfcGetFieldCount,
+    RfcGetFieldDescByIndex, RfcGetFieldDescByName, RfcGetTypeName, RFCTYPE, RFC_ABAP_NAME,
+    RFC_DIRECTION, RFC_FIELD_DESC, RFC_TYPE_DESC_HANDLE, _RFCTYPE, _RFC_DIRECTION, _RFC_RC,
+};
+use std::{fmt, marker::PhantomData};
+
+/// The type of a field or parameter as declared in the ABAP dictionary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RfcType {
+    /// Fixed-length characters, `C`.
+    Char,
+    /// A date as `YYYYMMDD`, `D`.
+    Date,
+    /// A packed number, `P`.
+    Bcd,
+    /// A time of day as `HHMMSS`, `T`.
+    Time,
+    /// Fixed-length binary data, `X`.
+    Byte,
+    Table,
+    /// Fixed-length digits, `N`.
+    Num,
+    Float,
+    Int,
+    Int2,
+    Int1,
+    Null,
+    AbapObject,
+    Structure,
+    DecF16,
+    DecF34,
+    XmlData,
+    String,
+    XString,
+    Int8,
+    UtcLong,
+    /// A type this version of the crate does not know about.
+    Other(RFCTYPE),
+}
+
+impl From<RFCTYPE> for RfcType {
+    fn from(rfc_type: RFCTYPE) -> Self {
+        match rfc_type {
+            _RFCTYPE::RFCTYPE_CHAR => Self::Char,
+            _RFCTYPE::RFCTYPE_DATE => Self::Date,
+            _RFCTYPE::RFCTYPE_BCD => Self::Bcd,
+            _RFCTYPE::RFCTYPE_TIME => Self::Time,
+            _RFCTYPE::RFCTYPE_BYTE => Self::Byte,
+            _RFCTYPE::RFCTYPE_TABLE => Self::Table,
+            _RFCTYPE::RFCTYPE_NUM => Self::Num,
+            _RFCTYPE::RFCTYPE_FLOAT => Self::Float,
+            _RFCTYPE::RFCTYPE_INT => Self::Int,
+            _RFCTYPE::RFCTYPE_INT2 => Self::Int2,
+            _RFCTYPE::RFCTYPE_INT1 => Self::Int1,
+            _RFCTYPE::RFCTYPE_NULL => Self::Null,
+            _RFCTYPE::RFCTYPE_ABAPOBJECT => Self::AbapObject,
+            _RFCTYPE::RFCTYPE_STRUCTURE => Self::Structure,
+            _RFCTYPE::RFCTYPE_DECF16 => Self::DecF16,
+            _RFCTYPE::RFCTYPE_DECF34 => Self::DecF34,
+            _RFCTYPE::RFCTYPE_XMLDATA => Self::XmlData,
+            _RFCTYPE::RFCTYPE_STRING => Self::String,
+            _RFCTYPE::RFCTYPE_XSTRING => Self::XString,
+            #[cfg(sapnwrfc_int8)]
+            _RFCTYPE::RFCTYPE_INT8 => Self::Int8,
+            #[cfg(sapnwrfc_utclong)]
+            _RFCTYPE::RFCTYPE_UTCLONG => Self::UtcLong,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// The type as named by the SDK, like `RFCTYPE_CHAR`.
+impl fmt::Display for RfcType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Char => "CHAR",
+            Self::Date => "DATE",
+            Self::Bcd => "BCD",
+            Self::Time => "TIME",
+            Self::Byte => "BYTE",
+            Self::Table => "TABLE",
+            Self::Num => "NUM",
+            Self::Float => "FLOAT",
+            Self::Int => "INT",
+            Self::Int2 => "INT2",
+            Self::Int1 => "INT1",
+            Self::Null => "NULL",
+            Self::AbapObject => "ABAPOBJECT",
+            Self::Structure => "STRUCTURE",
+            Self::DecF16 => "DECF16",
+            Self::DecF34 => "DECF34",
+            Self::XmlData => "XMLDATA",
+            Self::String => "STRING",
+            Self::XString => "XSTRING",
+            Self::Int8 => "INT8",
+            Self::UtcLong => "UTCLONG",
+            Self::Other(rfc_type) => return write!(f, "type {}", rfc_type),
+        };
+        write!(f, "RFCTYPE_{}", name)
+    }
+}
+
+/// The direction in which a function parameter is passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RfcDirection {
+    /// Provided by the caller, `IMPORTING` in ABAP.
+    Import,
+    /// Returned by the function, `EXPORTING` in ABAP.
+    Export,
+    /// Provided by the caller and returned by the function, `CHANGING` in ABAP.
+    Changing,
+    /// A table provided by the caller and returned by the function, `TABLES` in ABAP.
+    Tables,
+    /// A direction this version of the crate does not know about.
+    Other(RFC_DIRECTION),
+}
+
+impl From<RFC_DIRECTION> for RfcDirection {
+    fn from(direction: RFC_DIRECTION) -> Self {
+        match direction {
+            _RFC_DIRECTION::RFC_IMPORT => Self::Import,
+            _RFC_DIRECTION::RFC_EXPORT => Self::Export,
+            _RFC_DIRECTION::RFC_CHANGING => Self::Changing,
+            _RFC_DIRECTION::RFC_TABLES => Self::Tables,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// The description of a field of a structure or of the rows of a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RfcFieldDesc {
+    name: String,
+    rfc_type: RfcType,
+    nuc_length: u32,
+    uc_length: u32,
+    decimals: u32,
+}
+
+impl RfcFieldDesc {
+    fn from_desc(desc: &RFC_FIELD_DESC) -> Result<Self> {
+        Ok(Self {
+            name: uc::to_string_truncate(&desc.name)?,
+            rfc_type: desc.type_.into(),
+            nuc_length: desc.nucLength,
+            uc_length: desc.ucLength,
+            decimals: desc.decimals,
+        })
+    }
+
+    /// Get the name of the field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the type of the field.
+    pub fn rfc_type(&self) -> RfcType {
+        self.rfc_type
+    }
+
+    /// Get the length of the field in the non-Unicode layout, in bytes.
+    pub fn nuc_length(&self) -> u32 {
+        self.nuc_length
+    }
+
+    /// Get the length of the field in the Unicode layout, in bytes.
+    pub fn uc_length(&self) -> u32 {
+        self.uc_length
+    }
+
+    /// Get the number of decimals of a packed number.
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+}
+
+/// Get the description of a field of a type by its position.
+pub(crate) fn field_desc_by_index(
+    type_desc: RFC_TYPE_DESC_HANDLE,
+    index: u32,
+) -> Result<RfcFieldDesc> {
+    let mut desc = RFC_FIELD_DESC::default();
+    unsafe {
+        check_rc_ok!(RfcGetFieldDescByIndex(type_desc, index, &mut desc));
+    }
+    RfcFieldDesc::from_desc(&desc)
+}
+
+/// Get the description of a field of a type by its name.
+pub(crate) fn field_desc_by_name(
+    type_desc: RFC_TYPE_DESC_HANDLE,
+    name: &str,
+) -> Result<RfcFieldDesc> {
+    let name = uc::from_str_to_abap_name(name)?;
+    let mut desc = RFC_FIELD_DESC::default();
+    unsafe {
+        check_rc_ok!(RfcGetFieldDescByName(type_desc, name.as_ptr(), &mut desc));
+    }
+    RfcFieldDesc::from_desc(&desc)
+}
+
+/// The name of a field or parameter, converted once for the SDK.
+///
+/// The accessors of functions, structures and tables take names as text or as a `FieldName`,
+/// which saves converting the name again when the same field is accessed row after row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldName {
+    name: String,
+    uc_name: RFC_ABAP_NAME,
+}
+
+impl FieldName {
+    /// Convert a name, which must fit the 30 characters of an ABAP name.
+    pub fn new(name: &str) -> Result<Self> {
+        Ok(Self {
+            name: name.to_owned(),
+            uc_name: uc::from_str_to_abap_name(name)?,
+        })
+    }
+
+    /// Get the name as given.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn abap_name(&self) -> &RFC_ABAP_NAME {
+        &self.uc_name
+    }
+}
+
+impl fmt::Display for FieldName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A field or parameter name accepted by the accessors, either text or a [`FieldName`].
+pub trait AsFieldName: sealed::Sealed {
+    #[doc(hidden)]
+    fn to_abap_name(&self) -> Result<RFC_ABAP_NAME>;
+}
+
+/// Any text, like `&str`, `String`, `Cow<str>` or `Rc<str>`, is converted on each access.
+impl<T: AsRef<str> + ?Sized> sealed::Sealed for T {}
+
+impl<T: AsRef<str> + ?Sized> AsFieldName for T {
+    fn to_abap_name(&self) -> Result<RFC_ABAP_NAME> {
+        uc::from_str_to_abap_name(self.as_ref())
+    }
+}
+
+impl sealed::Sealed for FieldName {}
+
+impl AsFieldName for FieldName {
+    fn to_abap_name(&self) -> Result<RFC_ABAP_NAME> {
+        Ok(self.uc_name)
+    }
+}
+
+impl sealed::Sealed for &FieldName {}
+
+impl AsFieldName for &FieldName {
+    fn to_abap_name(&self) -> Result<RFC_ABAP_NAME> {
+        Ok(self.uc_name)
+    }
+}
+
+/// The description of a structure type of the ABAP dictionary.
+///
+/// Structures and tables created from a description are not tied to a function or connection,
+/// they can be filled ahead of a call and copied into its parameters.
+#[derive(Debug)]
+pub struct RfcTypeDesc {
+    desc: RFC_TYPE_DESC_HANDLE,
+}
+
+impl RfcTypeDesc {
+    pub(crate) fn new(desc: RFC_TYPE_DESC_HANDLE) -> Self {
+        Self { desc }
+    }
+
+    /// Get the name of the type.
+    pub fn name(&self) -> Result<String> {
+        let mut uc_name: RFC_ABAP_NAME = Default::default();
+        unsafe {
+            check_rc_ok!(RfcGetTypeName(self.desc, uc_name.as_mut_ptr()));
+        }
+        uc::to_string_truncate(&uc_name)
+    }
+
+    /// Iterate over the descriptions of all the fields, in declaration order.
+    pub fn fields(&self) -> Result<Fields<'_>> {
+        Fields::new(self.desc)
+    }
+
+    /// Create an empty structure of the type.
+    pub fn create_structure(&self) -> Result<RfcStructure<'static>> {
+        let mut err_info = RfcErrorInfo::new();
+        let handle = unsafe { RfcCreateStructure(self.desc, err_info.as_mut_ptr()) };
+        if handle.is_null() {
+            return Err(err_info.into());
+        }
+        Ok(RfcStructure::new_owned(handle, self.desc))
+    }
+
+    /// Create an empty table with rows of the type.
+    pub fn create_table(&self) -> Result<RfcTable<'static>> {
+        let mut err_info = RfcErrorInfo::new();
+        let handle = unsafe { RfcCreateTable(self.desc, err_info.as_mut_ptr()) };
+        if handle.is_null() {
+            return Err(err_info.into());
+        }
+        Ok(RfcTable::new_owned(handle, self.desc))
+    }
+}
+
+impl Drop for RfcTypeDesc {
+    fn drop(&mut self) {
+        let mut err_info = RfcErrorInfo::new();
+        unsafe {
+            let rc = RfcDestroyTypeDesc(self.desc, err_info.as_mut_ptr());
+            // Descriptions fetched from the backend are held in the cache of the SDK, which
+            // keeps them alive for the structures and tables created from them.
+            if is_rc_err!(rc) && rc != _RFC_RC::RFC_ILLEGAL_STATE {
+                log::warn!("Type description destroy failed: {}", err_info);
+            }
+        }
+    }
+}
+
+unsafe impl Send for RfcTypeDesc {}
+
+/// An iterator over the field descriptions of a structure or of the rows of a table.
+pub struct Fields<'data> {
+    type_desc: RFC_TYPE_DESC_HANDLE,
+    index: u32,
+    count: u32,
+    _data: PhantomData<&'data ()>,
+}
+
+impl Fields<'_> {
+    pub(crate) fn new(type_desc: RFC_TYPE_DESC_HANDLE) -> Result<Self> {
+        let mut count = 0;
+        unsafe {
+            check_rc_ok!(RfcGetFieldCount(type_desc, &mut count));
+        }
+        Ok(Self {
+            type_desc,
+            index: 0,
+            count,
+            _data: PhantomData,
+        })
+    }
+}
+
+impl Iterator for Fields<'_> {
+    type Item = Result<RfcFieldDesc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+        let desc = field_desc_by_index(self.type_desc, self.index);
+        self.index += 1;
+        Some(desc)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+unsafe impl Send for Fields<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{RfcDirection, RfcType};
+    use sapnwrfc_sys::{_RFCTYPE, _RFC_DIRECTION};
+
+    #[test]
+    fn rfc_type_test() {
+        assert_eq!(RfcType::from(_RFCTYPE::RFCTYPE_CHAR), RfcType::Char);
+        assert_eq!(RfcType::from(_RFCTYPE::RFCTYPE_BCD), RfcType::Bcd);
+        assert_eq!(RfcType::from(_RFCTYPE::RFCTYPE_XSTRING), RfcType::XString);
+        assert_eq!(RfcType::from(9999), RfcType::Other(9999));
+
+        assert_eq!(RfcType::Char.to_string(), "RFCTYPE_CHAR");
+        assert_eq!(RfcType::XString.to_string(), "RFCTYPE_XSTRING");
+        assert_eq!(RfcType::Other(9999).to_string(), "type 9999");
+    }
+
+    #[test]
+    fn rfc_direction_test() {
+        assert_eq!(
+            RfcDirection::from(_RFC_DIRECTION::RFC_IMPORT),
+            RfcDirection::Import
+        );
+        assert_eq!(
+            RfcDirection::from(_RFC_DIRECTION::RFC_TABLES),
+            RfcDirection::Tables
+        );
+        assert_eq!(RfcDirection::from(99), RfcDirection::Other(99));
+    }
+}
@@ -1,10 +1,22 @@
 use crate::{
-    error::{Result, RfcErrorInfo},
+    error::{Result, RfcError, RfcErrorInfo},
     macros::{check_rc_ok, is_rc_err},
 };
-use sapnwrfc_sys::{RfcSAPUCToUTF8, RfcUTF8ToSAPUC, RFC_ABAP_NAME, SAP_UC};
+use sapnwrfc_sys::{
+    RfcSAPUCToUTF8, RfcUTF8ToSAPUC, RFC_ABAP_NAME, RFC_ERROR_INFO, RFC_RC, SAP_UC, _RFC_RC,
+};
 
 pub fn from_str_to_buffer(value: &str, dest: *mut SAP_UC, size: usize) -> Result<u32> {
+    // ASCII text widens unit by unit, sparing the SDK call for the common case of names
+    if value.is_ascii() && value.len() < size {
+        unsafe {
+            for (index, byte) in value.bytes().enumerate() {
+                *dest.add(index) = SAP_UC::from(byte);
+            }
+            *dest.add(value.len()) = 0;
+        }
+        return Ok(value.len() as u32);
+    }
     let mut size = size as u32;
     let mut res_len: u32 = 0;
     unsafe {
@@ -23,89 +35,165 @@ pub fn from_str_to_slice(value: &str, dest: &mut [SAP_UC]) -> Result<u32> {
     from_str_to_buffer(value, dest.as_mut_ptr(), dest.len())
 }
 
+/// Copy as much of a string as fits in a buffer, ending with an ellipsis when cut short.
+///
+/// The code units are encoded in place without the SDK, so this never fails.
+pub fn from_str_to_slice_truncate(value: &str, dest: &mut [SAP_UC]) -> u32 {
+    let capacity = match dest.len().checked_sub(1) {
+        Some(capacity) => capacity,
+        None => return 0,
+    };
+    let mut len = 0;
+    for ch in value.chars() {
+        let mut units = [0; 2];
+        let units = ch.encode_utf16(&mut units);
+        if len + units.len() > capacity {
+            if len > 0 {
+                // Room is made for the ellipsis, without splitting a surrogate pair
+                len -= 1;
+                if (0xDC00..0xE000).contains(&dest[len]) {
+                    len -= 1;
+                }
+            }
+            if capacity > 0 {
+                dest[len] = 0x2026;
+                len += 1;
+            }
+            break;
+        }
+        dest[len..len + units.len()].copy_from_slice(units);
+        len += units.len();
+    }
+    dest[len] = 0;
+    len as u32
+}
+
 pub fn from_str_to_abap_name(value: &str) -> Result<RFC_ABAP_NAME> {
     let mut uc_value: RFC_ABAP_NAME = Default::default();
     from_str_to_slice(value, &mut uc_value)?;
     Ok(uc_value)
 }
 
-pub fn from_str(value: &str) -> Result<Vec<SAP_UC>> {
+/// Run an SDK conversion into a fresh buffer, retrying once with the size the SDK asked for
+/// when the initial guess turns out to be too small.
+///
+/// The conversion receives the output buffer, its size in and the result length out.
+pub fn convert<T, F>(initial_size: usize, mut conv: F) -> Result<Vec<T>>
+where
+    T: Copy + Default,
+    F: FnMut(*mut T, &mut u32, &mut u32, *mut RFC_ERROR_INFO) -> RFC_RC,
+{
     let mut err_info = RfcErrorInfo::new();
-    let mut buf = Vec::with_capacity(value.len() + 1);
-    let mut buf_len = buf.capacity() as u32;
+    let mut buf = vec![T::default(); initial_size + 1];
+    let mut buf_len = buf.len() as u32;
     let mut res_len: u32 = 0;
-    unsafe {
-        let rc = RfcUTF8ToSAPUC(
-            value.as_ptr(),
-            value.len() as u32,
-            buf.as_mut_ptr(),
-            &mut buf_len,
-            &mut res_len,
-            err_info.as_mut_ptr(),
+    let rc = conv(
+        buf.as_mut_ptr(),
+        &mut buf_len,
+        &mut res_len,
+        err_info.as_mut_ptr(),
+    );
+    if rc == _RFC_RC::RFC_BUFFER_TOO_SMALL {
+        buf = vec![T::default(); buf_len as usize + 1];
+        buf_len = buf.len() as u32;
+        check_rc_ok!(
+            conv(
+                buf.as_mut_ptr(),
+                &mut buf_len,
+                &mut res_len,
+                err_info.as_mut_ptr(),
+            ),
+            err_info
         );
-        if rc == sapnwrfc_sys::_RFC_RC::RFC_BUFFER_TOO_SMALL {
-            buf.reserve_exact(buf_len as usize + 1);
-            buf_len = buf.capacity() as u32;
-            check_rc_ok!(
-                RfcUTF8ToSAPUC(
-                    value.as_ptr(),
-                    value.len() as u32,
-                    buf.as_mut_ptr(),
-                    &mut buf_len,
-                    &mut res_len,
-                    err_info.as_mut_ptr(),
-                ),
-                err_info
-            );
-        } else if is_rc_err!(rc) {
-            return Err(err_info);
-        }
-        buf.set_len(res_len as usize);
+    } else if is_rc_err!(rc) {
+        return Err(err_info.into());
     }
+    buf.truncate(res_len as usize);
     Ok(buf)
 }
 
+/// A string converted to SAP_UC code units.
+///
+/// The buffer is NUL terminated for the SDK functions taking C strings, the length does not
+/// count the terminator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UcString {
+    buf: Vec<SAP_UC>,
+}
+
+impl UcString {
+    /// Get the number of code units, as expected by the SDK functions taking a length.
+    pub fn len(&self) -> u32 {
+        (self.buf.len() - 1) as u32
+    }
+
+    pub fn as_ptr(&self) -> *const SAP_UC {
+        self.buf.as_ptr()
+    }
+
+    /// Overwrite the code units with zeros, for strings holding secrets.
+    #[cfg(feature = "zeroize")]
+    pub fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(self.buf.as_mut_slice());
+    }
+}
+
+pub fn from_str(value: &str) -> Result<UcString> {
+    if value.is_ascii() {
+        let mut buf: Vec<SAP_UC> = value.bytes().map(SAP_UC::from).collect();
+        buf.push(0);
+        return Ok(UcString { buf });
+    }
+    let mut buf = convert(value.len(), |buf, buf_len, res_len, err_info| unsafe {
+        RfcUTF8ToSAPUC(
+            value.as_ptr(),
+            value.len() as u32,
+            buf,
+            buf_len,
+            res_len,
+            err_info,
+        )
+    })?;
+    buf.push(0);
+    Ok(UcString { buf })
+}
+
 pub fn to_string_truncate(value: &[SAP_UC]) -> Result<String> {
-    let uc_len = value
-        .iter()
-        .position(|&c| c == 0)
-        .unwrap_or_else(|| value.len());
+    let uc_len = value.iter().position(|&c| c == 0).unwrap_or(value.len());
     to_string(value, uc_len as u32)
 }
 
+/// Convert a NUL terminated SAP_UC string, as returned by some SDK functions.
+///
+/// # Safety
+///
+/// The pointer must be valid and point to a NUL terminated string.
+pub unsafe fn to_string_from_ptr(value: *const SAP_UC) -> Result<String> {
+    let mut len = 0;
+    while *value.add(len) != 0 {
+        len += 1;
+    }
+    to_string(std::slice::from_raw_parts(value, len), len as u32)
+}
+
+/// Convert the first `size` code units of a buffer, which must hold at least as many.
 pub fn to_string(value: &[SAP_UC], size: u32) -> Result<String> {
-    let mut err_info = RfcErrorInfo::new();
-    let mut buf = Vec::with_capacity(size as usize + 1);
-    let mut buf_len = buf.capacity() as u32;
-    let mut res_len: u32 = 0;
-    unsafe {
-        let rc = RfcSAPUCToUTF8(
-            value.as_ptr(),
-            size,
-            buf.as_mut_ptr(),
-            &mut buf_len,
-            &mut res_len,
-            err_info.as_mut_ptr(),
-        );
-        if rc == sapnwrfc_sys::_RFC_RC::RFC_BUFFER_TOO_SMALL {
-            buf.reserve_exact(buf_len as usize + 1);
-            buf_len = buf.capacity() as u32;
-            check_rc_ok!(
-                RfcSAPUCToUTF8(
-                    value.as_ptr(),
-                    size,
-                    buf.as_mut_ptr(),
-                    &mut buf_len,
-                    &mut res_len,
-                    err_info.as_mut_ptr(),
-                ),
-                err_info
-            );
-        } else if is_rc_err!(rc) {
-            return Err(err_info);
-        }
-        buf.set_len(res_len as usize);
+    let units = value.get(..size as usize).ok_or_else(|| {
+        RfcError::custom_with_code(
+            _RFC_RC::RFC_INVALID_PARAMETER,
+            &format!(
+                "Cannot convert {} code units from a buffer of {}",
+                size,
+                value.len()
+            ),
+        )
+    })?;
+    if units.iter().all(|&c| c < 0x80) {
+        return Ok(units.iter().map(|&c| c as u8 as char).collect());
     }
+    let buf = convert(size as usize, |buf, buf_len, res_len, err_info| unsafe {
+        RfcSAPUCToUTF8(units.as_ptr(), size, buf, buf_len, res_len, err_info)
+    })?;
     Ok(String::from_utf8(buf)?)
 }
 
@@ -113,12 +201,96 @@ pub fn to_string(value: &[SAP_UC], size: u32) -> Result<String> {
 mod tests {
     use super::*;
 
+    fn units(value: &UcString) -> &[SAP_UC] {
+        &value.buf[..value.len() as usize]
+    }
+
     #[test]
     fn sap_uc_roundtrip() {
-        assert_eq!(to_string_truncate(&from_str("").unwrap()).unwrap(), "",);
         assert_eq!(
-            to_string_truncate(&from_str("Test String").unwrap()).unwrap(),
+            to_string_truncate(units(&from_str("").unwrap())).unwrap(),
+            "",
+        );
+        assert_eq!(
+            to_string_truncate(units(&from_str("Test String").unwrap())).unwrap(),
             "Test String",
         );
     }
+
+    #[test]
+    fn sap_uc_lengths() {
+        assert_eq!(from_str("").unwrap().len(), 0);
+        assert_eq!(from_str("Test").unwrap().len(), 4);
+        assert_eq!(to_string(&[], 0).unwrap(), "");
+    }
+
+    #[test]
+    fn sap_uc_non_ascii_lengths() {
+        // The length counts code units, not UTF-8 bytes nor the terminator
+        assert_eq!(from_str("Grüße").unwrap().len(), 5);
+        assert_eq!(from_str("😀").unwrap().len(), 2);
+        let uc_value = from_str("Grüße 😀").unwrap();
+        assert_eq!(
+            unsafe { *uc_value.as_ptr().add(uc_value.len() as usize) },
+            0
+        );
+    }
+
+    #[test]
+    fn sap_uc_ascii_fast_path() {
+        let name = from_str_to_abap_name("RFCINT4").unwrap();
+        let expected: Vec<SAP_UC> = "RFCINT4".bytes().map(SAP_UC::from).collect();
+        assert_eq!(&name[..7], &expected[..]);
+        assert!(name[7..].iter().all(|&c| c == 0));
+        assert_eq!(to_string_truncate(&name).unwrap(), "RFCINT4");
+
+        // The terminator is written right after the units
+        let mut buf = [1 as SAP_UC; 4];
+        assert_eq!(from_str_to_slice("ABC", &mut buf).unwrap(), 3);
+        assert_eq!(buf, [65, 66, 67, 0]);
+    }
+
+    #[test]
+    fn sap_uc_truncate() {
+        let mut buf = [1 as SAP_UC; 5];
+        assert_eq!(from_str_to_slice_truncate("ABC", &mut buf), 3);
+        assert_eq!(&buf[..4], [65, 66, 67, 0]);
+        assert_eq!(from_str_to_slice_truncate("ABCDEF", &mut buf), 4);
+        assert_eq!(buf, [65, 66, 67, 0x2026, 0]);
+        assert_eq!(to_string_truncate(&buf).unwrap(), "ABC…");
+
+        // A surrogate pair is dropped whole
+        assert_eq!(from_str_to_slice_truncate("AB😀C", &mut buf), 3);
+        assert_eq!(&buf[..4], [65, 66, 0x2026, 0]);
+
+        let mut buf = [1 as SAP_UC; 1];
+        assert_eq!(from_str_to_slice_truncate("ABC", &mut buf), 0);
+        assert_eq!(buf, [0]);
+        assert_eq!(from_str_to_slice_truncate("ABC", &mut []), 0);
+    }
+
+    #[test]
+    fn sap_uc_short_buffer() {
+        // Asking for more units than the buffer holds never reads past it
+        assert!(to_string(&[65], 2).is_err());
+        assert!(to_string(&[], 1).is_err());
+        assert_eq!(to_string(&[65, 66, 0], 2).unwrap(), "AB");
+    }
+
+    #[test]
+    fn sap_uc_exact_capacity() {
+        // One code unit encodes to two UTF-8 bytes, exactly the initial guess
+        let uc_value = from_str("é").unwrap();
+        assert_eq!(uc_value.len(), 1);
+        assert_eq!(to_string(units(&uc_value), 1).unwrap(), "é");
+    }
+
+    #[test]
+    fn sap_uc_buffer_too_small_retry() {
+        // Three code units need nine UTF-8 bytes, forcing the retry path
+        let uc_value = from_str("日本語").unwrap();
+        assert_eq!(uc_value.len(), 3);
+        assert_eq!(to_string(units(&uc_value), 3).unwrap(), "日本語");
+        assert_eq!(to_string_truncate(units(&uc_value)).unwrap(), "日本語");
+    }
 }
@@ -0,0 +1,325 @@
+use crate::{
+    error::{Result, RfcError},
+    function::RfcFunction,
+    structure::RfcStructure,
+    table::RfcTable,
+    types::{RfcDirection, RfcType},
+};
+use sapnwrfc_sys::_RFC_RC;
+use std::collections::HashMap;
+
+/// A value of a parameter or field, for calls made with [`RfcConnection::call`].
+///
+/// [`RfcConnection::call`]: crate::RfcConnection::call
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// An integer of any size.
+    Int(i64),
+    Float(f64),
+    /// Text, also used for packed numbers, digits, times and initial dates.
+    Str(String),
+    Bytes(Vec<u8>),
+    #[cfg(feature = "chrono")]
+    Date(chrono::NaiveDate),
+    /// The fields of a structure with their names, in declaration order when read.
+    Structure(Vec<(String, Value)>),
+    /// The rows of a table, each with its fields like a structure.
+    Table(Vec<Vec<(String, Value)>>),
+}
+
+impl Value {
+    /// Get a field of a structure by name.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Self::Structure(fields) => fields
+                .iter()
+                .find(|(field, _)| field.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Describe the kind of value, for error messages.
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "an integer",
+            Self::Float(_) => "a float",
+            Self::Str(_) => "text",
+            Self::Bytes(_) => "binary data",
+            #[cfg(feature = "chrono")]
+            Self::Date(_) => "a date",
+            Self::Structure(_) => "a structure",
+            Self::Table(_) => "a table",
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+/// Fill the import parameters of a function from values by name.
+pub(crate) fn set_imports(func: &mut RfcFunction, imports: &HashMap<String, Value>) -> Result<()> {
+    let params = func
+        .parameters()?
+        .map(|desc| desc.map(|desc| (desc.name().to_owned(), desc)))
+        .collect::<Result<HashMap<_, _>>>()?;
+    for (name, value) in imports {
+        let desc = params
+            .get(&name.to_uppercase())
+            .ok_or_else(|| invalid_parameter(&format!("Function has no parameter {}", name)))?;
+        if desc.direction() == RfcDirection::Export {
+            return Err(invalid_parameter(&format!(
+                "Parameter {} is an export, it cannot be set",
+                desc.name()
+            )));
+        }
+        set_value(func, desc.name(), desc.rfc_type(), value, desc.name())?;
+    }
+    Ok(())
+}
+
+/// Read the export, changing and table parameters of a function into values by name.
+pub(crate) fn get_exports(func: &RfcFunction) -> Result<HashMap<String, Value>> {
+    let mut exports = HashMap::new();
+    for desc in func.parameters()? {
+        let desc = desc?;
+        if desc.direction() == RfcDirection::Import {
+            continue;
+        }
+        let value = get_value(func, desc.name(), desc.rfc_type())?;
+        exports.insert(desc.name().to_owned(), value);
+    }
+    Ok(exports)
+}
+
+/// The accessors shared by functions and structures, to fill and read both the same way.
+trait Container {
+    fn set_i64(&mut self, name: &str, value: i64) -> Result<()>;
+    fn set_float(&mut self, name: &str, value: f64) -> Result<()>;
+    fn set_text(&mut self, name: &str, value: &str) -> Result<()>;
+    fn set_bytes(&mut self, name: &str, value: &[u8]) -> Result<()>;
+    fn get_i64(&self, name: &str) -> Result<i64>;
+    fn get_float(&self, name: &str) -> Result<f64>;
+    fn get_text(&self, name: &str) -> Result<String>;
+    fn get_bytes(&self, name: &str) -> Result<Vec<u8>>;
+    #[cfg(feature = "chrono")]
+    fn get_date(&self, name: &str) -> Result<Option<chrono::NaiveDate>>;
+    fn structure(&self, name: &str) -> Result<RfcStructure<'_>>;
+    fn table(&self, name: &str) -> Result<RfcTable<'_>>;
+}
+
+macro_rules! impl_container {
+    ($($ty:ty),*) => {
+        $(
+            impl Container for $ty {
+                fn set_i64(&mut self, name: &str, value: i64) -> Result<()> {
+                    <$ty>::set_i64(self, name, value)
+                }
+
+                fn set_float(&mut self, name: &str, value: f64) -> Result<()> {
+                    <$ty>::set_float(self, name, value)
+                }
+
+                fn set_text(&mut self, name: &str, value: &str) -> Result<()> {
+                    <$ty>::set_from_str(self, name, value)
+                }
+
+                fn set_bytes(&mut self, name: &str, value: &[u8]) -> Result<()> {
+                    <$ty>::set_bytes(self, name, value)
+                }
+
+                fn get_i64(&self, name: &str) -> Result<i64> {
+                    <$ty>::get_i64(self, name)
+                }
+
+                fn get_float(&self, name: &str) -> Result<f64> {
+                    <$ty>::get_float(self, name)
+                }
+
+                fn get_text(&self, name: &str) -> Result<String> {
+                    <$ty>::get_string(self, name)
+                }
+
+                fn get_bytes(&self, name: &str) -> Result<Vec<u8>> {
+                    <$ty>::get_bytes(self, name)
+                }
+
+                #[cfg(feature = "chrono")]
+                fn get_date(&self, name: &str) -> Result<Option<chrono::NaiveDate>> {
+                    <$ty>::get_date(self, name)
+                }
+
+                fn structure(&self, name: &str) -> Result<RfcStructure<'_>> {
+                    <$ty>::get_structure(self, name)
+                }
+
+                fn table(&self, name: &str) -> Result<RfcTable<'_>> {
+                    <$ty>::get_table(self, name)
+                }
+            }
+        )*
+    };
+}
+
+impl_container!(RfcFunction<'_>, RfcStructure<'_>);
+
+/// Set a value according to the type of the parameter or field, the path names it in errors.
+fn set_value<C: Container + ?Sized>(
+    target: &mut C,
+    name: &str,
+    rfc_type: RfcType,
+    value: &Value,
+    path: &str,
+) -> Result<()> {
+    match (rfc_type, value) {
+        (RfcType::Int | RfcType::Int1 | RfcType::Int2 | RfcType::Int8, Value::Int(value)) => {
+            target.set_i64(name, *value)
+        }
+        (RfcType::Float, Value::Int(value)) => target.set_float(name, *value as f64),
+        (RfcType::Float, Value::Float(value)) => target.set_float(name, *value),
+        (RfcType::Bcd | RfcType::DecF16 | RfcType::DecF34, Value::Int(value)) => {
+            target.set_text(name, &value.to_string())
+        }
+        (RfcType::Bcd | RfcType::DecF16 | RfcType::DecF34, Value::Float(value)) => {
+            target.set_text(name, &value.to_string())
+        }
+        (RfcType::Num, Value::Int(value)) if *value >= 0 => {
+            target.set_text(name, &value.to_string())
+        }
+        (
+            RfcType::Char
+            | RfcType::String
+            | RfcType::Num
+            | RfcType::Date
+            | RfcType::Time
+            | RfcType::UtcLong
+            | RfcType::Bcd
+            | RfcType::DecF16
+            | RfcType::DecF34,
+            Value::Str(value),
+        ) => target.set_text(name, value),
+        #[cfg(feature = "chrono")]
+        (RfcType::Date, Value::Date(value)) => {
+            target.set_text(name, &value.format("%Y%m%d").to_string())
+        }
+        (RfcType::Byte | RfcType::XString, Value::Bytes(value)) => target.set_bytes(name, value),
+        (RfcType::Structure, Value::Structure(fields)) => {
+            set_fields(&mut target.structure(name)?, fields, path)
+        }
+        (RfcType::Table, Value::Table(rows)) => set_rows(&mut target.table(name)?, rows, path),
+        (rfc_type, value) => Err(RfcError::custom_with_code(
+            _RFC_RC::RFC_CONVERSION_FAILURE,
+            &format!("{} of type {} cannot take {}", path, rfc_type, value.kind()),
+        )),
+    }
+}
+
+fn set_fields(structure: &mut RfcStructure, fields: &[(String, Value)], path: &str) -> Result<()> {
+    for (name, value) in fields {
+        let desc = structure
+            .field_desc_by_name(name)
+            .map_err(|_| no_field(path, &structure.name(), name))?;
+        let path = format!("{}.{}", path, desc.name());
+        set_value(structure, desc.name(), desc.rfc_type(), value, &path)?;
+    }
+    Ok(())
+}
+
+/// Replace the rows of a table, once every field of the rows is known to its line type.
+fn set_rows(table: &mut RfcTable, rows: &[Vec<(String, Value)>], path: &str) -> Result<()> {
+    let layout = table.field_layout()?;
+    for (index, row) in rows.iter().enumerate() {
+        if let Some((name, _)) = row.iter().find(|(name, _)| {
+            !layout
+                .iter()
+                .any(|(field, _)| field.eq_ignore_ascii_case(name))
+        }) {
+            let path = format!("{}[{}]", path, index);
+            return Err(no_field(&path, &table.name(), name));
+        }
+    }
+    table.clear_rows()?;
+    for (index, row) in rows.iter().enumerate() {
+        let path = format!("{}[{}]", path, index);
+        set_fields(&mut table.append_row()?, row, &path)?;
+    }
+    Ok(())
+}
+
+/// Read a value according to the type of the parameter or field.
+fn get_value<C: Container + ?Sized>(source: &C, name: &str, rfc_type: RfcType) -> Result<Value> {
+    Ok(match rfc_type {
+        RfcType::Int | RfcType::Int1 | RfcType::Int2 | RfcType::Int8 => {
+            Value::Int(source.get_i64(name)?)
+        }
+        RfcType::Float => Value::Float(source.get_float(name)?),
+        RfcType::Byte | RfcType::XString => Value::Bytes(source.get_bytes(name)?),
+        // Initial dates are not valid dates, they are kept as text
+        #[cfg(feature = "chrono")]
+        RfcType::Date => match source.get_date(name)? {
+            Some(date) => Value::Date(date),
+            None => Value::Str(source.get_text(name)?),
+        },
+        RfcType::Structure => Value::Structure(get_fields(&source.structure(name)?)?),
+        RfcType::Table => {
+            let table = source.table(name)?;
+            let rows = (0..table.row_count()?)
+                .map(|index| get_fields(&table.get_row(index)?))
+                .collect::<Result<_>>()?;
+            Value::Table(rows)
+        }
+        _ => Value::Str(source.get_text(name)?),
+    })
+}
+
+/// Copy every field of a structure into another one with the same fields, keeping their types.
+pub(crate) fn copy_fields(source: &RfcStructure, target: &mut RfcStructure) -> Result<()> {
+    for desc in source.fields()? {
+        let desc = desc?;
+        let value = get_value(source, desc.name(), desc.rfc_type())?;
+        set_value(target, desc.name(), desc.rfc_type(), &value, desc.name())?;
+    }
+    Ok(())
+}
+
+fn get_fields(structure: &RfcStructure) -> Result<Vec<(String, Value)>> {
+    let mut fields = Vec::new();
+    for desc in structure.fields()? {
+        let desc = desc?;
+        let value = get_value(structure, desc.name(), desc.rfc_type())?;
+        fields.push((desc.name().to_owned(), value));
+    }
+    Ok(fields)
+}
+
+fn invalid_parameter(message: &str) -> RfcError {
+    RfcError::custom_with_code(_RFC_RC::RFC_INVALID_PARAMETER, message)
+}
+
+fn no_field(path: &str, type_name: &str, name: &str) -> RfcError {
+    invalid_parameter(&format!(
+        "{} of type {} has no field {}",
+        path, type_name, name
+    ))
+}